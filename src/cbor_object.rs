@@ -1,3 +1,6 @@
+use bstr::ByteSlice as _;
+
+use crate::context::Context;
 use crate::tree::Node;
 use crate::type_array::Array;
 use crate::type_byte_string::{ByteString, ByteStringWithEmbedded, IndefiniteByteString};
@@ -5,14 +8,18 @@ use crate::type_map::Map;
 use crate::type_negative::NegativeInteger;
 use crate::type_simple_or_float::{
     Break, DoublePrecisionFloat, HalfPrecisionFloat, ReservedSimpleOrFloat, SimpleValue,
-    SinglePrecisionFloat,
+    SinglePrecisionFloat, SIMPLE_VALUE_NULL,
 };
 use crate::type_tag::Tag;
 use crate::type_text_string::{IndefiniteTextString, TextString};
 use crate::type_unsigned::UnsignedInteger;
 
-pub trait ToTree {
-    fn into_tree(self) -> Node;
+pub trait ToTree: Sized {
+    fn into_tree(self) -> Node {
+        self.into_tree_with_context(&mut Context::default())
+    }
+
+    fn into_tree_with_context(self, context: &mut Context) -> Node;
 }
 
 pub enum CborObject {
@@ -38,27 +45,137 @@ impl CborObject {
     pub fn is_break(&self) -> bool {
         matches!(self, CborObject::Break(_))
     }
+
+    /// Returns the bytes the item was parsed from, with its heads as they were encoded.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.encode_into(&mut output);
+        output
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        match self {
+            CborObject::UnsignedInteger(x) => x.encode_into(output),
+            CborObject::NegativeInteger(x) => x.encode_into(output),
+            CborObject::ByteString(x) => x.encode_into(output),
+            CborObject::IndefiniteByteString(x) => x.encode_into(output),
+            CborObject::ByteStringWithEmbedded(x) => x.encode_into(output),
+            CborObject::TextString(x) => x.encode_into(output),
+            CborObject::IndefiniteTextString(x) => x.encode_into(output),
+            CborObject::Array(x) => x.encode_into(output),
+            CborObject::Map(x) => x.encode_into(output),
+            CborObject::Tag(x) => x.encode_into(output),
+            CborObject::SimpleValue(x) => x.encode_into(output),
+            CborObject::HalfPrecisionFloat(x) => x.encode_into(output),
+            CborObject::SinglePrecisionFloat(x) => x.encode_into(output),
+            CborObject::DoublePrecisionFloat(x) => x.encode_into(output),
+            CborObject::ReservedSimpleOrFloat(x) => x.encode_into(output),
+            CborObject::Break(x) => x.encode_into(output),
+        }
+    }
+
+    pub fn as_unsigned(&self) -> Option<u64> {
+        match self {
+            CborObject::UnsignedInteger(x) => x.value(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an unsigned or negative integer.
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            CborObject::UnsignedInteger(x) => Some(i128::from(x.value()?)),
+            CborObject::NegativeInteger(x) => Some(-1 - i128::from(x.value()?)),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, CborObject::SimpleValue(x) if x.value() == SIMPLE_VALUE_NULL)
+    }
+
+    /// Returns the content of a byte string, joining the chunks of an indefinite one.
+    pub fn as_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            CborObject::ByteString(x) => Some(x.value().to_vec()),
+            CborObject::ByteStringWithEmbedded(x) => Some(x.raw_value().to_vec()),
+            CborObject::IndefiniteByteString(x) => x
+                .chunks()
+                .iter()
+                .filter(|chunk| !chunk.is_break())
+                .try_fold(Vec::new(), |mut acc, chunk| {
+                    acc.extend(chunk.as_bytes()?);
+                    Some(acc)
+                }),
+            _ => None,
+        }
+    }
+
+    /// Returns the content of a text string, joining the chunks of an indefinite one.
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            CborObject::TextString(x) => Some(x.value().to_str_lossy().into_owned()),
+            CborObject::IndefiniteTextString(x) => x
+                .chunks()
+                .iter()
+                .filter(|chunk| !chunk.is_break())
+                .map(|chunk| chunk.as_text())
+                .collect(),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of an array, without the break of an indefinite one.
+    pub fn as_array(&self) -> Option<Vec<&CborObject>> {
+        match self {
+            CborObject::Array(x) => Some(x.value().iter().filter(|x| !x.is_break()).collect()),
+            _ => None,
+        }
+    }
+
+    pub fn as_tag(&self) -> Option<(u64, &CborObject)> {
+        match self {
+            CborObject::Tag(x) => Some((x.tag()?, x.payload())),
+            _ => None,
+        }
+    }
+
+    /// Returns the key/value pairs of a map, without the break of an indefinite one.
+    pub fn as_map(&self) -> Option<Vec<(&CborObject, &CborObject)>> {
+        match self {
+            CborObject::Map(x) => {
+                let items: Vec<_> = x.value().iter().filter(|x| !x.is_break()).collect();
+                Some(
+                    items
+                        .chunks_exact(2)
+                        .map(|pair| (pair[0], pair[1]))
+                        .collect(),
+                )
+            }
+            _ => None,
+        }
+    }
 }
 
 impl ToTree for CborObject {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, context: &mut Context) -> Node {
         match self {
-            CborObject::UnsignedInteger(x) => x.into_tree(),
-            CborObject::NegativeInteger(x) => x.into_tree(),
-            CborObject::ByteString(x) => x.into_tree(),
-            CborObject::IndefiniteByteString(x) => x.into_tree(),
-            CborObject::ByteStringWithEmbedded(x) => x.into_tree(),
-            CborObject::TextString(x) => x.into_tree(),
-            CborObject::IndefiniteTextString(x) => x.into_tree(),
-            CborObject::Array(x) => x.into_tree(),
-            CborObject::Map(x) => x.into_tree(),
-            CborObject::Tag(x) => x.into_tree(),
-            CborObject::SimpleValue(x) => x.into_tree(),
-            CborObject::HalfPrecisionFloat(x) => x.into_tree(),
-            CborObject::SinglePrecisionFloat(x) => x.into_tree(),
-            CborObject::DoublePrecisionFloat(x) => x.into_tree(),
-            CborObject::ReservedSimpleOrFloat(x) => x.into_tree(),
-            CborObject::Break(x) => x.into_tree(),
+            CborObject::UnsignedInteger(x) => x.into_tree_with_context(context),
+            CborObject::NegativeInteger(x) => x.into_tree_with_context(context),
+            CborObject::ByteString(x) => x.into_tree_with_context(context),
+            CborObject::IndefiniteByteString(x) => x.into_tree_with_context(context),
+            CborObject::ByteStringWithEmbedded(x) => x.into_tree_with_context(context),
+            CborObject::TextString(x) => x.into_tree_with_context(context),
+            CborObject::IndefiniteTextString(x) => x.into_tree_with_context(context),
+            CborObject::Array(x) => x.into_tree_with_context(context),
+            CborObject::Map(x) => x.into_tree_with_context(context),
+            CborObject::Tag(x) => x.into_tree_with_context(context),
+            CborObject::SimpleValue(x) => x.into_tree_with_context(context),
+            CborObject::HalfPrecisionFloat(x) => x.into_tree_with_context(context),
+            CborObject::SinglePrecisionFloat(x) => x.into_tree_with_context(context),
+            CborObject::DoublePrecisionFloat(x) => x.into_tree_with_context(context),
+            CborObject::ReservedSimpleOrFloat(x) => x.into_tree_with_context(context),
+            CborObject::Break(x) => x.into_tree_with_context(context),
         }
     }
 }
@@ -158,3 +275,23 @@ impl From<Break> for CborObject {
         CborObject::Break(x)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_hex;
+    use crate::test_helpers::parse_item;
+
+    #[test]
+    fn encode_keeps_the_original_bytes() {
+        for hex in [
+            "1800",
+            "d9 0018 5a 00000001 01",
+            "9f 5f 41 01 ff 7f 61 61 ff a1 f9 3c00 fa 3fc00000 ff",
+            "c2 58 01 01",
+            "83 f8 20 fc fb 3ff0000000000000",
+            "d8 18 42 1817",
+        ] {
+            assert_eq!(parse_item(hex).encode(), parse_hex(hex).unwrap(), "{}", hex);
+        }
+    }
+}
@@ -17,14 +17,14 @@ use crate::type_tag::Tag;
 use crate::type_text_string::{IndefiniteTextString, TextString};
 use crate::type_unsigned::UnsignedInteger;
 
-const MAJOR_UNSIGNED_INTEGER: u8 = 0;
-const MAJOR_NEGATIVE_INTEGER: u8 = 1;
-const MAJOR_BYTE_STRING: u8 = 2;
-const MAJOR_TEXT_STRING: u8 = 3;
-const MAJOR_ARRAY: u8 = 4;
-const MAJOR_MAP: u8 = 5;
-const MAJOR_TAG: u8 = 6;
-const MAJOR_SIMPLE_OR_FLOAT: u8 = 7;
+pub const MAJOR_UNSIGNED_INTEGER: u8 = 0;
+pub const MAJOR_NEGATIVE_INTEGER: u8 = 1;
+pub const MAJOR_BYTE_STRING: u8 = 2;
+pub const MAJOR_TEXT_STRING: u8 = 3;
+pub const MAJOR_ARRAY: u8 = 4;
+pub const MAJOR_MAP: u8 = 5;
+pub const MAJOR_TAG: u8 = 6;
+pub const MAJOR_SIMPLE_OR_FLOAT: u8 = 7;
 
 const ADDITIONAL_INFO_VALUE_FOLLOWED_BY_1_BYTE: u8 = 24;
 const ADDITIONAL_INFO_VALUE_FOLLOWED_BY_2_BYTES: u8 = 25;
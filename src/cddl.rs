@@ -0,0 +1,1201 @@
+//! Concise Data Definition Language (RFC 8610): the rules of a CDDL file, parsed along with
+//! the standard prelude of Appendix D.
+//!
+//! Generic rules and operators other than the controls of Section 3.8 and RFC 9165 are not
+//! supported.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine as _;
+
+/// The rules that every CDDL file can use (RFC 8610 Appendix D).
+const PRELUDE: &str = "
+any = #
+uint = #0
+nint = #1
+int = uint / nint
+bstr = #2
+bytes = bstr
+tstr = #3
+text = tstr
+tdate = #6.0(tstr)
+time = #6.1(number)
+number = int / float
+biguint = #6.2(bstr)
+bignint = #6.3(bstr)
+bigint = biguint / bignint
+integer = int / bigint
+unsigned = uint / biguint
+decfrac = #6.4([e10: int, m: integer])
+bigfloat = #6.5([e2: int, m: integer])
+eb64url = #6.21(any)
+eb64legacy = #6.22(any)
+eb16 = #6.23(any)
+encoded-cbor = #6.24(bstr)
+uri = #6.32(tstr)
+b64url = #6.33(tstr)
+b64legacy = #6.34(tstr)
+regexp = #6.35(tstr)
+mime-message = #6.36(tstr)
+cbor-any = #6.55799(any)
+float16 = #7.25
+float32 = #7.26
+float64 = #7.27
+float16-32 = float16 / float32
+float32-64 = float32 / float64
+float = float16-32 / float64
+false = #7.20
+true = #7.21
+bool = false / true
+nil = #7.22
+null = nil
+undefined = #7.23
+";
+
+/// Controls that can follow a type (RFC 8610 Section 3.8 and RFC 9165).
+const CONTROLS: &[&str] = &[
+    "size", "bits", "regexp", "pcre", "cbor", "cborseq", "within", "and", "lt", "le", "gt", "ge",
+    "eq", "ne", "default", "plus", "cat", "det",
+];
+
+const PUNCTUATION: &[&str] = &[
+    "//=", "/=", "//", "...", "..", "=>", "=", "/", "(", ")", "{", "}", "[", "]", "<", ">", ",",
+    ":", "?", "*", "+", "~", "&", "^", "#",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Int(i128),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Occurrence {
+    pub min: u64,
+    /// `None` for no upper bound.
+    pub max: Option<u64>,
+}
+
+impl Occurrence {
+    pub const ONCE: Occurrence = Occurrence {
+        min: 1,
+        max: Some(1),
+    };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemberKey {
+    /// `name:`, which stands for the text string `"name"`.
+    Bareword(String),
+    /// `1:` or `"name":`.
+    Value(Literal),
+    /// `type =>`, or `type ^ =>` with a cut.
+    Type(Box<Type1>, bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryKind {
+    Type(Type),
+    /// A parenthesized group, which is spliced into the enclosing one.
+    Group(Group),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub occurrence: Occurrence,
+    pub key: Option<MemberKey>,
+    pub kind: EntryKind,
+}
+
+/// Choices of sequences of entries, separated by `//`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group(pub Vec<Vec<Entry>>);
+
+/// Choices of types, separated by `/`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Type(pub Vec<Type1>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operator {
+    Range { inclusive: bool },
+    Control(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Type1 {
+    pub base: Type2,
+    pub operator: Option<(Operator, Type2)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type2 {
+    Value(Literal),
+    Name(String),
+    Parenthesized(Type),
+    Map(Group),
+    Array(Group),
+    /// `~name`: the group of a map or array, or the content of a tag.
+    Unwrap(String),
+    /// `&group`: the values of the entries of a group.
+    Choice(Group),
+    /// `#6.n(type)`, or `#6(type)` for any tag.
+    Tag(Option<u64>, Box<Type>),
+    /// `#m` or `#m.n`.
+    Major(u8, Option<u64>),
+    /// `#`
+    Any,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Definition {
+    Type(Type),
+    Group(Group),
+}
+
+/// The rules of a CDDL file and of the prelude.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cddl {
+    rules: BTreeMap<String, Definition>,
+    /// Names of the rules of the prelude.
+    prelude: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Value(Literal),
+    Punct(&'static str),
+    Control(String),
+    Major(u8, Option<u64>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Lexed {
+    token: Token,
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+fn is_name_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || b"@_$".contains(&c)
+}
+
+fn is_name_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b"@_$.-".contains(&c)
+}
+
+pub fn decode_hex(text: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = text
+        .lines()
+        .map(|line| line.split(';').next().unwrap_or_default())
+        .flat_map(|line| line.bytes())
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err("odd number of hex digits".to_string());
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| "invalid hex digit".to_string())
+        })
+        .collect()
+}
+
+pub fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    URL_SAFE_NO_PAD
+        .decode(text.trim_end_matches('='))
+        .or_else(|_| STANDARD.decode(&text))
+        .map_err(|err| err.to_string())
+}
+
+pub fn unescape(text: &str) -> Result<String, String> {
+    let mut output = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('r') => output.push('\r'),
+            Some('t') => output.push('\t'),
+            Some('b') => output.push('\u{8}'),
+            Some('f') => output.push('\u{c}'),
+            Some('u') => {
+                let mut code: String = chars.by_ref().take(4).collect();
+                let mut value = u32::from_str_radix(&code, 16).ok();
+                // A high surrogate is followed by the escape of the low one.
+                if let Some(high @ 0xd800..0xdc00) = value {
+                    let low: String = chars.by_ref().take(6).collect();
+                    value = low
+                        .strip_prefix("\\u")
+                        .and_then(|low| u32::from_str_radix(low, 16).ok())
+                        .filter(|low| (0xdc00..0xe000).contains(low))
+                        .map(|low| 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00));
+                    code.push_str(&low);
+                }
+                let c = value
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid escape \\u{}", code))?;
+                output.push(c);
+            }
+            Some(c) => output.push(c),
+            None => return Err("unterminated escape".to_string()),
+        }
+    }
+    Ok(output)
+}
+
+fn lex(text: &str) -> Result<Vec<Lexed>, String> {
+    let bytes = text.as_bytes();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        let error = |message: String| format!("line {}: {}", line, message);
+        if c == b'\n' {
+            line += 1;
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == b';' {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            continue;
+        }
+        // Byte strings: h'...', b64'...' and '...'.
+        let prefix = [("h'", 2), ("b64'", 4), ("'", 1)]
+            .into_iter()
+            .find(|(prefix, _)| text[i..].starts_with(prefix));
+        if let Some((prefix, length)) = prefix {
+            let content_start = i + length;
+            let Some(content_length) = text[content_start..].find('\'') else {
+                return Err(error("unterminated byte string".to_string()));
+            };
+            let content = &text[content_start..content_start + content_length];
+            let value = match prefix {
+                "h'" => decode_hex(content),
+                "b64'" => decode_base64(content),
+                _ => unescape(content).map(String::into_bytes),
+            }
+            .map_err(&error)?;
+            let lines = content.matches('\n').count();
+            i = content_start + content_length + 1;
+            tokens.push(Lexed {
+                token: Token::Value(Literal::Bytes(value)),
+                line,
+                start,
+                end: i,
+            });
+            line += lines;
+            continue;
+        }
+        let token = if c == b'"' {
+            i += 1;
+            while i < bytes.len() && bytes[i] != b'"' {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            if i >= bytes.len() {
+                return Err(error("unterminated text string".to_string()));
+            }
+            i += 1;
+            Token::Value(Literal::Text(
+                unescape(&text[start + 1..i - 1]).map_err(&error)?,
+            ))
+        } else if is_name_start(c) {
+            while i < bytes.len() && is_name_char(bytes[i]) {
+                i += 1;
+            }
+            // A name does not end with `-` or `.`.
+            while matches!(bytes[i - 1], b'-' | b'.') {
+                i -= 1;
+            }
+            Token::Name(text[start..i].to_string())
+        } else if c.is_ascii_digit()
+            || (c == b'-' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit))
+        {
+            let negative = c == b'-';
+            if negative {
+                i += 1;
+            }
+            let radix = match &text[i..] {
+                rest if rest.starts_with("0x") => 16,
+                rest if rest.starts_with("0b") => 2,
+                _ => 10,
+            };
+            if radix != 10 {
+                i += 2;
+            }
+            let digits_start = i;
+            while i < bytes.len() && bytes[i].is_ascii_alphanumeric() && radix != 10
+                || i < bytes.len() && bytes[i].is_ascii_digit()
+            {
+                i += 1;
+            }
+            let mut float = false;
+            if radix == 10 {
+                if bytes.get(i) == Some(&b'.') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+                    float = true;
+                    i += 1;
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                if matches!(bytes.get(i), Some(b'e' | b'E')) {
+                    float = true;
+                    i += 1;
+                    if matches!(bytes.get(i), Some(b'+' | b'-')) {
+                        i += 1;
+                    }
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let number = &text[digits_start..i];
+            let literal = if float {
+                number
+                    .parse::<f64>()
+                    .map(|value| Literal::Float(if negative { -value } else { value }))
+                    .ok()
+            } else {
+                i128::from_str_radix(number, radix)
+                    .map(|value| Literal::Int(if negative { -value } else { value }))
+                    .ok()
+            };
+            Token::Value(
+                literal.ok_or_else(|| error(format!("invalid number {}", &text[start..i])))?,
+            )
+        } else if c == b'#' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+            let major_type = bytes[i + 1] - b'0';
+            i += 2;
+            let mut argument = None;
+            if bytes.get(i) == Some(&b'.') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+                i += 1;
+                let argument_start = i;
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+                argument = Some(
+                    text[argument_start..i]
+                        .parse()
+                        .map_err(|_| error(format!("invalid argument in {}", &text[start..i])))?,
+                );
+            }
+            if major_type > 7 {
+                return Err(error(format!("invalid major type {}", major_type)));
+            }
+            Token::Major(major_type, argument)
+        } else if c == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_alphabetic) {
+            i += 1;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'-') {
+                i += 1;
+            }
+            let name = &text[start + 1..i];
+            if !CONTROLS.contains(&name) {
+                return Err(error(format!("unknown control .{}", name)));
+            }
+            Token::Control(name.to_string())
+        } else if let Some(punct) = PUNCTUATION.iter().find(|p| text[i..].starts_with(*p)) {
+            i += punct.len();
+            Token::Punct(punct)
+        } else {
+            let c = text[i..].chars().next().unwrap_or_default();
+            return Err(error(format!("unexpected character {:?}", c)));
+        };
+        tokens.push(Lexed {
+            token,
+            line,
+            start,
+            end: i,
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Lexed>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.position + n).map(|lexed| &lexed.token)
+    }
+
+    fn is(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let found = self.is(punct);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn error(&self, message: &str) -> String {
+        match self.tokens.get(self.position) {
+            Some(lexed) => format!("line {}: {}", lexed.line, message),
+            None => format!("end of file: {}", message),
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), String> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", punct)))
+        }
+    }
+
+    /// Whether the next tokens are in contact, as `1*3` is.
+    fn adjacent(&self) -> bool {
+        match (
+            self.tokens.get(self.position.wrapping_sub(1)),
+            self.tokens.get(self.position),
+        ) {
+            (Some(previous), Some(next)) => previous.end == next.start,
+            _ => false,
+        }
+    }
+
+    fn at_rule_start(&self) -> bool {
+        matches!(self.peek(), Some(Token::Name(_)))
+            && matches!(self.peek_at(1), Some(Token::Punct("=" | "/=" | "//=")))
+    }
+
+    fn parse_rules(&mut self) -> Result<Vec<(String, &'static str, Group)>, String> {
+        let mut rules = vec![];
+        while let Some(token) = self.peek() {
+            let Token::Name(name) = token.clone() else {
+                return Err(self.error("expected a rule name"));
+            };
+            self.position += 1;
+            if self.is("<") {
+                return Err(self.error("generic rules are not supported"));
+            }
+            let assign = match self.peek() {
+                Some(Token::Punct(assign @ ("=" | "/=" | "//="))) => *assign,
+                _ => return Err(self.error("expected =, /= or //=")),
+            };
+            self.position += 1;
+            rules.push((name, assign, self.parse_group()?));
+        }
+        Ok(rules)
+    }
+
+    fn parse_group(&mut self) -> Result<Group, String> {
+        let mut choices = vec![self.parse_group_choice()?];
+        while self.eat("//") {
+            choices.push(self.parse_group_choice()?);
+        }
+        Ok(Group(choices))
+    }
+
+    fn parse_group_choice(&mut self) -> Result<Vec<Entry>, String> {
+        let mut entries = vec![];
+        while self.peek().is_some()
+            && !self.at_rule_start()
+            && !matches!(self.peek(), Some(Token::Punct(")" | "}" | "]" | "//")))
+        {
+            entries.push(self.parse_entry()?);
+            self.eat(",");
+        }
+        Ok(entries)
+    }
+
+    fn parse_occurrence(&mut self) -> Occurrence {
+        if self.eat("?") {
+            return Occurrence {
+                min: 0,
+                max: Some(1),
+            };
+        }
+        if self.eat("+") {
+            return Occurrence { min: 1, max: None };
+        }
+        let min = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Value(Literal::Int(min))), Some(Token::Punct("*"))) if *min >= 0 => {
+                let min = *min as u64;
+                self.position += 1;
+                Some(min)
+            }
+            _ => None,
+        };
+        if !self.eat("*") {
+            return Occurrence::ONCE;
+        }
+        let max = match self.peek() {
+            Some(Token::Value(Literal::Int(max))) if *max >= 0 && self.adjacent() => {
+                let max = *max as u64;
+                self.position += 1;
+                Some(max)
+            }
+            _ => None,
+        };
+        Occurrence {
+            min: min.unwrap_or(0),
+            max,
+        }
+    }
+
+    fn parse_entry(&mut self) -> Result<Entry, String> {
+        let occurrence = self.parse_occurrence();
+        if self.eat("(") {
+            let group = self.parse_group()?;
+            self.expect(")")?;
+            return Ok(Entry {
+                occurrence,
+                key: None,
+                kind: EntryKind::Group(group),
+            });
+        }
+        let key = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Name(name)), Some(Token::Punct(":"))) => {
+                Some(MemberKey::Bareword(name.clone()))
+            }
+            (Some(Token::Value(value)), Some(Token::Punct(":"))) => {
+                Some(MemberKey::Value(value.clone()))
+            }
+            _ => None,
+        };
+        if key.is_some() {
+            self.position += 2;
+            return Ok(Entry {
+                occurrence,
+                key,
+                kind: EntryKind::Type(self.parse_type()?),
+            });
+        }
+        let first = self.parse_type1()?;
+        let cut = self.eat("^");
+        if cut || self.is("=>") {
+            self.expect("=>")?;
+            return Ok(Entry {
+                occurrence,
+                key: Some(MemberKey::Type(Box::new(first), cut)),
+                kind: EntryKind::Type(self.parse_type()?),
+            });
+        }
+        let mut choices = vec![first];
+        while self.eat("/") {
+            choices.push(self.parse_type1()?);
+        }
+        Ok(Entry {
+            occurrence,
+            key: None,
+            kind: EntryKind::Type(Type(choices)),
+        })
+    }
+
+    fn parse_type(&mut self) -> Result<Type, String> {
+        let mut choices = vec![self.parse_type1()?];
+        while self.eat("/") {
+            choices.push(self.parse_type1()?);
+        }
+        Ok(Type(choices))
+    }
+
+    fn parse_type1(&mut self) -> Result<Type1, String> {
+        let base = self.parse_type2()?;
+        let operator = match self.peek() {
+            Some(Token::Punct(range @ (".." | "..."))) => Operator::Range {
+                inclusive: *range == "..",
+            },
+            Some(Token::Control(name)) => Operator::Control(name.clone()),
+            _ => {
+                return Ok(Type1 {
+                    base,
+                    operator: None,
+                })
+            }
+        };
+        self.position += 1;
+        Ok(Type1 {
+            base,
+            operator: Some((operator, self.parse_type2()?)),
+        })
+    }
+
+    fn parse_type2(&mut self) -> Result<Type2, String> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.error("expected a type"));
+        };
+        self.position += 1;
+        Ok(match token {
+            Token::Value(value) => Type2::Value(value),
+            Token::Name(name) => {
+                if self.is("<") {
+                    return Err(self.error("generic rules are not supported"));
+                }
+                Type2::Name(name)
+            }
+            Token::Punct("(") => {
+                let content = self.parse_type()?;
+                self.expect(")")?;
+                Type2::Parenthesized(content)
+            }
+            Token::Punct("{") => {
+                let group = self.parse_group()?;
+                self.expect("}")?;
+                Type2::Map(group)
+            }
+            Token::Punct("[") => {
+                let group = self.parse_group()?;
+                self.expect("]")?;
+                Type2::Array(group)
+            }
+            Token::Punct("~") => match self.peek().cloned() {
+                Some(Token::Name(name)) => {
+                    self.position += 1;
+                    Type2::Unwrap(name)
+                }
+                _ => return Err(self.error("expected a rule name after ~")),
+            },
+            Token::Punct("&") => {
+                if self.eat("(") {
+                    let group = self.parse_group()?;
+                    self.expect(")")?;
+                    Type2::Choice(group)
+                } else if let Some(Token::Name(name)) = self.peek().cloned() {
+                    self.position += 1;
+                    Type2::Choice(Group(vec![vec![Entry {
+                        occurrence: Occurrence::ONCE,
+                        key: None,
+                        kind: EntryKind::Type(Type(vec![Type1 {
+                            base: Type2::Name(name),
+                            operator: None,
+                        }])),
+                    }]]))
+                } else {
+                    return Err(self.error("expected a group after &"));
+                }
+            }
+            Token::Major(6, tag) if self.is("(") => {
+                self.position += 1;
+                let content = self.parse_type()?;
+                self.expect(")")?;
+                Type2::Tag(tag, Box::new(content))
+            }
+            Token::Major(major_type, argument) => Type2::Major(major_type, argument),
+            Token::Punct("#") => Type2::Any,
+            _ => {
+                self.position -= 1;
+                return Err(self.error("expected a type"));
+            }
+        })
+    }
+}
+
+impl Group {
+    /// The type of a group that is a single entry without key or occurrence, such as the
+    /// group of `rule = (int / tstr)`.
+    pub fn as_type(&self) -> Option<&Type> {
+        match &self.0[..] {
+            [entries] => match &entries[..] {
+                [Entry {
+                    occurrence: Occurrence::ONCE,
+                    key: None,
+                    kind,
+                }] => match kind {
+                    EntryKind::Type(content) => Some(content),
+                    EntryKind::Group(group) => group.as_type(),
+                },
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl From<Group> for Definition {
+    fn from(group: Group) -> Self {
+        if let Some(content) = group.as_type() {
+            return Definition::Type(content.clone());
+        }
+        // `rule = (a // b)` is the group `a // b`.
+        match &group.0[..] {
+            [entries] => match &entries[..] {
+                [Entry {
+                    occurrence: Occurrence::ONCE,
+                    key: None,
+                    kind: EntryKind::Group(inner),
+                }] => Definition::from(inner.clone()),
+                _ => Definition::Group(group),
+            },
+            _ => Definition::Group(group),
+        }
+    }
+}
+
+impl Cddl {
+    pub fn parse(text: &str) -> Result<Cddl, String> {
+        // unwrap safety: the prelude is valid CDDL
+        let mut cddl = Cddl::from_rules(
+            Parser {
+                tokens: lex(PRELUDE).unwrap(),
+                position: 0,
+            }
+            .parse_rules()
+            .unwrap(),
+        )?;
+        cddl.prelude = cddl.rules.keys().cloned().collect();
+        let rules = Parser {
+            tokens: lex(text)?,
+            position: 0,
+        }
+        .parse_rules()?;
+        let mut defined = vec![];
+        for (name, assign, group) in rules {
+            if assign == "=" {
+                if defined.contains(&name) {
+                    return Err(format!("rule {} is defined twice", name));
+                }
+                defined.push(name.clone());
+            }
+            cddl.add_rule(name, assign, group)?;
+        }
+        cddl.check_names()?;
+        cddl.check_recursion()?;
+        Ok(cddl)
+    }
+
+    fn from_rules(rules: Vec<(String, &'static str, Group)>) -> Result<Cddl, String> {
+        let mut cddl = Cddl {
+            rules: BTreeMap::new(),
+            prelude: BTreeSet::new(),
+        };
+        for (name, assign, group) in rules {
+            cddl.add_rule(name, assign, group)?;
+        }
+        Ok(cddl)
+    }
+
+    fn add_rule(&mut self, name: String, assign: &str, group: Group) -> Result<(), String> {
+        let previous = if assign == "=" {
+            None
+        } else {
+            self.rules.remove(&name)
+        };
+        let definition = match (previous, assign) {
+            (None, _) => Definition::from(group),
+            (Some(Definition::Type(Type(mut choices))), "/=") => match group.as_type() {
+                Some(Type(more)) => {
+                    choices.extend(more.iter().cloned());
+                    Definition::Type(Type(choices))
+                }
+                None => return Err(format!("/= of rule {} is not a type", name)),
+            },
+            (Some(Definition::Group(Group(mut choices))), "//=") => {
+                choices.extend(group.0);
+                Definition::Group(Group(choices))
+            }
+            (Some(Definition::Type(content)), "//=") => {
+                let mut choices = vec![vec![Entry {
+                    occurrence: Occurrence::ONCE,
+                    key: None,
+                    kind: EntryKind::Type(content),
+                }]];
+                choices.extend(group.0);
+                Definition::Group(Group(choices))
+            }
+            (Some(_), _) => return Err(format!("{} of rule {} is not a group", assign, name)),
+        };
+        self.rules.insert(name, definition);
+        Ok(())
+    }
+
+    pub fn rule(&self, name: &str) -> Option<&Definition> {
+        self.rules.get(name)
+    }
+
+    /// Whether `name` is one of the rules of the prelude, such as `uint` or `tdate`.
+    pub fn is_prelude(&self, name: &str) -> bool {
+        self.prelude.contains(name)
+    }
+
+    /// Checks that every name that is used is defined, except sockets (`$name` and
+    /// `$$name`), which may be left empty.
+    fn check_names(&self) -> Result<(), String> {
+        let mut names = vec![];
+        for definition in self.rules.values() {
+            match definition {
+                Definition::Type(content) => content.names(&mut names),
+                Definition::Group(group) => group.names(&mut names),
+            }
+        }
+        match names
+            .into_iter()
+            .find(|name| !name.starts_with('$') && !self.rules.contains_key(*name))
+        {
+            Some(name) => Err(format!("rule {} is not defined", name)),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that no rule refers to itself before an array, map or tag nests the reference
+    /// in an item of its own, as in `a = int / a`, which could never match anything.
+    fn check_recursion(&self) -> Result<(), String> {
+        for start in self.rules.keys() {
+            let mut seen = BTreeSet::new();
+            let mut pending = vec![start.as_str()];
+            while let Some(name) = pending.pop() {
+                let mut names = vec![];
+                match self.rules.get(name) {
+                    Some(Definition::Type(content)) => content.head_names(&mut names),
+                    Some(Definition::Group(group)) => group.head_names(&mut names),
+                    None => {}
+                }
+                for name in names {
+                    if name == start {
+                        return Err(format!(
+                            "rule {} refers to itself outside an array, map or tag",
+                            start
+                        ));
+                    }
+                    if seen.insert(name) {
+                        pending.push(name);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Group {
+    fn names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        for entry in self.0.iter().flatten() {
+            if let Some(MemberKey::Type(key, _)) = &entry.key {
+                key.names(names);
+            }
+            match &entry.kind {
+                EntryKind::Type(content) => content.names(names),
+                EntryKind::Group(group) => group.names(names),
+            }
+        }
+    }
+}
+
+impl Group {
+    /// The names used by the first entry of each choice, unless it names a member.
+    fn head_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        for entries in &self.0 {
+            match entries.first() {
+                Some(Entry {
+                    key: None,
+                    kind: EntryKind::Type(content),
+                    ..
+                }) => content.head_names(names),
+                Some(Entry {
+                    key: None,
+                    kind: EntryKind::Group(group),
+                    ..
+                }) => group.head_names(names),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Type {
+    /// The names that the type stands for without an array, map or tag around them.
+    fn head_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        for choice in &self.0 {
+            match &choice.base {
+                Type2::Name(name) | Type2::Unwrap(name) => names.push(name),
+                Type2::Parenthesized(content) => content.head_names(names),
+                _ => {}
+            }
+        }
+    }
+
+    fn names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        for choice in &self.0 {
+            choice.names(names);
+        }
+    }
+}
+
+impl Type1 {
+    fn names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        self.base.names(names);
+        if let Some((_, controller)) = &self.operator {
+            controller.names(names);
+        }
+    }
+}
+
+impl Type2 {
+    fn names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Type2::Name(name) | Type2::Unwrap(name) => names.push(name),
+            Type2::Parenthesized(content) => content.names(names),
+            Type2::Tag(_, content) => content.names(names),
+            Type2::Map(group) | Type2::Array(group) | Type2::Choice(group) => group.names(names),
+            Type2::Value(_) | Type2::Major(..) | Type2::Any => {}
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Int(value) => write!(f, "{}", value),
+            Literal::Float(value) => write!(f, "{:?}", value),
+            Literal::Text(text) => write!(f, "{:?}", text),
+            Literal::Bytes(bytes) => write!(f, "h'{}'", crate::format_hex(bytes)),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, choice) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, " / ")?;
+            }
+            write!(f, "{}", choice)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Type1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.base)?;
+        match &self.operator {
+            Some((Operator::Range { inclusive: true }, end)) => write!(f, "..{}", end),
+            Some((Operator::Range { inclusive: false }, end)) => write!(f, "...{}", end),
+            Some((Operator::Control(name), controller)) => write!(f, " .{} {}", name, controller),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Writes types in short: maps, arrays and groups are not spelled out.
+impl fmt::Display for Type2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type2::Value(value) => write!(f, "{}", value),
+            Type2::Name(name) => write!(f, "{}", name),
+            Type2::Parenthesized(content) => write!(f, "({})", content),
+            Type2::Map(_) => write!(f, "map"),
+            Type2::Array(_) => write!(f, "array"),
+            Type2::Unwrap(name) => write!(f, "~{}", name),
+            Type2::Choice(_) => write!(f, "choice"),
+            Type2::Tag(Some(tag), content) => write!(f, "#6.{}({})", tag, content),
+            Type2::Tag(None, content) => write!(f, "#6({})", content),
+            Type2::Major(major_type, Some(argument)) => write!(f, "#{}.{}", major_type, argument),
+            Type2::Major(major_type, None) => write!(f, "#{}", major_type),
+            Type2::Any => write!(f, "#"),
+        }
+    }
+}
+
+impl fmt::Display for MemberKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemberKey::Bareword(name) => write!(f, "{:?}", name),
+            MemberKey::Value(value) => write!(f, "{}", value),
+            MemberKey::Type(key, _) => write!(f, "{}", key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(name: &str) -> Type1 {
+        Type1 {
+            base: Type2::Name(name.to_string()),
+            operator: None,
+        }
+    }
+
+    #[test]
+    fn rules() {
+        let cddl = Cddl::parse(
+            "message = {
+                 id: uint .size 4,   ; a comment
+                 ? 2 => [* tstr],
+                 * tstr => any,
+             }
+             color = &colors
+             colors = (red: 0, green: 1)
+             header = (1 => int // 2 => bstr)
+             small = 0..10
+             bytes4 = h'01 02 03 04' / b64'AQIDBA'",
+        )
+        .unwrap();
+        let Some(Definition::Type(Type(message))) = cddl.rule("message") else {
+            panic!("message is not a type");
+        };
+        let Type2::Map(Group(choices)) = &message[0].base else {
+            panic!("message is not a map");
+        };
+        let entries = &choices[0];
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].key, Some(MemberKey::Bareword("id".to_string())));
+        assert_eq!(
+            entries[0].kind,
+            EntryKind::Type(Type(vec![Type1 {
+                base: Type2::Name("uint".to_string()),
+                operator: Some((
+                    Operator::Control("size".to_string()),
+                    Type2::Value(Literal::Int(4))
+                )),
+            }]))
+        );
+        assert_eq!(
+            entries[1].occurrence,
+            Occurrence {
+                min: 0,
+                max: Some(1)
+            }
+        );
+        assert_eq!(
+            entries[1].key,
+            Some(MemberKey::Type(
+                Box::new(Type1 {
+                    base: Type2::Value(Literal::Int(2)),
+                    operator: None
+                }),
+                false
+            ))
+        );
+        assert_eq!(
+            entries[2].key,
+            Some(MemberKey::Type(Box::new(name("tstr")), false))
+        );
+        assert_eq!(entries[2].occurrence, Occurrence { min: 0, max: None });
+        assert!(matches!(cddl.rule("colors"), Some(Definition::Group(_))));
+        assert!(matches!(
+            cddl.rule("header"),
+            Some(Definition::Group(Group(choices))) if choices.len() == 2
+        ));
+        assert_eq!(
+            cddl.rule("bytes4"),
+            Some(&Definition::Type(Type(vec![
+                Type1 {
+                    base: Type2::Value(Literal::Bytes(vec![1, 2, 3, 4])),
+                    operator: None
+                };
+                2
+            ])))
+        );
+        assert_eq!(
+            cddl.rule("small").map(|rule| match rule {
+                Definition::Type(content) => content.to_string(),
+                Definition::Group(_) => String::new(),
+            }),
+            Some("0..10".to_string())
+        );
+    }
+
+    #[test]
+    fn occurrences() {
+        let cddl = Cddl::parse("a = [1*3 int, 2* tstr, *5 bstr]").unwrap();
+        let Some(Definition::Type(Type(a))) = cddl.rule("a") else {
+            panic!("a is not a type");
+        };
+        let Type2::Array(Group(choices)) = &a[0].base else {
+            panic!("a is not an array");
+        };
+        let occurrences: Vec<_> = choices[0].iter().map(|entry| entry.occurrence).collect();
+        assert_eq!(
+            occurrences,
+            vec![
+                Occurrence {
+                    min: 1,
+                    max: Some(3)
+                },
+                Occurrence { min: 2, max: None },
+                Occurrence {
+                    min: 0,
+                    max: Some(5)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_cddl() {
+        assert_eq!(
+            Cddl::parse("a = {\n b: foo }"),
+            Err("rule foo is not defined".to_string())
+        );
+        assert_eq!(
+            Cddl::parse("a = int .bogus 3"),
+            Err("line 1: unknown control .bogus".to_string())
+        );
+        assert_eq!(
+            Cddl::parse("a = [\n int"),
+            Err("end of file: expected ]".to_string())
+        );
+        assert_eq!(
+            Cddl::parse("a<T> = [T]"),
+            Err("line 1: generic rules are not supported".to_string())
+        );
+        assert_eq!(
+            Cddl::parse("a = int\na = tstr"),
+            Err("rule a is defined twice".to_string())
+        );
+        assert!(Cddl::parse("a = $socket\n$socket /= int").is_ok());
+    }
+
+    #[test]
+    fn recursive_rules() {
+        for text in [
+            "a = a",
+            "a = int / a",
+            "a = (a)",
+            "a = ~a",
+            "a = b\nb = [int] / a",
+        ] {
+            assert_eq!(
+                Cddl::parse(text),
+                Err("rule a refers to itself outside an array, map or tag".to_string()),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(
+            Cddl::parse("a = [g]\ng = (g)"),
+            Err("rule g refers to itself outside an array, map or tag".to_string())
+        );
+        for text in [
+            "a = int / [a]",
+            "a = #6.1(a) / int",
+            "a = {? x: a}",
+            "a = [g]\ng = (int, ? g)",
+        ] {
+            assert!(Cddl::parse(text).is_ok(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,1581 @@
+//! Validation of decoded items against the rules of a CDDL file (RFC 8610), reporting each
+//! mismatch with the path and byte offset of the item.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::cbor_object::CborObject;
+use crate::cbor_parser::parse_cbor;
+use crate::cddl::{
+    Cddl, Definition, Entry, EntryKind, Group, Literal, MemberKey, Occurrence, Operator, Type,
+    Type1, Type2,
+};
+use crate::ctap2;
+use crate::dictionary;
+use crate::value::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Map keys and array indexes from the top-level item, such as `/1/0`.
+    pub path: String,
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at offset {}: {}",
+            self.path, self.offset, self.message
+        )
+    }
+}
+
+/// Names of the CDDL member keys and rules that map members and array elements match, by
+/// the path of the member or element.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Names {
+    names: HashMap<Vec<String>, String>,
+}
+
+impl Names {
+    /// The name of the map key or array element at `path`.
+    pub fn get(&self, path: &[String]) -> Option<&str> {
+        self.names.get(path).map(String::as_str)
+    }
+
+    fn insert(&mut self, path: Vec<String>, name: String) {
+        self.names.entry(path).or_insert(name);
+    }
+
+    fn extend(&mut self, names: Names) {
+        for (path, name) in names.names {
+            self.insert(path, name);
+        }
+    }
+}
+
+/// The outcome of checking an item against a rule.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub mismatches: Vec<Mismatch>,
+    pub names: Names,
+}
+
+/// An item and the offset of its encoding.
+#[derive(Clone, Copy)]
+struct Item<'o> {
+    object: &'o CborObject,
+    offset: usize,
+}
+
+/// A member of a map group once nested groups are spliced in.
+#[derive(Clone, Copy)]
+struct Member<'c> {
+    key: &'c MemberKey,
+    value: &'c Type,
+    occurrence: Occurrence,
+}
+
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn of(object: &CborObject) -> Option<Number> {
+        match object {
+            CborObject::HalfPrecisionFloat(x) => Some(Number::Float(x.value().into())),
+            CborObject::SinglePrecisionFloat(x) => Some(Number::Float(x.value().into())),
+            CborObject::DoublePrecisionFloat(x) => Some(Number::Float(x.value())),
+            _ => object.as_integer().map(Number::Int),
+        }
+    }
+
+    fn of_literal(literal: &Literal) -> Option<Number> {
+        match literal {
+            Literal::Int(value) => Some(Number::Int(*value)),
+            Literal::Float(value) => Some(Number::Float(*value)),
+            _ => None,
+        }
+    }
+
+    fn compare(&self, other: &Number) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => Some(a.cmp(b)),
+            (Number::Int(a), Number::Float(b)) => (*a as f64).partial_cmp(b),
+            (Number::Float(a), Number::Int(b)) => a.partial_cmp(&(*b as f64)),
+            (Number::Float(a), Number::Float(b)) => a.partial_cmp(b),
+        }
+    }
+}
+
+/// Returns the offset of the item that follows the one at `offset`.
+fn item_end(bytes: &[u8], offset: usize) -> Result<usize, String> {
+    let (major_type, argument, head_length) = ctap2::head(bytes.get(offset..).unwrap_or(&[]))?;
+    let mut next = offset + head_length;
+    let Some(argument) = argument else {
+        if major_type == 7 {
+            return Ok(next);
+        }
+        while bytes.get(next) != Some(&0xff) {
+            if next >= bytes.len() {
+                return Err("truncated item".to_string());
+            }
+            next = item_end(bytes, next)?;
+        }
+        return Ok(next + 1);
+    };
+    match major_type {
+        2 | 3 => Ok(next + argument as usize),
+        4 | 5 => {
+            let count = if major_type == 5 {
+                2 * argument
+            } else {
+                argument
+            };
+            for _ in 0..count {
+                next = item_end(bytes, next)?;
+            }
+            Ok(next)
+        }
+        6 => item_end(bytes, next),
+        _ => Ok(next),
+    }
+}
+
+fn major_type(object: &CborObject) -> u8 {
+    match object {
+        CborObject::UnsignedInteger(_) => 0,
+        CborObject::NegativeInteger(_) => 1,
+        CborObject::ByteString(_)
+        | CborObject::IndefiniteByteString(_)
+        | CborObject::ByteStringWithEmbedded(_) => 2,
+        CborObject::TextString(_) | CborObject::IndefiniteTextString(_) => 3,
+        CborObject::Array(_) => 4,
+        CborObject::Map(_) => 5,
+        CborObject::Tag(_) => 6,
+        _ => 7,
+    }
+}
+
+/// Describes an item for a mismatch message: its value if it is short, else its type.
+fn describe(object: &CborObject) -> String {
+    if let Some(value) = object.as_integer() {
+        return value.to_string();
+    }
+    if let Some(text) = object.as_text() {
+        return format!("{:?}", text);
+    }
+    match object {
+        CborObject::ByteString(_)
+        | CborObject::IndefiniteByteString(_)
+        | CborObject::ByteStringWithEmbedded(_) => "bstr".to_string(),
+        CborObject::Array(_) => "array".to_string(),
+        CborObject::Map(_) => "map".to_string(),
+        CborObject::Tag(x) => match x.tag() {
+            Some(tag) => format!("tag {}", tag),
+            None => "tag".to_string(),
+        },
+        CborObject::SimpleValue(x) => match x.value() {
+            20 => "false".to_string(),
+            21 => "true".to_string(),
+            22 => "null".to_string(),
+            23 => "undefined".to_string(),
+            value => format!("simple({})", value),
+        },
+        CborObject::HalfPrecisionFloat(x) => format!("float16 {}", x.value()),
+        CborObject::SinglePrecisionFloat(x) => format!("float32 {}", x.value()),
+        CborObject::DoublePrecisionFloat(x) => format!("float64 {}", x.value()),
+        _ => "invalid item".to_string(),
+    }
+}
+
+fn matches_literal(literal: &Literal, object: &CborObject) -> bool {
+    match literal {
+        Literal::Int(value) => object.as_integer() == Some(*value),
+        Literal::Float(value) => {
+            matches!(Number::of(object), Some(Number::Float(x)) if x == *value)
+        }
+        Literal::Text(text) => object.as_text().as_deref() == Some(text.as_str()),
+        Literal::Bytes(bytes) => object.as_bytes().as_deref() == Some(bytes.as_slice()),
+    }
+}
+
+/// Whether the item is of the major type and additional information of `#m.n`.
+fn matches_major(major: u8, argument: Option<u64>, object: &CborObject) -> bool {
+    if major_type(object) != major {
+        return false;
+    }
+    let Some(argument) = argument else {
+        return true;
+    };
+    match object {
+        CborObject::UnsignedInteger(x) => x.value() == Some(argument),
+        CborObject::NegativeInteger(x) => x.value() == Some(argument),
+        CborObject::Tag(x) => x.tag() == Some(argument),
+        CborObject::SimpleValue(x) => u64::from(x.value()) == argument,
+        CborObject::HalfPrecisionFloat(_) => argument == 25,
+        CborObject::SinglePrecisionFloat(_) => argument == 26,
+        CborObject::DoublePrecisionFloat(_) => argument == 27,
+        _ => object
+            .as_bytes()
+            .map(|bytes| bytes.len())
+            .or(object.as_text().map(|text| text.len()))
+            .or(object.as_array().map(|elements| elements.len()))
+            .or(object.as_map().map(|pairs| pairs.len()))
+            .is_some_and(|length| length as u64 == argument),
+    }
+}
+
+/// The group of a group socket (`$$name`) that nothing plugs into.
+static EMPTY_GROUP: LazyLock<Group> = LazyLock::new(|| Group(vec![vec![]]));
+
+fn with_segment(path: &[String], segment: String) -> Vec<String> {
+    let mut path = path.to_vec();
+    path.push(segment);
+    path
+}
+
+pub struct Validator<'c, 'b> {
+    cddl: &'c Cddl,
+    /// The encoding of the items, into which their offsets point.
+    bytes: &'b [u8],
+    /// Offset of `bytes` in the input, for the items of an indefinite-length byte string
+    /// with a `.cbor` control.
+    base: usize,
+}
+
+impl<'c, 'b> Validator<'c, 'b> {
+    pub fn new(cddl: &'c Cddl, bytes: &'b [u8]) -> Self {
+        Validator {
+            cddl,
+            bytes,
+            base: 0,
+        }
+    }
+
+    /// Checks the item encoded at `offset` against the type of rule `rule`.
+    pub fn validate(
+        &self,
+        rule: &str,
+        object: &CborObject,
+        offset: usize,
+    ) -> Result<Report, String> {
+        let Some(definition) = self.cddl.rule(rule) else {
+            return Err(format!("rule {} is not defined", rule));
+        };
+        let Definition::Type(content) = definition else {
+            return Err(format!("rule {} is a group, not a type", rule));
+        };
+        let mut report = Report::default();
+        self.check_type(content, Item { object, offset }, &[], &mut report);
+        Ok(report)
+    }
+
+    fn mismatch(&self, path: &[String], item: Item, message: String) -> Mismatch {
+        Mismatch {
+            path: format!("/{}", path.join("/")),
+            offset: self.base + item.offset,
+            message,
+        }
+    }
+
+    fn head_length(&self, offset: usize) -> usize {
+        ctap2::head(self.bytes.get(offset..).unwrap_or(&[])).map_or(1, |(_, _, length)| length)
+    }
+
+    /// Returns the items in an array, or the keys and values of a map, with their offsets.
+    fn children<'o>(&self, item: Item<'o>, children: Vec<&'o CborObject>) -> Vec<Item<'o>> {
+        let mut offset = item.offset + self.head_length(item.offset);
+        children
+            .into_iter()
+            .map(|object| {
+                let child = Item { object, offset };
+                offset = item_end(self.bytes, offset).unwrap_or(self.bytes.len());
+                child
+            })
+            .collect()
+    }
+
+    /// The definition of a rule, which is `None` for a socket that nothing plugs into.
+    fn definition(&self, name: &str) -> Option<&'c Definition> {
+        self.cddl.rule(name)
+    }
+
+    /// The group that an entry of type `content` stands for when it names a group or
+    /// unwraps a map or an array.
+    fn entry_group(&self, content: &'c Type) -> Option<&'c Group> {
+        let [Type1 {
+            base,
+            operator: None,
+        }] = &content.0[..]
+        else {
+            return None;
+        };
+        match base {
+            Type2::Name(name) => match self.definition(name) {
+                Some(Definition::Group(group)) => Some(group),
+                None if name.starts_with("$$") => Some(&EMPTY_GROUP),
+                _ => None,
+            },
+            Type2::Unwrap(name) => match self.unwrapped(name)? {
+                Type2::Map(group) | Type2::Array(group) => Some(group),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The map, array or tag that `~name` unwraps.
+    fn unwrapped(&self, name: &str) -> Option<&'c Type2> {
+        match self.definition(name)? {
+            Definition::Type(Type(choices)) => match &choices[..] {
+                [Type1 {
+                    base: Type2::Name(name),
+                    operator: None,
+                }] => self.unwrapped(name),
+                [Type1 {
+                    base,
+                    operator: None,
+                }] => Some(base),
+                _ => None,
+            },
+            Definition::Group(_) => None,
+        }
+    }
+
+    fn literal(&self, content: &'c Type2) -> Option<&'c Literal> {
+        match content {
+            Type2::Value(literal) => Some(literal),
+            Type2::Name(name) => match self.definition(name)? {
+                Definition::Type(Type(choices)) => match &choices[..] {
+                    [Type1 {
+                        base,
+                        operator: None,
+                    }] => self.literal(base),
+                    _ => None,
+                },
+                Definition::Group(_) => None,
+            },
+            Type2::Parenthesized(Type(choices)) => match &choices[..] {
+                [Type1 {
+                    base,
+                    operator: None,
+                }] => self.literal(base),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn matches_type(&self, content: &'c Type, item: Item) -> bool {
+        let mut report = Report::default();
+        self.check_type(content, item, &[], &mut report);
+        report.mismatches.is_empty()
+    }
+
+    fn matches_type1(&self, content: &'c Type1, item: Item) -> bool {
+        let mut report = Report::default();
+        self.check_type1(content, item, &[], &mut report);
+        report.mismatches.is_empty()
+    }
+
+    fn matches_type2(&self, content: &'c Type2, item: Item) -> bool {
+        let mut report = Report::default();
+        self.check_type2(content, item, &[], &mut report);
+        report.mismatches.is_empty()
+    }
+
+    /// Checks the item against a type, returning the names of its members if it matches.
+    fn trial_type(&self, content: &'c Type, item: Item, path: &[String]) -> Option<Report> {
+        let mut report = Report::default();
+        self.check_type(content, item, path, &mut report);
+        report.mismatches.is_empty().then_some(report)
+    }
+
+    fn trial_type1(&self, content: &'c Type1, item: Item, path: &[String]) -> Option<Report> {
+        let mut report = Report::default();
+        self.check_type1(content, item, path, &mut report);
+        report.mismatches.is_empty().then_some(report)
+    }
+
+    /// Whether a value would match the controller of a control, such as a size or a bit
+    /// number.
+    fn controller_accepts(&self, controller: &'c Type2, value: u64) -> bool {
+        let bytes = Value::Unsigned(value).encode();
+        // unwrap safety: the encoding of a value is always a well-formed item
+        let (_, object) = parse_cbor(&bytes).unwrap();
+        Validator::new(self.cddl, &bytes).matches_type2(
+            controller,
+            Item {
+                object: &object,
+                offset: 0,
+            },
+        )
+    }
+
+    /// Whether the item is of the same kind as the type, so that the type is the one to
+    /// explain a mismatch with.
+    fn could_be(&self, content: &'c Type2, object: &CborObject, depth: usize) -> bool {
+        if depth > 16 {
+            return false;
+        }
+        match content {
+            Type2::Value(literal) => match literal {
+                Literal::Int(_) => object.as_integer().is_some(),
+                Literal::Float(_) => matches!(Number::of(object), Some(Number::Float(_))),
+                Literal::Text(_) => major_type(object) == 3,
+                Literal::Bytes(_) => major_type(object) == 2,
+            },
+            Type2::Name(name) => match self.definition(name) {
+                Some(Definition::Type(Type(choices))) => choices
+                    .iter()
+                    .any(|choice| self.could_be(&choice.base, object, depth + 1)),
+                _ => false,
+            },
+            Type2::Parenthesized(Type(choices)) => choices
+                .iter()
+                .any(|choice| self.could_be(&choice.base, object, depth + 1)),
+            Type2::Map(_) => major_type(object) == 5,
+            Type2::Array(_) => major_type(object) == 4,
+            Type2::Tag(tag, _) => match object.as_tag() {
+                Some((object_tag, _)) => tag.is_none_or(|tag| tag == object_tag),
+                None => false,
+            },
+            Type2::Major(major, _) => major_type(object) == *major,
+            Type2::Unwrap(_) | Type2::Choice(_) | Type2::Any => true,
+        }
+    }
+
+    fn check_type(&self, content: &'c Type, item: Item, path: &[String], out: &mut Report) {
+        let choices = &content.0;
+        if let Some(report) = choices
+            .iter()
+            .find_map(|choice| self.trial_type1(choice, item, path))
+        {
+            out.names.extend(report.names);
+            return;
+        }
+        let plausible: Vec<_> = choices
+            .iter()
+            .filter(|choice| self.could_be(&choice.base, item.object, 0))
+            .collect();
+        match &plausible[..] {
+            [choice] => self.check_type1(choice, item, path, out),
+            _ => out.mismatches.push(self.mismatch(
+                path,
+                item,
+                format!("expected {}, got {}", content, describe(item.object)),
+            )),
+        }
+    }
+
+    fn check_type1(&self, content: &'c Type1, item: Item, path: &[String], out: &mut Report) {
+        match &content.operator {
+            None => self.check_type2(&content.base, item, path, out),
+            Some((Operator::Range { inclusive }, end)) => {
+                let (Some(start), Some(end)) = (self.literal(&content.base), self.literal(end))
+                else {
+                    out.mismatches.push(self.mismatch(
+                        path,
+                        item,
+                        format!("range {} does not have literal bounds", content),
+                    ));
+                    return;
+                };
+                let in_range = match (
+                    Number::of_literal(start),
+                    Number::of_literal(end),
+                    Number::of(item.object),
+                ) {
+                    (Some(start), Some(end), Some(value))
+                        if matches!(
+                            (&start, &value),
+                            (Number::Int(_), Number::Int(_)) | (Number::Float(_), Number::Float(_))
+                        ) =>
+                    {
+                        let above = value.compare(&start).is_some_and(|order| order.is_ge());
+                        let below = value
+                            .compare(&end)
+                            .is_some_and(|order| order.is_lt() || (*inclusive && order.is_eq()));
+                        above && below
+                    }
+                    _ => false,
+                };
+                if !in_range {
+                    out.mismatches.push(self.mismatch(
+                        path,
+                        item,
+                        format!("expected {}, got {}", content, describe(item.object)),
+                    ));
+                }
+            }
+            Some((Operator::Control(control), controller)) => {
+                let count = out.mismatches.len();
+                self.check_type2(&content.base, item, path, out);
+                if out.mismatches.len() == count {
+                    self.check_control(control, controller, item, path, out);
+                }
+            }
+        }
+    }
+
+    fn check_control(
+        &self,
+        control: &str,
+        controller: &'c Type2,
+        item: Item,
+        path: &[String],
+        out: &mut Report,
+    ) {
+        let object = item.object;
+        let failure = match control {
+            "size" => match (object.as_unsigned(), object.as_bytes(), object.as_text()) {
+                (Some(value), _, _) => {
+                    let fits = (0..=8u32).any(|size| {
+                        (size == 8 || value < 1 << (8 * size))
+                            && self.controller_accepts(controller, u64::from(size))
+                    });
+                    (!fits).then(|| format!("{} does not fit in .size {}", value, controller))
+                }
+                (_, Some(bytes), _) => (!self.controller_accepts(controller, bytes.len() as u64))
+                    .then(|| format!("size {} is not .size {}", bytes.len(), controller)),
+                (_, _, Some(text)) => (!self.controller_accepts(controller, text.len() as u64))
+                    .then(|| format!("size {} is not .size {}", text.len(), controller)),
+                _ => Some(".size applies to uint, bstr and tstr".to_string()),
+            },
+            "bits" => {
+                let bits: Vec<u64> = match (object.as_unsigned(), object.as_bytes()) {
+                    (Some(value), _) => (0..64).filter(|bit| value >> bit & 1 == 1).collect(),
+                    (_, Some(bytes)) => (0..bytes.len() as u64 * 8)
+                        .filter(|bit| bytes[(bit / 8) as usize] >> (bit % 8) & 1 == 1)
+                        .collect(),
+                    _ => vec![],
+                };
+                let unknown: Vec<String> = bits
+                    .into_iter()
+                    .filter(|bit| !self.controller_accepts(controller, *bit))
+                    .map(|bit| bit.to_string())
+                    .collect();
+                (!unknown.is_empty()).then(|| {
+                    format!(
+                        "bits {} are not in .bits {}",
+                        unknown.join(", "),
+                        controller
+                    )
+                })
+            }
+            "regexp" | "pcre" => match (self.literal(controller), object.as_text()) {
+                (Some(Literal::Text(pattern)), Some(text)) => match full_match(pattern, &text) {
+                    Ok(true) => None,
+                    Ok(false) => Some(format!(
+                        "{:?} does not match .{} {}",
+                        text, control, controller
+                    )),
+                    Err(err) => Some(format!("invalid regular expression: {}", err)),
+                },
+                _ => Some(format!(".{} applies to tstr with a text pattern", control)),
+            },
+            "cbor" | "cborseq" => {
+                self.check_embedded(control == "cborseq", controller, item, path, out);
+                None
+            }
+            "lt" | "le" | "gt" | "ge" => {
+                match (
+                    self.literal(controller).and_then(Number::of_literal),
+                    Number::of(object),
+                ) {
+                    (Some(bound), Some(value)) => {
+                        let satisfied = value.compare(&bound).is_some_and(|order| match control {
+                            "lt" => order.is_lt(),
+                            "le" => order.is_le(),
+                            "gt" => order.is_gt(),
+                            _ => order.is_ge(),
+                        });
+                        (!satisfied).then(|| {
+                            format!("{} is not .{} {}", describe(object), control, controller)
+                        })
+                    }
+                    _ => Some(format!(".{} applies to numbers", control)),
+                }
+            }
+            "eq" => (!self.matches_type2(controller, item))
+                .then(|| format!("{} is not .eq {}", describe(object), controller)),
+            "ne" => self
+                .matches_type2(controller, item)
+                .then(|| format!("{} is .eq {}", describe(object), controller)),
+            "and" | "within" => {
+                self.check_type2(controller, item, path, out);
+                None
+            }
+            // Other controls do not restrict the values.
+            _ => None,
+        };
+        if let Some(message) = failure {
+            out.mismatches.push(self.mismatch(path, item, message));
+        }
+    }
+
+    /// Checks the items embedded in a byte string against `.cbor` or `.cborseq`.
+    fn check_embedded(
+        &self,
+        sequence: bool,
+        controller: &'c Type2,
+        item: Item,
+        path: &[String],
+        out: &mut Report,
+    ) {
+        let Some(content) = item.object.as_bytes() else {
+            out.mismatches
+                .push(self.mismatch(path, item, "expected bstr".to_string()));
+            return;
+        };
+        let (bytes, base, start) = match item.object {
+            CborObject::IndefiniteByteString(_) => (content.as_slice(), item.offset, 0),
+            _ => {
+                let start = item.offset + self.head_length(item.offset);
+                (self.bytes, self.base, start)
+            }
+        };
+        let validator = Validator {
+            cddl: self.cddl,
+            bytes,
+            base,
+        };
+        let end = start + content.len();
+        let mut objects = vec![];
+        let mut offset = start;
+        while offset < end {
+            match parse_cbor(&bytes[offset..end]) {
+                Ok((rest, object)) => {
+                    let length = end - offset - rest.len();
+                    objects.push((object, offset));
+                    offset += length;
+                }
+                Err(_) => {
+                    out.mismatches.push(self.mismatch(
+                        path,
+                        item,
+                        format!("not well-formed CBOR at offset {}", base + offset),
+                    ));
+                    return;
+                }
+            }
+            if !sequence {
+                break;
+            }
+        }
+        let items: Vec<_> = objects
+            .iter()
+            .map(|(object, offset)| Item {
+                object,
+                offset: *offset,
+            })
+            .collect();
+        if sequence {
+            match controller {
+                Type2::Array(group) => validator.check_sequence(group, item, &items, path, out),
+                _ => out.mismatches.push(self.mismatch(
+                    path,
+                    item,
+                    ".cborseq applies to an array type".to_string(),
+                )),
+            }
+            return;
+        }
+        match items.first() {
+            Some(embedded) if offset == end => {
+                validator.check_type2(controller, *embedded, path, out);
+            }
+            Some(_) => out.mismatches.push(self.mismatch(
+                path,
+                item,
+                "more than one item in .cbor byte string".to_string(),
+            )),
+            None => out.mismatches.push(self.mismatch(
+                path,
+                item,
+                "empty .cbor byte string".to_string(),
+            )),
+        }
+    }
+
+    fn check_type2(&self, content: &'c Type2, item: Item, path: &[String], out: &mut Report) {
+        let object = item.object;
+        let unexpected = || format!("expected {}, got {}", content, describe(object));
+        match content {
+            Type2::Value(literal) => {
+                if !matches_literal(literal, object) {
+                    out.mismatches.push(self.mismatch(path, item, unexpected()));
+                }
+            }
+            Type2::Name(name) => match self.definition(name) {
+                Some(Definition::Type(content)) => self.check_type(content, item, path, out),
+                Some(Definition::Group(_)) => out.mismatches.push(self.mismatch(
+                    path,
+                    item,
+                    format!("rule {} is a group, not a type", name),
+                )),
+                None => out.mismatches.push(self.mismatch(path, item, unexpected())),
+            },
+            Type2::Parenthesized(content) => self.check_type(content, item, path, out),
+            Type2::Map(group) => match object.as_map() {
+                Some(pairs) => {
+                    let flattened: Vec<_> = pairs
+                        .into_iter()
+                        .flat_map(|(key, value)| [key, value])
+                        .collect();
+                    let children = self.children(item, flattened);
+                    let pairs: Vec<_> = children
+                        .chunks_exact(2)
+                        .map(|pair| (pair[0], pair[1]))
+                        .collect();
+                    self.check_map(group, item, &pairs, path, out);
+                }
+                None => out.mismatches.push(self.mismatch(path, item, unexpected())),
+            },
+            Type2::Array(group) => match object.as_array() {
+                Some(elements) => {
+                    let elements = self.children(item, elements);
+                    self.check_sequence(group, item, &elements, path, out);
+                }
+                None => out.mismatches.push(self.mismatch(path, item, unexpected())),
+            },
+            Type2::Unwrap(name) => match self.unwrapped(name) {
+                Some(Type2::Tag(_, content)) => match object.as_tag() {
+                    Some((_, payload)) => {
+                        self.check_type(content, self.payload(item, payload), path, out)
+                    }
+                    None => self.check_type(content, item, path, out),
+                },
+                Some(unwrapped) => self.check_type2(unwrapped, item, path, out),
+                None => out.mismatches.push(self.mismatch(
+                    path,
+                    item,
+                    format!("~{} does not unwrap a map, an array or a tag", name),
+                )),
+            },
+            Type2::Choice(group) => {
+                let mut values = vec![];
+                self.choice_values(group, &mut values, 0);
+                if !values.iter().any(|value| self.matches_type(value, item)) {
+                    out.mismatches.push(self.mismatch(
+                        path,
+                        item,
+                        format!(
+                            "{} is not a choice of &{}",
+                            describe(object),
+                            group_name(group)
+                        ),
+                    ));
+                }
+            }
+            Type2::Tag(tag, content) => match object.as_tag() {
+                Some((object_tag, payload)) if tag.is_none_or(|tag| tag == object_tag) => {
+                    self.check_type(content, self.payload(item, payload), path, out)
+                }
+                _ => out.mismatches.push(self.mismatch(path, item, unexpected())),
+            },
+            Type2::Major(major, argument) => {
+                if !matches_major(*major, *argument, object) {
+                    out.mismatches.push(self.mismatch(path, item, unexpected()));
+                }
+            }
+            Type2::Any => {}
+        }
+    }
+
+    fn payload<'o>(&self, item: Item, payload: &'o CborObject) -> Item<'o> {
+        Item {
+            object: payload,
+            offset: item.offset + self.head_length(item.offset),
+        }
+    }
+
+    /// Collects the types of the entries of a group, for `&group`.
+    fn choice_values(&self, group: &'c Group, values: &mut Vec<&'c Type>, depth: usize) {
+        if depth > 16 {
+            return;
+        }
+        for entry in group.0.iter().flatten() {
+            match &entry.kind {
+                EntryKind::Group(group) => self.choice_values(group, values, depth + 1),
+                EntryKind::Type(content) => match self.entry_group(content) {
+                    Some(group) => self.choice_values(group, values, depth + 1),
+                    None => values.push(content),
+                },
+            }
+        }
+    }
+
+    /// Returns the positions in `items` at which a match of `group` from `start` can end.
+    fn group_ends(
+        &self,
+        group: &'c Group,
+        items: &[Item],
+        start: usize,
+        depth: usize,
+    ) -> BTreeSet<usize> {
+        if depth > 32 {
+            return BTreeSet::new();
+        }
+        group
+            .0
+            .iter()
+            .flat_map(|entries| self.sequence_ends(entries, items, start, depth))
+            .collect()
+    }
+
+    fn sequence_ends(
+        &self,
+        entries: &'c [Entry],
+        items: &[Item],
+        start: usize,
+        depth: usize,
+    ) -> BTreeSet<usize> {
+        entries
+            .iter()
+            .fold(BTreeSet::from([start]), |positions, entry| {
+                positions
+                    .into_iter()
+                    .flat_map(|position| self.entry_ends(entry, items, position, depth))
+                    .collect()
+            })
+    }
+
+    fn entry_ends(
+        &self,
+        entry: &'c Entry,
+        items: &[Item],
+        start: usize,
+        depth: usize,
+    ) -> BTreeSet<usize> {
+        self.repetition_ends(&entry.kind, entry.occurrence, items, start, depth)
+    }
+
+    fn repetition_ends(
+        &self,
+        kind: &'c EntryKind,
+        occurrence: Occurrence,
+        items: &[Item],
+        start: usize,
+        depth: usize,
+    ) -> BTreeSet<usize> {
+        let mut ends = BTreeSet::new();
+        if occurrence.min == 0 {
+            ends.insert(start);
+        }
+        let mut positions = BTreeSet::from([start]);
+        // A repetition that matches no item can be left out, so the number of repetitions
+        // is at most the number of items.
+        let limit = occurrence
+            .max
+            .unwrap_or(u64::MAX)
+            .min(items.len() as u64 + 1);
+        for count in 1..=limit {
+            positions = positions
+                .into_iter()
+                .flat_map(|position| self.once_ends(kind, items, position, depth))
+                .collect();
+            if count >= occurrence.min {
+                ends.extend(&positions);
+            }
+            if positions.is_empty() {
+                break;
+            }
+        }
+        ends
+    }
+
+    fn once_ends(
+        &self,
+        kind: &'c EntryKind,
+        items: &[Item],
+        start: usize,
+        depth: usize,
+    ) -> BTreeSet<usize> {
+        match kind {
+            EntryKind::Group(group) => self.group_ends(group, items, start, depth + 1),
+            EntryKind::Type(content) => match self.entry_group(content) {
+                Some(group) => self.group_ends(group, items, start, depth + 1),
+                None => match items.get(start) {
+                    Some(item) if self.matches_type(content, *item) => BTreeSet::from([start + 1]),
+                    _ => BTreeSet::new(),
+                },
+            },
+        }
+    }
+
+    /// Checks the elements of an array, or the items of a CBOR sequence, against a group.
+    fn check_sequence(
+        &self,
+        group: &'c Group,
+        container: Item,
+        items: &[Item],
+        path: &[String],
+        out: &mut Report,
+    ) {
+        if self.group_ends(group, items, 0, 0).contains(&items.len()) {
+            self.name_elements(group, items, path, out);
+            return;
+        }
+        // Explain the mismatch with the choice that goes furthest.
+        let Some(entries) = group.0.iter().max_by_key(|entries| {
+            let choice = Group(vec![entries.to_vec()]);
+            self.group_ends(&choice, items, 0, 0).last().copied()
+        }) else {
+            return;
+        };
+        let end = self.explain_sequence(entries, container, items, 0, path, out, 0);
+        for (index, item) in items.iter().enumerate().skip(end) {
+            out.mismatches.push(self.mismatch(
+                &with_segment(path, index.to_string()),
+                *item,
+                format!("unexpected element {}", describe(item.object)),
+            ));
+        }
+    }
+
+    /// Names the elements of an array, or the items of a CBOR sequence, that matches
+    /// `group` after the entries they match.
+    fn name_elements(&self, group: &'c Group, items: &[Item], path: &[String], out: &mut Report) {
+        let Some(assigned) = group.0.iter().find_map(|entries| {
+            self.sequence_ends(entries, items, 0, 0)
+                .contains(&items.len())
+                .then(|| self.assign_sequence(entries, items, 0, items.len(), 0))
+                .flatten()
+        }) else {
+            return;
+        };
+        for (index, entry) in assigned {
+            let path = with_segment(path, index.to_string());
+            if let Some(name) = self.element_name(entry) {
+                out.names.insert(path.clone(), name.to_string());
+            }
+            if let EntryKind::Type(content) = &entry.kind {
+                if let Some(report) = self.trial_type(content, items[index], &path) {
+                    out.names.extend(report.names);
+                }
+            }
+        }
+    }
+
+    /// Returns the entry that each item from `start` to `end` matches, for a match of the
+    /// entries that ends at `end`.
+    fn assign_sequence(
+        &self,
+        entries: &'c [Entry],
+        items: &[Item],
+        start: usize,
+        end: usize,
+        depth: usize,
+    ) -> Option<Vec<(usize, &'c Entry)>> {
+        if depth > 32 {
+            return None;
+        }
+        let [entry, rest @ ..] = entries else {
+            return (start == end).then(Vec::new);
+        };
+        self.entry_ends(entry, items, start, depth)
+            .into_iter()
+            .rev()
+            .filter(|middle| {
+                *middle <= end
+                    && self
+                        .sequence_ends(rest, items, *middle, depth)
+                        .contains(&end)
+            })
+            .find_map(|middle| {
+                let mut assigned = self.assign_repetitions(entry, items, start, middle, depth)?;
+                assigned.extend(self.assign_sequence(rest, items, middle, end, depth)?);
+                Some(assigned)
+            })
+    }
+
+    /// Splits the items from `start` to `end` into repetitions of `entry`, taking the
+    /// longest repetition that leaves a match of the others each time.
+    fn assign_repetitions(
+        &self,
+        entry: &'c Entry,
+        items: &[Item],
+        start: usize,
+        end: usize,
+        depth: usize,
+    ) -> Option<Vec<(usize, &'c Entry)>> {
+        let group = match &entry.kind {
+            EntryKind::Group(group) => Some(group),
+            EntryKind::Type(content) => self.entry_group(content),
+        };
+        let Some(group) = group else {
+            return Some((start..end).map(|index| (index, entry)).collect());
+        };
+        let Occurrence { min, max } = entry.occurrence;
+        let mut assigned = vec![];
+        let mut position = start;
+        let mut count = 0;
+        while position < end || count < min {
+            if max.is_some_and(|max| count >= max) {
+                return None;
+            }
+            let remaining = Occurrence {
+                min: min.saturating_sub(count + 1),
+                max: max.map(|max| max - count - 1),
+            };
+            let once = self
+                .once_ends(&entry.kind, items, position, depth)
+                .into_iter()
+                .rev()
+                .filter(|middle| {
+                    *middle > position
+                        && *middle <= end
+                        && self
+                            .repetition_ends(&entry.kind, remaining, items, *middle, depth)
+                            .contains(&end)
+                })
+                .find_map(|middle| {
+                    group.0.iter().find_map(|entries| {
+                        self.sequence_ends(entries, items, position, depth + 1)
+                            .contains(&middle)
+                            .then(|| {
+                                self.assign_sequence(entries, items, position, middle, depth + 1)
+                            })
+                            .flatten()
+                            .map(|once| (middle, once))
+                    })
+                });
+            let (middle, once) = once?;
+            assigned.extend(once);
+            position = middle;
+            count += 1;
+        }
+        Some(assigned)
+    }
+
+    /// The name of the element that matches `entry`: its key, as in `[x: int, y: int]`,
+    /// or the rule that its type names.
+    fn element_name(&self, entry: &'c Entry) -> Option<&'c str> {
+        match (&entry.key, &entry.kind) {
+            (Some(MemberKey::Bareword(name)), _) => Some(name),
+            (_, EntryKind::Type(content)) => self.type_name(content),
+            _ => None,
+        }
+    }
+
+    /// The name of the rule that `content` refers to, unless it is a rule of the prelude.
+    fn type_name(&self, content: &'c Type) -> Option<&'c str> {
+        match &content.0[..] {
+            [Type1 {
+                base: Type2::Name(name),
+                operator: None,
+            }] if !self.cddl.is_prelude(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The name of a map member whose key is `key`: the rule or the enumerator of the
+    /// choice that the key matches, as in `exp => int` or `&(exp: 4) => int`, or else the
+    /// rule of its value. Keys given by name, as in `exp: int`, already say it.
+    fn member_name(&self, member: &Member<'c>, key: Item) -> Option<&'c str> {
+        let key_name = match member.key {
+            MemberKey::Bareword(_) => return None,
+            MemberKey::Value(_) => None,
+            MemberKey::Type(content, _) => match &content.base {
+                Type2::Choice(group) => self.enumerator(group, key),
+                Type2::Name(name) if !self.cddl.is_prelude(name) => match self.unwrapped(name) {
+                    Some(Type2::Choice(group)) => self.enumerator(group, key),
+                    _ => None,
+                }
+                .or(Some(name)),
+                _ => None,
+            },
+        };
+        key_name.or_else(|| self.type_name(member.value))
+    }
+
+    /// The name of the member of the group of `&(...)` whose value is `key`.
+    fn enumerator(&self, group: &'c Group, key: Item) -> Option<&'c str> {
+        group.0.iter().flatten().find_map(|entry| {
+            let (Some(MemberKey::Bareword(name)), EntryKind::Type(Type(choices))) =
+                (&entry.key, &entry.kind)
+            else {
+                return None;
+            };
+            let [Type1 {
+                base,
+                operator: None,
+            }] = &choices[..]
+            else {
+                return None;
+            };
+            self.literal(base)
+                .is_some_and(|literal| matches_literal(literal, key.object))
+                .then_some(name.as_str())
+        })
+    }
+
+    /// Matches the entries one after the other, greedily, reporting why those that do not
+    /// match fail. Returns the position after the items that were matched.
+    #[allow(clippy::too_many_arguments)]
+    fn explain_sequence(
+        &self,
+        entries: &'c [Entry],
+        container: Item,
+        items: &[Item],
+        mut position: usize,
+        path: &[String],
+        out: &mut Report,
+        depth: usize,
+    ) -> usize {
+        if depth > 16 {
+            return position;
+        }
+        for (index, entry) in entries.iter().enumerate() {
+            let max = entry.occurrence.max.unwrap_or(u64::MAX);
+            let mut count = 0;
+            while count < max {
+                let next = self
+                    .once_ends(&entry.kind, items, position, depth)
+                    .into_iter()
+                    .filter(|end| *end > position)
+                    .max();
+                if let Some(next) = next {
+                    position = next;
+                    count += 1;
+                    continue;
+                }
+                // An element that the entries after this one cannot take either is
+                // explained with this one, as in `[* int]`.
+                let (EntryKind::Type(content), Some(item)) = (&entry.kind, items.get(position))
+                else {
+                    break;
+                };
+                let rest = self.sequence_ends(&entries[index + 1..], items, position, depth);
+                if self.entry_group(content).is_some() || rest.iter().any(|end| *end > position) {
+                    break;
+                }
+                self.check_type(
+                    content,
+                    *item,
+                    &with_segment(path, position.to_string()),
+                    out,
+                );
+                position += 1;
+                count += 1;
+            }
+            while count < entry.occurrence.min {
+                let group = match &entry.kind {
+                    EntryKind::Group(group) => Some(group),
+                    EntryKind::Type(content) => self.entry_group(content),
+                };
+                match (group, items.get(position)) {
+                    (Some(group), _) => {
+                        position = self.explain_sequence(
+                            &group.0[0],
+                            container,
+                            items,
+                            position,
+                            path,
+                            out,
+                            depth + 1,
+                        );
+                    }
+                    (None, Some(item)) => {
+                        if let EntryKind::Type(content) = &entry.kind {
+                            self.check_type(
+                                content,
+                                *item,
+                                &with_segment(path, position.to_string()),
+                                out,
+                            );
+                        }
+                        position += 1;
+                    }
+                    (None, None) => {
+                        out.mismatches.push(self.mismatch(
+                            path,
+                            container,
+                            format!("missing element {}: {}", position, entry_name(entry)),
+                        ));
+                        position += 1;
+                    }
+                }
+                count += 1;
+            }
+        }
+        position.min(items.len())
+    }
+
+    /// Splices nested groups into the members of a map group, one list of members for each
+    /// combination of group choices.
+    fn map_alternatives(
+        &self,
+        group: &'c Group,
+        optional: bool,
+        depth: usize,
+    ) -> Vec<Vec<Member<'c>>> {
+        if depth > 16 {
+            return vec![vec![]];
+        }
+        let mut alternatives = vec![];
+        for entries in &group.0 {
+            let mut combinations: Vec<Vec<Member<'c>>> = vec![vec![]];
+            for entry in entries {
+                let nested = match &entry.kind {
+                    EntryKind::Group(group) => Some(group),
+                    EntryKind::Type(content) if entry.key.is_none() => self.entry_group(content),
+                    EntryKind::Type(_) => None,
+                };
+                let optional = optional || entry.occurrence.min == 0;
+                let options = match (nested, &entry.key, &entry.kind) {
+                    (Some(group), _, _) => self.map_alternatives(group, optional, depth + 1),
+                    (None, Some(key), EntryKind::Type(value)) => vec![vec![Member {
+                        key,
+                        value,
+                        occurrence: Occurrence {
+                            min: if optional { 0 } else { entry.occurrence.min },
+                            max: entry.occurrence.max,
+                        },
+                    }]],
+                    // Entries without keys do not match any member.
+                    _ => vec![vec![]],
+                };
+                combinations = combinations
+                    .into_iter()
+                    .flat_map(|members| {
+                        options.iter().map(move |option| {
+                            let mut members = members.clone();
+                            members.extend(option);
+                            members
+                        })
+                    })
+                    .take(64)
+                    .collect();
+            }
+            alternatives.extend(combinations);
+        }
+        alternatives
+    }
+
+    fn key_matches(&self, key: &'c MemberKey, item: Item) -> bool {
+        match key {
+            MemberKey::Bareword(name) => item.object.as_text().as_deref() == Some(name.as_str()),
+            MemberKey::Value(literal) => matches_literal(literal, item.object),
+            MemberKey::Type(content, _) => self.matches_type1(content, item),
+        }
+    }
+
+    /// Checks the members of a map against a group.
+    fn check_map(
+        &self,
+        group: &'c Group,
+        map: Item,
+        pairs: &[(Item, Item)],
+        path: &[String],
+        out: &mut Report,
+    ) {
+        let mut best: Option<Report> = None;
+        for members in self.map_alternatives(group, false, 0) {
+            let mut report = Report::default();
+            self.check_members(&members, map, pairs, path, &mut report);
+            if report.mismatches.is_empty() {
+                out.names.extend(report.names);
+                return;
+            }
+            if best
+                .as_ref()
+                .is_none_or(|best| report.mismatches.len() < best.mismatches.len())
+            {
+                best = Some(report);
+            }
+        }
+        if let Some(best) = best {
+            out.mismatches.extend(best.mismatches);
+            out.names.extend(best.names);
+        }
+    }
+
+    fn check_members(
+        &self,
+        members: &[Member<'c>],
+        map: Item,
+        pairs: &[(Item, Item)],
+        path: &[String],
+        out: &mut Report,
+    ) {
+        let mut used = vec![false; pairs.len()];
+        // Members with a literal key come first: they cut the match of their key.
+        let (literal, typed): (Vec<&Member>, Vec<&Member>) = members
+            .iter()
+            .partition(|member| !matches!(member.key, MemberKey::Type(..)));
+        for member in literal.iter().chain(&typed) {
+            let mut count = 0;
+            let mut keys_seen = 0;
+            for (index, (key, value)) in pairs.iter().enumerate() {
+                if used[index] || !self.key_matches(member.key, *key) {
+                    continue;
+                }
+                let cut = match member.key {
+                    MemberKey::Type(_, cut) => *cut,
+                    _ => true,
+                };
+                if !cut && !self.matches_type(member.value, *value) {
+                    // The value is reported with the members that are left.
+                    keys_seen += 1;
+                    continue;
+                }
+                used[index] = true;
+                count += 1;
+                let path = with_segment(path, dictionary::segment(key.object));
+                if let Some(name) = self.member_name(member, *key) {
+                    out.names.insert(path.clone(), name.to_string());
+                }
+                self.check_type(member.value, *value, &path, out);
+            }
+            if count + keys_seen < member.occurrence.min {
+                out.mismatches.push(self.mismatch(
+                    path,
+                    map,
+                    format!("missing member {}", member.key),
+                ));
+            }
+            if member.occurrence.max.is_some_and(|max| count > max) {
+                out.mismatches.push(self.mismatch(
+                    path,
+                    map,
+                    format!("too many members {}", member.key),
+                ));
+            }
+        }
+        for (index, (key, value)) in pairs.iter().enumerate() {
+            if used[index] {
+                continue;
+            }
+            let path = with_segment(path, dictionary::segment(key.object));
+            // A key that matches a member whose value does not is reported with the value.
+            match typed
+                .iter()
+                .find(|member| self.key_matches(member.key, *key))
+            {
+                Some(member) => self.check_type(member.value, *value, &path, out),
+                None => out.mismatches.push(self.mismatch(
+                    &path,
+                    *key,
+                    format!("unexpected member {}", describe(key.object)),
+                )),
+            }
+        }
+    }
+}
+
+fn group_name(group: &Group) -> String {
+    match group.0.iter().flatten().next() {
+        Some(Entry {
+            kind: EntryKind::Type(content),
+            ..
+        }) if group.0.iter().flatten().count() == 1 => content.to_string(),
+        _ => "(...)".to_string(),
+    }
+}
+
+fn entry_name(entry: &Entry) -> String {
+    let name = match &entry.key {
+        Some(MemberKey::Bareword(name)) => format!("{}: ", name),
+        _ => String::new(),
+    };
+    match &entry.kind {
+        EntryKind::Type(content) => format!("{}{}", name, content),
+        EntryKind::Group(_) => format!("{}group", name),
+    }
+}
+
+/// Whether the whole text matches the regular expression, as XSD patterns do
+/// (RFC 8610 Section 3.8.3).
+fn full_match(pattern: &str, text: &str) -> Result<bool, String> {
+    let regex = Regex::new(&format!("^(?:{})$", pattern)).map_err(|err| err.to_string())?;
+    Ok(regex.is_match(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_hex;
+    use crate::test_helpers::parse_item;
+
+    const CDDL: &str = r#"
+        message = {
+            id: uint .size 2,
+            ? name: tstr .regexp "[a-z]+",
+            values: [* int],
+            ? flags: uint .bits flag-bits,
+            ? payload: bstr .cbor point,
+            ? 1 => 0..10,
+        }
+        point = [x: int, y: int, ? label: tstr]
+        flag-bits = &(read: 0, write: 1)
+    "#;
+
+    fn validate(hex: &str) -> Vec<String> {
+        let cddl = Cddl::parse(CDDL).unwrap();
+        let bytes = parse_hex(hex).unwrap();
+        let object = parse_item(hex);
+        Validator::new(&cddl, &bytes)
+            .validate("message", &object, 0)
+            .unwrap()
+            .mismatches
+            .iter()
+            .map(Mismatch::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn valid_items() {
+        // {"id": 1, "values": [1, -1]}
+        assert_eq!(
+            validate("a2 626964 01 6676616c756573 82 01 20"),
+            Vec::<String>::new()
+        );
+        // {"id": 500, "name": "abc", "values": [], "flags": 3, "payload": <<[1, 2]>>, 1: 9}
+        assert_eq!(
+            validate(
+                "a6 626964 1901f4 646e616d65 63616263 6676616c756573 80 \
+                 65666c616773 03 677061796c6f6164 43 820102 01 09"
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn names() {
+        let cddl = Cddl::parse(
+            r#"
+            claims = {
+                exp => int,
+                ? &(nbf: 5, iat: 6) => int,
+                1 => origin,
+                "tags" => [* (label, tstr)],
+            }
+            exp = 4
+            origin = [lat: float, lon: float]
+            label = uint
+        "#,
+        )
+        .unwrap();
+        // {4: 1, 6: 2, 1: [1.5, 2.5], "tags": [7, "a", 8, "b"]}
+        let hex = "a4 04 01 06 02 01 82 f93e00 f94100 6474616773 84 07 6161 08 6162";
+        let bytes = parse_hex(hex).unwrap();
+        let object = parse_item(hex);
+        let report = Validator::new(&cddl, &bytes)
+            .validate("claims", &object, 0)
+            .unwrap();
+        assert_eq!(report.mismatches, vec![]);
+        let name = |path: &[&str]| {
+            let path: Vec<String> = path.iter().map(|segment| segment.to_string()).collect();
+            report.names.get(&path).map(str::to_string)
+        };
+        assert_eq!(name(&["4"]).as_deref(), Some("exp"));
+        assert_eq!(name(&["6"]).as_deref(), Some("iat"));
+        assert_eq!(name(&["1"]).as_deref(), Some("origin"));
+        assert_eq!(name(&["1", "0"]).as_deref(), Some("lat"));
+        assert_eq!(name(&["1", "1"]).as_deref(), Some("lon"));
+        assert_eq!(name(&["tags"]), None);
+        assert_eq!(name(&["tags", "2"]).as_deref(), Some("label"));
+        assert_eq!(name(&["tags", "3"]), None);
+    }
+
+    #[test]
+    fn mismatches() {
+        // {"id": 70000, "name": "ABC", "values": [1, "x"], "flags": 4, 1: 10, 2: 0}
+        assert_eq!(
+            validate(
+                "a6 626964 1a00011170 646e616d65 63414243 6676616c756573 82 01 6178 \
+                 65666c616773 04 01 0b 02 00"
+            ),
+            vec![
+                "/id at offset 4: 70000 does not fit in .size 2",
+                "/name at offset 14: \"ABC\" does not match .regexp \"[a-z]+\"",
+                "/values/1 at offset 27: expected int, got \"x\"",
+                "/flags at offset 35: bits 2 are not in .bits flag-bits",
+                "/1 at offset 37: expected 0..10, got 11",
+                "/2 at offset 38: unexpected member 2",
+            ]
+        );
+        // {"values": 1, "payload": <<[1, "y"]>>}
+        assert_eq!(
+            validate("a2 6676616c756573 01 677061796c6f6164 44 82016179"),
+            vec![
+                "/ at offset 0: missing member \"id\"",
+                "/values at offset 8: expected array, got 1",
+                "/payload/1 at offset 20: expected int, got \"y\"",
+            ]
+        );
+        // {"id": 1, "values": [], "payload": <<[1]>>}
+        assert_eq!(
+            validate("a3 626964 01 6676616c756573 80 677061796c6f6164 42 8101"),
+            vec!["/payload at offset 22: missing element 1: y: int"]
+        );
+    }
+
+    #[test]
+    fn choices_and_tags() {
+        let cddl = Cddl::parse(
+            "header = { 1 => int // 4 => bstr, $$header-extension }
+             dated = #6.1(uint) / tdate
+             records = bstr .cborseq [* uint]
+             point = [~coordinates]
+             coordinates = [x: int, y: int]",
+        )
+        .unwrap();
+        let validate = |rule: &str, hex: &str| -> Vec<String> {
+            let bytes = parse_hex(hex).unwrap();
+            let object = parse_item(hex);
+            Validator::new(&cddl, &bytes)
+                .validate(rule, &object, 0)
+                .unwrap()
+                .mismatches
+                .iter()
+                .map(Mismatch::to_string)
+                .collect()
+        };
+        assert_eq!(
+            validate("header", "a10401"),
+            vec!["/4 at offset 2: expected bstr, got 1"]
+        );
+        assert_eq!(validate("header", "a10120"), Vec::<String>::new());
+        assert_eq!(validate("dated", "c11a5f5e1000"), Vec::<String>::new());
+        assert_eq!(validate("dated", "c06131"), Vec::<String>::new());
+        assert_eq!(
+            validate("dated", "c26131"),
+            vec!["/ at offset 0: expected #6.1(uint) / tdate, got tag 2"]
+        );
+        assert_eq!(validate("records", "43010203"), Vec::<String>::new());
+        assert_eq!(
+            validate("records", "4401026131"),
+            vec!["/2 at offset 3: expected uint, got \"1\""]
+        );
+        assert_eq!(validate("point", "820102"), Vec::<String>::new());
+        assert_eq!(
+            validate("point", "83010203"),
+            vec!["/2 at offset 3: unexpected element 3"]
+        );
+    }
+
+    #[test]
+    fn regular_expressions() {
+        assert_eq!(full_match("[a-z]+", "abc"), Ok(true));
+        assert_eq!(full_match("[a-z]+", "abc1"), Ok(false));
+        assert_eq!(full_match("(ab|a)*c", "abac"), Ok(true));
+        assert_eq!(full_match("a{2,3}", "aaaa"), Ok(false));
+        assert_eq!(full_match("x*?y", "xxy"), Ok(true));
+        assert!(full_match("(", "").is_err());
+        assert_eq!(full_match("a|b", "ab"), Ok(false));
+        assert_eq!(full_match(r"\bab\b", "ab"), Ok(true));
+        assert_eq!(full_match(r"a\bb", "ab"), Ok(false));
+        assert_eq!(full_match("(a|aa)*b", &"a".repeat(100)), Ok(false));
+        assert_eq!(full_match("[a-z]*", &"a".repeat(20000)), Ok(true));
+    }
+}
@@ -0,0 +1,120 @@
+use std::rc::Rc;
+
+use crate::cbor_object::CborObject;
+use crate::cddl_validate::Names;
+use crate::dictionary::{self, Dictionary};
+use crate::schema::Schema;
+use crate::type_byte_string::ExpectedConversion;
+use crate::value::Value;
+
+/// State carried from a parent item to its children while building the tree.
+#[derive(Default)]
+pub struct Context {
+    /// Encoding hint from the innermost enclosing tag 21, 22 or 23.
+    pub expected_conversion: Option<ExpectedConversion>,
+    /// Set where byte strings are known to hold binary data, so that they are not shown as
+    /// embedded items even when they happen to be well-formed CBOR.
+    pub opaque_byte_strings: bool,
+    /// Strings that tag 25 can refer to, one table per enclosing tag 256.
+    pub string_namespaces: Vec<Vec<String>>,
+    /// Values marked with tag 28, in order of appearance.
+    pub shared_values: Vec<String>,
+    /// Expected structure of the item being built, set by its parent.
+    pub schema: Schema,
+    /// Items that packed CBOR references refer to, set up by the enclosing tags 113.
+    pub packing_table: Option<Vec<Value>>,
+    /// Time in seconds since the epoch at which the validity of tokens is checked.
+    pub now: Option<i64>,
+    /// Names of private tags and map keys given by the user.
+    pub dictionary: Option<Rc<Dictionary>>,
+    /// Names that a CDDL rule gives to map members and array elements, by path.
+    pub cddl_names: Option<Rc<Names>>,
+    /// Map keys and array indexes from the top-level item to the item being built, kept
+    /// only when there is a dictionary or CDDL names.
+    pub path: Vec<String>,
+    /// Tags around the item being built, innermost last.
+    pub enclosing_tags: Vec<u64>,
+}
+
+impl Context {
+    /// Adds a definite-length string to the innermost stringref namespace if it is long
+    /// enough to be worth a reference at the next index.
+    pub fn register_string(&mut self, length: usize, diagnostic: impl FnOnce() -> String) {
+        let Some(namespace) = self.string_namespaces.last_mut() else {
+            return;
+        };
+        let min_length = match namespace.len() as u64 {
+            0..24 => 3,
+            24..256 => 4,
+            256..65536 => 5,
+            65536..4294967296 => 7,
+            _ => 11,
+        };
+        if length >= min_length {
+            namespace.push(diagnostic());
+        }
+    }
+
+    /// Runs `f` with `schema` as the expected structure of the item it builds.
+    pub fn with_schema<T>(&mut self, schema: Schema, f: impl FnOnce(&mut Self) -> T) -> T {
+        let outer_schema = std::mem::replace(&mut self.schema, schema);
+        let result = f(self);
+        self.schema = outer_schema;
+        result
+    }
+
+    /// Runs `f` with the key or index `segment` added to the path of the item it builds.
+    pub fn with_path_segment<T>(
+        &mut self,
+        segment: impl FnOnce() -> String,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        if !self.keeps_path() {
+            return f(self);
+        }
+        self.path.push(segment());
+        let result = f(self);
+        self.path.pop();
+        result
+    }
+
+    /// Returns the name the dictionary gives to the key `key` of the map being built.
+    pub fn dictionary_key_name(&self, key: &CborObject) -> Option<String> {
+        let dictionary = self.dictionary.as_ref()?;
+        dictionary
+            .key_name(key, &self.path, &self.enclosing_tags)
+            .map(str::to_string)
+    }
+
+    /// Returns the name the CDDL rule gives to the key `key` of the map being built.
+    pub fn cddl_key_name(&self, key: &CborObject) -> Option<String> {
+        self.cddl_name(dictionary::segment(key))
+    }
+
+    /// Returns the name the CDDL rule gives to the member or element at `segment` of the
+    /// map or array being built.
+    pub fn cddl_name(&self, segment: String) -> Option<String> {
+        let names = self.cddl_names.as_ref()?;
+        let mut path = self.path.clone();
+        path.push(segment);
+        names.get(&path).map(str::to_string)
+    }
+
+    /// Returns the path segment of the map key `key`, when the path is kept.
+    pub fn key_segment(&self, key: &CborObject) -> Option<String> {
+        self.keeps_path().then(|| dictionary::segment(key))
+    }
+
+    fn keeps_path(&self) -> bool {
+        self.dictionary.is_some() || self.cddl_names.is_some()
+    }
+
+    /// Runs `f` outside of any stringref namespace, for the chunks of indefinite-length
+    /// strings and for embedded items, which are never added to the enclosing one.
+    pub fn without_string_namespaces<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let namespaces = std::mem::take(&mut self.string_namespaces);
+        let result = f(self);
+        self.string_namespaces = namespaces;
+        result
+    }
+}
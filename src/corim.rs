@@ -0,0 +1,181 @@
+//! Concise Reference Integrity Manifests (draft-ietf-rats-corim): the manifest, its signed
+//! form and the CoMID tags of reference values and endorsements it carries.
+
+use crate::cose;
+use crate::coswid;
+use crate::schema::{Entry, Field, Schema};
+
+pub const TAG_CORIM: u64 = 500;
+pub const TAG_UNSIGNED_CORIM: u64 = 501;
+pub const TAG_SIGNED_CORIM: u64 = 502;
+pub const TAG_COSWID: u64 = 505;
+pub const TAG_COMID: u64 = 506;
+pub const TAG_COTL: u64 = 508;
+
+const CORIM_ROLES: &[(i64, &str)] = &[(1, "manifest-creator"), (2, "manifest-signer")];
+
+const COMID_ROLES: &[(i64, &str)] = &[(0, "tag-creator"), (1, "creator"), (2, "maintainer")];
+
+const TAG_RELATIONS: &[(i64, &str)] = &[(0, "supplements"), (1, "replaces")];
+
+const VALIDITY: Schema = Schema::Map(&[
+    Entry::int(0, "not-before", Schema::Time),
+    Entry::int(1, "not-after", Schema::Time),
+]);
+
+const CORIM_ENTITY: Schema = Schema::Map(&[
+    Entry::int(0, "entity-name", Schema::Any),
+    Entry::int(1, "reg-id", Schema::Any),
+    Entry::int(2, "role", Schema::ArrayOf(&Schema::Values(CORIM_ROLES))),
+]);
+
+const COMID_ENTITY: Schema = Schema::Map(&[
+    Entry::int(0, "entity-name", Schema::Any),
+    Entry::int(1, "reg-id", Schema::Any),
+    Entry::int(2, "role", Schema::ArrayOf(&Schema::Values(COMID_ROLES))),
+]);
+
+const TAG_IDENTITY: Schema = Schema::Map(&[
+    Entry::int(0, "tag-id", Schema::Any),
+    Entry::int(1, "tag-version", Schema::Any),
+]);
+
+const CLASS: Schema = Schema::Map(&[
+    Entry::int(0, "class-id", Schema::Any),
+    Entry::int(1, "vendor", Schema::Any),
+    Entry::int(2, "model", Schema::Any),
+    Entry::int(3, "layer", Schema::Any),
+    Entry::int(4, "index", Schema::Any),
+]);
+
+const ENVIRONMENT: Schema = Schema::Map(&[
+    Entry::int(0, "class", CLASS),
+    Entry::int(1, "instance", Schema::Any),
+    Entry::int(2, "group", Schema::Any),
+]);
+
+const VERSION: Schema = Schema::Map(&[
+    Entry::int(0, "version", Schema::Any),
+    Entry::int(1, "version-scheme", Schema::Values(coswid::VERSION_SCHEMES)),
+]);
+
+const FLAGS: Schema = Schema::Map(&[
+    Entry::int(0, "is-configured", Schema::Any),
+    Entry::int(1, "is-secure", Schema::Any),
+    Entry::int(2, "is-recovery", Schema::Any),
+    Entry::int(3, "is-debug", Schema::Any),
+    Entry::int(4, "is-replay-protected", Schema::Any),
+    Entry::int(5, "is-integrity-protected", Schema::Any),
+    Entry::int(6, "is-runtime-measured", Schema::Any),
+    Entry::int(7, "is-immutable", Schema::Any),
+    Entry::int(8, "is-tcb", Schema::Any),
+    Entry::int(9, "is-confidentiality-protected", Schema::Any),
+]);
+
+const MEASUREMENT_VALUES: Schema = Schema::Map(&[
+    Entry::int(0, "version", VERSION),
+    Entry::int(1, "svn", Schema::Any),
+    Entry::int(2, "digests", Schema::ArrayOf(&coswid::HASH_ENTRY)),
+    Entry::int(3, "flags", FLAGS),
+    Entry::int(4, "raw-value", Schema::Any),
+    Entry::int(5, "raw-value-mask", Schema::Opaque),
+    Entry::int(6, "mac-addr", Schema::Opaque),
+    Entry::int(7, "ip-addr", Schema::Opaque),
+    Entry::int(8, "serial-number", Schema::Any),
+    Entry::int(9, "ueid", Schema::Opaque),
+    Entry::int(10, "uuid", Schema::Opaque),
+    Entry::int(11, "name", Schema::Any),
+    Entry::int(13, "cryptokeys", Schema::Any),
+    Entry::int(14, "integrity-registers", Schema::Any),
+]);
+
+const MEASUREMENT: Schema = Schema::Map(&[
+    Entry::int(0, "mkey", Schema::Any),
+    Entry::int(1, "mval", MEASUREMENT_VALUES),
+    Entry::int(2, "authorized-by", Schema::Any),
+]);
+
+const fn field(name: &'static str, schema: Schema) -> Field {
+    Field { name, schema }
+}
+
+/// Reference values and endorsements: an environment and its measurements.
+const MEASUREMENT_TRIPLE: Schema = Schema::Array(&[
+    field("environment", ENVIRONMENT),
+    field("measurements", Schema::ArrayOf(&MEASUREMENT)),
+]);
+
+/// Identity and attestation keys: an environment, its keys and the conditions on them.
+const KEY_TRIPLE: Schema = Schema::Array(&[
+    field("environment", ENVIRONMENT),
+    field("keys", Schema::Any),
+    field("conditions", Schema::Any),
+]);
+
+const TRIPLES: Schema = Schema::Map(&[
+    Entry::int(0, "reference-triples", Schema::ArrayOf(&MEASUREMENT_TRIPLE)),
+    Entry::int(1, "endorsed-triples", Schema::ArrayOf(&MEASUREMENT_TRIPLE)),
+    Entry::int(2, "identity-triples", Schema::ArrayOf(&KEY_TRIPLE)),
+    Entry::int(3, "attest-key-triples", Schema::ArrayOf(&KEY_TRIPLE)),
+    Entry::int(4, "dependency-triples", Schema::Any),
+    Entry::int(5, "membership-triples", Schema::Any),
+    Entry::int(6, "coswid-triples", Schema::Any),
+    Entry::int(8, "conditional-endorsement-series-triples", Schema::Any),
+    Entry::int(10, "conditional-endorsement-triples", Schema::Any),
+]);
+
+/// A concise-mid-tag.
+pub const COMID: Schema = Schema::Map(&[
+    Entry::int(0, "language", Schema::Any),
+    Entry::int(1, "tag-identity", TAG_IDENTITY),
+    Entry::int(2, "entities", Schema::ArrayOf(&COMID_ENTITY)),
+    Entry::int(
+        3,
+        "linked-tags",
+        Schema::ArrayOf(&Schema::Map(&[
+            Entry::int(0, "linked-tag-id", Schema::Any),
+            Entry::int(1, "tag-rel", Schema::Values(TAG_RELATIONS)),
+        ])),
+    ),
+    Entry::int(4, "triples", TRIPLES),
+]);
+
+/// A concise-tl-tag, which lists the tags that are valid together.
+const COTL: Schema = Schema::Map(&[
+    Entry::int(0, "tag-identity", TAG_IDENTITY),
+    Entry::int(1, "tags-list", Schema::Any),
+    Entry::int(2, "tl-validity", VALIDITY),
+]);
+
+/// An unsigned-corim-map. Its tags are tagged, so they are shown with their own structure.
+pub const CORIM: Schema = Schema::Map(&[
+    Entry::int(0, "id", Schema::Any),
+    Entry::int(1, "tags", Schema::Any),
+    Entry::int(
+        2,
+        "dependent-rims",
+        Schema::ArrayOf(&Schema::Map(&[
+            Entry::int(0, "href", Schema::Any),
+            Entry::int(1, "thumbprint", coswid::HASH_ENTRY),
+        ])),
+    ),
+    Entry::int(3, "profile", Schema::Any),
+    Entry::int(4, "rim-validity", VALIDITY),
+    Entry::int(5, "entities", Schema::ArrayOf(&CORIM_ENTITY)),
+]);
+
+/// A COSE_Sign1 message around a tagged unsigned-corim-map.
+static SIGNED_CORIM: Schema = Schema::Array(&cose::sign1_with_payload(&Schema::Any));
+
+/// Returns the name of a CoRIM tag and the structure of its content.
+pub fn tag_schema(tag: u64) -> Option<(&'static str, Schema)> {
+    match tag {
+        TAG_CORIM => Some(("corim", Schema::Any)),
+        TAG_UNSIGNED_CORIM => Some(("unsigned corim", CORIM)),
+        TAG_SIGNED_CORIM => Some(("signed corim", SIGNED_CORIM)),
+        TAG_COSWID => Some(("coswid", Schema::Embedded(&coswid::COSWID))),
+        TAG_COMID => Some(("comid", Schema::Embedded(&COMID))),
+        TAG_COTL => Some(("cotl", Schema::Embedded(&COTL))),
+        _ => None,
+    }
+}
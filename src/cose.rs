@@ -0,0 +1,428 @@
+//! CBOR Object Signing and Encryption structures (RFC 9052).
+
+use crate::cbor_parser::parse_cbor;
+use crate::cose_key;
+use crate::coswid;
+use crate::cwt;
+use crate::schema::{Entry, Field, Schema};
+use crate::senml;
+use crate::tag_magic;
+use crate::value::Value;
+
+pub const TAG_COSE_ENCRYPT0: u64 = 16;
+pub const TAG_COSE_MAC0: u64 = 17;
+pub const TAG_COSE_SIGN1: u64 = 18;
+pub const TAG_COSE_ENCRYPT: u64 = 96;
+pub const TAG_COSE_MAC: u64 = 97;
+pub const TAG_COSE_SIGN: u64 = 98;
+
+pub const CONTENT_FORMAT_CWT: u64 = 61;
+pub const CONTENT_FORMAT_COSE_KEY: u64 = 101;
+pub const CONTENT_FORMAT_COSE_KEY_SET: u64 = 102;
+
+pub const LABEL_ALG: i64 = 1;
+pub const LABEL_IV: i64 = 5;
+
+/// COSE Algorithms registry.
+pub const ALGORITHMS: &[(i64, &str)] = &[
+    (-65535, "RS1"),
+    (-259, "RS512"),
+    (-258, "RS384"),
+    (-257, "RS256"),
+    (-53, "Ed448"),
+    (-47, "ES256K"),
+    (-46, "HSS-LMS"),
+    (-45, "SHAKE256"),
+    (-44, "SHA-512"),
+    (-43, "SHA-384"),
+    (-42, "RSAES-OAEP w/ SHA-512"),
+    (-41, "RSAES-OAEP w/ SHA-256"),
+    (-40, "RSAES-OAEP w/ RFC 8017 default parameters"),
+    (-39, "PS512"),
+    (-38, "PS384"),
+    (-37, "PS256"),
+    (-36, "ES512"),
+    (-35, "ES384"),
+    (-34, "ECDH-SS + A256KW"),
+    (-33, "ECDH-SS + A192KW"),
+    (-32, "ECDH-SS + A128KW"),
+    (-31, "ECDH-ES + A256KW"),
+    (-30, "ECDH-ES + A192KW"),
+    (-29, "ECDH-ES + A128KW"),
+    (-28, "ECDH-SS + HKDF-512"),
+    (-27, "ECDH-SS + HKDF-256"),
+    (-26, "ECDH-ES + HKDF-512"),
+    (-25, "ECDH-ES + HKDF-256"),
+    (-19, "Ed25519"),
+    (-18, "SHAKE128"),
+    (-17, "SHA-512/256"),
+    (-16, "SHA-256"),
+    (-15, "SHA-256/64"),
+    (-14, "SHA-1"),
+    (-13, "direct+HKDF-AES-256"),
+    (-12, "direct+HKDF-AES-128"),
+    (-11, "direct+HKDF-SHA-512"),
+    (-10, "direct+HKDF-SHA-256"),
+    (-8, "EdDSA"),
+    (-7, "ES256"),
+    (-6, "direct"),
+    (-5, "A256KW"),
+    (-4, "A192KW"),
+    (-3, "A128KW"),
+    (1, "A128GCM"),
+    (2, "A192GCM"),
+    (3, "A256GCM"),
+    (4, "HMAC 256/64"),
+    (5, "HMAC 256/256"),
+    (6, "HMAC 384/384"),
+    (7, "HMAC 512/512"),
+    (10, "AES-CCM-16-64-128"),
+    (11, "AES-CCM-16-64-256"),
+    (12, "AES-CCM-64-64-128"),
+    (13, "AES-CCM-64-64-256"),
+    (14, "AES-MAC 128/64"),
+    (15, "AES-MAC 256/64"),
+    (24, "ChaCha20/Poly1305"),
+    (25, "AES-MAC 128/128"),
+    (26, "AES-MAC 256/128"),
+    (30, "AES-CCM-16-128-128"),
+    (31, "AES-CCM-16-128-256"),
+    (32, "AES-CCM-64-128-128"),
+    (33, "AES-CCM-64-128-256"),
+];
+
+/// COSE Header Parameters registry, including the algorithm parameters of RFC 9053.
+pub const HEADER_MAP: Schema = Schema::Map(&[
+    Entry::int(1, "alg", Schema::Values(ALGORITHMS)),
+    Entry::int(2, "crit", Schema::Any),
+    Entry::int(3, "content type", Schema::Any),
+    Entry::int(4, "kid", Schema::Opaque),
+    Entry::int(5, "IV", Schema::Opaque),
+    Entry::int(6, "Partial IV", Schema::Opaque),
+    Entry::int(7, "counter signature", Schema::Any),
+    Entry::int(9, "CounterSignature0", Schema::Opaque),
+    Entry::int(10, "kid context", Schema::Opaque),
+    Entry::int(11, "Countersignature version 2", Schema::Any),
+    Entry::int(12, "Countersignature0 version 2", Schema::Any),
+    Entry::int(13, "kcwt", Schema::Any),
+    Entry::int(14, "kccs", Schema::Any),
+    Entry::int(15, "CWT Claims", cwt::CLAIMS),
+    Entry::int(16, "typ", Schema::Any),
+    Entry::int(32, "x5bag", Schema::Opaque),
+    Entry::int(33, "x5chain", Schema::Opaque),
+    Entry::int(34, "x5t", Schema::Any),
+    Entry::int(35, "x5u", Schema::Any),
+    Entry::int(-1, "ephemeral key", cose_key::COSE_KEY),
+    Entry::int(-2, "static key", cose_key::COSE_KEY),
+    Entry::int(-3, "static key id", Schema::Opaque),
+    Entry::int(-20, "salt", Schema::Opaque),
+    Entry::int(-21, "PartyU identity", Schema::Opaque),
+    Entry::int(-22, "PartyU nonce", Schema::Opaque),
+    Entry::int(-23, "PartyU other", Schema::Opaque),
+    Entry::int(-24, "PartyV identity", Schema::Opaque),
+    Entry::int(-25, "PartyV nonce", Schema::Opaque),
+    Entry::int(-26, "PartyV other", Schema::Opaque),
+    Entry::int(-27, "x5t-sender", Schema::Any),
+    Entry::int(-28, "x5u-sender", Schema::Any),
+    Entry::int(-29, "x5chain-sender", Schema::Opaque),
+]);
+
+const PROTECTED: Field = Field {
+    name: "protected header",
+    schema: Schema::Embedded(&HEADER_MAP),
+};
+const UNPROTECTED: Field = Field {
+    name: "unprotected header",
+    schema: HEADER_MAP,
+};
+const PAYLOAD: Field = Field {
+    name: "payload",
+    schema: Schema::Any,
+};
+const CIPHERTEXT: Field = Field {
+    name: "ciphertext",
+    schema: Schema::Opaque,
+};
+
+const fn opaque(name: &'static str) -> Field {
+    Field {
+        name,
+        schema: Schema::Opaque,
+    }
+}
+
+pub static COSE_SIGN1: Schema =
+    Schema::Array(&[PROTECTED, UNPROTECTED, PAYLOAD, opaque("signature")]);
+pub static COSE_SIGN: Schema = Schema::Array(&[
+    PROTECTED,
+    UNPROTECTED,
+    PAYLOAD,
+    Field {
+        name: "signatures",
+        schema: Schema::ArrayOf(&COSE_SIGNATURE),
+    },
+]);
+static COSE_SIGNATURE: Schema = Schema::Array(&[PROTECTED, UNPROTECTED, opaque("signature")]);
+pub static COSE_MAC0: Schema = Schema::Array(&[PROTECTED, UNPROTECTED, PAYLOAD, opaque("tag")]);
+pub static COSE_MAC: Schema = Schema::Array(&[
+    PROTECTED,
+    UNPROTECTED,
+    PAYLOAD,
+    opaque("tag"),
+    Field {
+        name: "recipients",
+        schema: Schema::ArrayOf(&COSE_RECIPIENT),
+    },
+]);
+pub static COSE_ENCRYPT0: Schema = Schema::Array(&[PROTECTED, UNPROTECTED, CIPHERTEXT]);
+pub static COSE_ENCRYPT: Schema = Schema::Array(&[
+    PROTECTED,
+    UNPROTECTED,
+    CIPHERTEXT,
+    Field {
+        name: "recipients",
+        schema: Schema::ArrayOf(&COSE_RECIPIENT),
+    },
+]);
+static COSE_RECIPIENT: Schema = Schema::Array(&[
+    PROTECTED,
+    UNPROTECTED,
+    CIPHERTEXT,
+    Field {
+        name: "recipients",
+        schema: Schema::ArrayOf(&COSE_RECIPIENT),
+    },
+]);
+
+/// Messages of CBOR Web Tokens, whose payload is a claims set (RFC 8392 Section 7).
+const CWT_PAYLOAD: Field = Field {
+    name: "payload",
+    schema: Schema::Embedded(&cwt::CLAIMS),
+};
+pub static CWT_SIGN1: Schema =
+    Schema::Array(&[PROTECTED, UNPROTECTED, CWT_PAYLOAD, opaque("signature")]);
+static CWT_SIGN: Schema = Schema::Array(&[
+    PROTECTED,
+    UNPROTECTED,
+    CWT_PAYLOAD,
+    Field {
+        name: "signatures",
+        schema: Schema::ArrayOf(&COSE_SIGNATURE),
+    },
+]);
+static CWT_MAC0: Schema = Schema::Array(&[PROTECTED, UNPROTECTED, CWT_PAYLOAD, opaque("tag")]);
+static CWT_MAC: Schema = Schema::Array(&[
+    PROTECTED,
+    UNPROTECTED,
+    CWT_PAYLOAD,
+    opaque("tag"),
+    Field {
+        name: "recipients",
+        schema: Schema::ArrayOf(&COSE_RECIPIENT),
+    },
+]);
+
+/// The elements of a COSE_Sign1 message whose payload is an item with the structure
+/// `payload`, for protocols that sign their own structures.
+pub const fn sign1_with_payload(payload: &'static Schema) -> [Field; 4] {
+    [
+        PROTECTED,
+        UNPROTECTED,
+        Field {
+            name: "payload",
+            schema: Schema::Embedded(payload),
+        },
+        opaque("signature"),
+    ]
+}
+
+/// `Sig_structure` of a COSE_Sign1 message and `MAC_structure` of a COSE_Mac0 message,
+/// which are the bytes that are signed or MACed.
+pub static TO_BE_SIGNED: Schema = Schema::Array(&[
+    Field {
+        name: "context",
+        schema: Schema::Any,
+    },
+    Field {
+        name: "body protected",
+        schema: Schema::Embedded(&HEADER_MAP),
+    },
+    opaque("external aad"),
+    PAYLOAD,
+]);
+
+/// Returns the name of a COSE message tag and the structure of its content.
+pub fn tag_schema(tag: u64) -> Option<(&'static str, Schema)> {
+    match tag {
+        TAG_COSE_ENCRYPT0 => Some(("cose encrypt0", COSE_ENCRYPT0)),
+        TAG_COSE_MAC0 => Some(("cose mac0", COSE_MAC0)),
+        TAG_COSE_SIGN1 => Some(("cose sign1", COSE_SIGN1)),
+        TAG_COSE_ENCRYPT => Some(("cose encrypt", COSE_ENCRYPT)),
+        TAG_COSE_MAC => Some(("cose mac", COSE_MAC)),
+        TAG_COSE_SIGN => Some(("cose sign", COSE_SIGN)),
+        _ => None,
+    }
+}
+
+/// Returns the structure of a COSE message with the tag `tag` that carries a CWT.
+pub fn cwt_schema(tag: u64) -> Option<Schema> {
+    match tag {
+        TAG_COSE_SIGN1 => Some(CWT_SIGN1),
+        TAG_COSE_SIGN => Some(CWT_SIGN),
+        TAG_COSE_MAC0 => Some(CWT_MAC0),
+        TAG_COSE_MAC => Some(CWT_MAC),
+        // The claims set is encrypted.
+        _ => tag_schema(tag).map(|(_, schema)| schema),
+    }
+}
+
+/// Returns the structure of COSE messages and keys, and of the other items with a known
+/// structure, labeled with a CoAP Content-Format.
+pub fn content_format_schema(content_format: u64) -> Option<Schema> {
+    match content_format {
+        CONTENT_FORMAT_COSE_KEY => Some(cose_key::COSE_KEY),
+        CONTENT_FORMAT_COSE_KEY_SET => Some(cose_key::COSE_KEY_SET),
+        CONTENT_FORMAT_CWT => Some(cwt::CWT),
+        senml::CONTENT_FORMAT_SENML | senml::CONTENT_FORMAT_SENSML => Some(senml::PACK),
+        coswid::CONTENT_FORMAT_COSWID => Some(coswid::COSWID),
+        // The Content-Formats of COSE messages have the numbers of their tags.
+        _ => tag_schema(content_format).map(|(_, schema)| schema),
+    }
+}
+
+/// `Enc_structure` of a COSE_Encrypt0 or COSE_Encrypt message, which is the additional
+/// authenticated data of the content encryption.
+pub static ENC_STRUCTURE: Schema = Schema::Array(&[
+    Field {
+        name: "context",
+        schema: Schema::Any,
+    },
+    Field {
+        name: "protected",
+        schema: Schema::Embedded(&HEADER_MAP),
+    },
+    opaque("external aad"),
+]);
+
+pub fn algorithm_name(algorithm: i128) -> String {
+    ALGORITHMS
+        .iter()
+        .find(|(id, _)| i128::from(*id) == algorithm)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("algorithm {}", algorithm))
+}
+
+/// Looks through the labels of RFC 8949 Section 3.4.6 and RFC 9277, the CWT tag and the tag
+/// of a COSE message for its content, returning the tag, or `untagged` if there is none.
+pub fn message_content(mut value: &Value, untagged: Option<u64>) -> Result<(u64, &Value), String> {
+    let mut message_tag = untagged;
+    while let Value::Tag(tag, content) = value {
+        let tag = tag_magic::content_format(*tag).unwrap_or(*tag);
+        if tag_schema(tag).is_some() {
+            message_tag = Some(tag);
+        } else if tag != tag_magic::TAG_SELF_DESCRIBED_CBOR && tag != cwt::TAG_CWT {
+            return Err(format!("tag {} is not a COSE message tag", tag));
+        }
+        value = content;
+    }
+    match message_tag {
+        Some(tag) => Ok((tag, value)),
+        None => Err("untagged message, use --profile to tell its type".to_string()),
+    }
+}
+
+/// The protected and unprotected header parameters of a message, signer or recipient.
+pub struct Headers {
+    /// The serialized protected header, as it is signed, MACed or authenticated.
+    pub protected: Vec<u8>,
+    protected_map: Value,
+    unprotected_map: Value,
+}
+
+impl Headers {
+    pub fn new(protected: &Value, unprotected: &Value) -> Result<Self, String> {
+        let Value::Bytes(protected) = protected else {
+            return Err("expected byte string protected header".to_string());
+        };
+        let protected_map = match &protected[..] {
+            [] => Value::Map(vec![]),
+            protected => match parse_cbor(protected) {
+                Ok(([], object)) => Value::from_object(&object)?,
+                _ => return Err("protected header is not a well-formed item".to_string()),
+            },
+        };
+        Ok(Headers {
+            protected: protected.clone(),
+            protected_map,
+            unprotected_map: unprotected.clone(),
+        })
+    }
+
+    pub fn get(&self, label: i64) -> Option<&Value> {
+        self.protected_map
+            .get(label)
+            .or_else(|| self.unprotected_map.get(label))
+    }
+
+    pub fn algorithm(&self) -> Result<i128, String> {
+        self.get(LABEL_ALG)
+            .and_then(Value::as_integer)
+            .ok_or_else(|| "missing alg header parameter".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cbor_object::ToTree;
+    use crate::cbor_parser::parse_cbor;
+    use crate::context::Context;
+    use crate::tree::Node;
+
+    #[test]
+    fn untagged_encrypt0() {
+        let input = b"\x83\x43\xa1\x01\x01\xa1\x05\x41\xa0\x41\xa0";
+        let (_, object) = parse_cbor(input).unwrap();
+        let mut context = Context {
+            schema: COSE_ENCRYPT0,
+            ..Default::default()
+        };
+        // Only the protected header is embedded, the IV and the ciphertext are binary data.
+        let expected = Node::new(vec![0x83])
+            .with_comment("array(0x3 = 3)")
+            .with_children(vec![
+                Node::new(vec![0x43])
+                    .with_comment("bstr(0x3 = 3)")
+                    .with_annotation("protected header")
+                    .with_child(
+                        Node::new(vec![0xa1])
+                            .with_comment("map(0x1 = 1)")
+                            .with_children(vec![
+                                Node::new(vec![0x01])
+                                    .with_comment("unsigned(0x1) = 1")
+                                    .with_annotation("alg"),
+                                Node::new(vec![0x01])
+                                    .with_comment("unsigned(0x1) = 1")
+                                    .with_annotation("A128GCM"),
+                            ])
+                            .mark_embedded(),
+                    ),
+                Node::new(vec![0xa1])
+                    .with_comment("map(0x1 = 1)")
+                    .with_annotation("unprotected header")
+                    .with_children(vec![
+                        Node::new(vec![0x05])
+                            .with_comment("unsigned(0x5) = 5")
+                            .with_annotation("IV"),
+                        Node::new(vec![0x41])
+                            .with_comment("bstr(0x1 = 1)")
+                            .with_child(Node::new(vec![0xa0]).with_comment("\"\\xA0\"")),
+                    ]),
+                Node::new(vec![0x41])
+                    .with_comment("bstr(0x1 = 1)")
+                    .with_annotation("ciphertext")
+                    .with_child(Node::new(vec![0xa0]).with_comment("\"\\xA0\"")),
+            ]);
+        assert_eq!(object.into_tree_with_context(&mut context), expected);
+    }
+}
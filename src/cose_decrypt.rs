@@ -0,0 +1,292 @@
+//! Decryption of COSE_Encrypt0 and COSE_Encrypt messages (RFC 9052 Section 5).
+
+use aes::{Aes128, Aes192, Aes256};
+use aes_gcm::aead::consts::{U12, U13, U16, U7, U8};
+use aes_gcm::aead::{Aead, KeyInit, Nonce, Payload};
+use aes_gcm::AesGcm;
+use aes_kw::{KwAes128, KwAes192, KwAes256};
+use ccm::Ccm;
+use chacha20poly1305::ChaCha20Poly1305;
+
+use crate::cose::{self, algorithm_name, Headers, LABEL_IV};
+use crate::cose_key::Key;
+use crate::value::Value;
+
+pub const ALG_A128GCM: i128 = 1;
+pub const ALG_A192GCM: i128 = 2;
+pub const ALG_A256GCM: i128 = 3;
+pub const ALG_AES_CCM_16_64_128: i128 = 10;
+pub const ALG_AES_CCM_16_64_256: i128 = 11;
+pub const ALG_AES_CCM_64_64_128: i128 = 12;
+pub const ALG_AES_CCM_64_64_256: i128 = 13;
+pub const ALG_CHACHA20_POLY1305: i128 = 24;
+pub const ALG_AES_CCM_16_128_128: i128 = 30;
+pub const ALG_AES_CCM_16_128_256: i128 = 31;
+pub const ALG_AES_CCM_64_128_128: i128 = 32;
+pub const ALG_AES_CCM_64_128_256: i128 = 33;
+pub const ALG_DIRECT: i128 = -6;
+pub const ALG_A128KW: i128 = -3;
+pub const ALG_A192KW: i128 = -4;
+pub const ALG_A256KW: i128 = -5;
+
+struct Recipient {
+    headers: Headers,
+    encrypted_key: Vec<u8>,
+}
+
+pub struct EncryptedMessage {
+    pub tag: u64,
+    pub headers: Headers,
+    ciphertext: Vec<u8>,
+    recipients: Vec<Recipient>,
+}
+
+impl EncryptedMessage {
+    /// Reads a COSE_Encrypt0 or COSE_Encrypt message, which is of the type of the tag
+    /// `untagged` if it is not tagged.
+    pub fn from_value(value: &Value, untagged: Option<u64>) -> Result<Self, String> {
+        let (tag, content) = cose::message_content(value, untagged)?;
+        let Value::Array(items) = content else {
+            return Err("expected array".to_string());
+        };
+        let (protected, unprotected, ciphertext, recipients) = match (tag, &items[..]) {
+            (cose::TAG_COSE_ENCRYPT0, [protected, unprotected, ciphertext]) => {
+                (protected, unprotected, ciphertext, &[][..])
+            }
+            (
+                cose::TAG_COSE_ENCRYPT,
+                [protected, unprotected, ciphertext, Value::Array(recipients)],
+            ) => (protected, unprotected, ciphertext, &recipients[..]),
+            (cose::TAG_COSE_ENCRYPT0, _) => {
+                return Err("expected [protected, unprotected, ciphertext]".to_string())
+            }
+            (cose::TAG_COSE_ENCRYPT, _) => {
+                return Err("expected [protected, unprotected, ciphertext, recipients]".to_string())
+            }
+            _ => return Err("not a COSE_Encrypt0 or COSE_Encrypt message".to_string()),
+        };
+        let ciphertext = match ciphertext {
+            Value::Bytes(ciphertext) => ciphertext.clone(),
+            Value::Simple(_) => return Err("detached ciphertext is not supported".to_string()),
+            _ => return Err("expected byte string ciphertext".to_string()),
+        };
+        let recipients = recipients
+            .iter()
+            .map(|recipient| match recipient {
+                Value::Array(items) => match &items[..] {
+                    [protected, unprotected, Value::Bytes(encrypted_key), ..] => Ok(Recipient {
+                        headers: Headers::new(protected, unprotected)?,
+                        encrypted_key: encrypted_key.clone(),
+                    }),
+                    _ => Err("expected recipient [protected, unprotected, ciphertext]".to_string()),
+                },
+                _ => Err("expected recipient array".to_string()),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(EncryptedMessage {
+            tag,
+            headers: Headers::new(protected, unprotected)?,
+            ciphertext,
+            recipients,
+        })
+    }
+
+    /// Encodes the `Enc_structure` of the message.
+    pub fn enc_structure(&self, external_aad: &[u8]) -> Vec<u8> {
+        let context = match self.tag {
+            cose::TAG_COSE_ENCRYPT0 => "Encrypt0",
+            _ => "Encrypt",
+        };
+        Value::Array(vec![
+            Value::Text(context.to_string()),
+            Value::Bytes(self.headers.protected.clone()),
+            Value::Bytes(external_aad.to_vec()),
+        ])
+        .encode()
+    }
+}
+
+fn unwrap_key(algorithm: i128, kek: &[u8], encrypted_key: &[u8]) -> Result<Vec<u8>, String> {
+    let mut key = vec![0; encrypted_key.len().saturating_sub(aes_kw::IV_LEN)];
+    let unwrapped = match algorithm {
+        ALG_A128KW => KwAes128::new_from_slice(kek)
+            .map_err(|_| "expected 16 bytes key".to_string())?
+            .unwrap_key(encrypted_key, &mut key)
+            .map(<[u8]>::len),
+        ALG_A192KW => KwAes192::new_from_slice(kek)
+            .map_err(|_| "expected 24 bytes key".to_string())?
+            .unwrap_key(encrypted_key, &mut key)
+            .map(<[u8]>::len),
+        ALG_A256KW => KwAes256::new_from_slice(kek)
+            .map_err(|_| "expected 32 bytes key".to_string())?
+            .unwrap_key(encrypted_key, &mut key)
+            .map(<[u8]>::len),
+        _ => return Err(format!("{} is not supported", algorithm_name(algorithm))),
+    };
+    let length = unwrapped.map_err(|err| format!("key unwrapping failed: {}", err))?;
+    key.truncate(length);
+    Ok(key)
+}
+
+/// Recovers the content encryption key, which is `key` itself for COSE_Encrypt0 and is
+/// given by the first recipient that `key` opens for COSE_Encrypt.
+fn content_key(message: &EncryptedMessage, key: &Key) -> Result<Vec<u8>, String> {
+    let Key::Symmetric { k } = key else {
+        return Err("expected symmetric key".to_string());
+    };
+    if message.tag == cose::TAG_COSE_ENCRYPT0 {
+        return Ok(k.clone());
+    }
+    let mut errors = vec![];
+    for recipient in &message.recipients {
+        let content_key = recipient
+            .headers
+            .algorithm()
+            .and_then(|algorithm| match algorithm {
+                ALG_DIRECT if recipient.encrypted_key.is_empty() => Ok(k.clone()),
+                ALG_DIRECT => Err("expected empty ciphertext for direct".to_string()),
+                _ => unwrap_key(algorithm, k, &recipient.encrypted_key),
+            });
+        match content_key {
+            Ok(content_key) => return Ok(content_key),
+            Err(err) => errors.push(err),
+        }
+    }
+    match errors.is_empty() {
+        true => Err("no recipients".to_string()),
+        false => Err(format!(
+            "no recipient could be opened ({})",
+            errors.join(", ")
+        )),
+    }
+}
+
+fn aead_decrypt<A: Aead + KeyInit>(
+    key: &[u8],
+    iv: &[u8],
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, String> {
+    let cipher = A::new_from_slice(key).map_err(|_| "invalid key length".to_string())?;
+    let nonce = Nonce::<A>::try_from(iv).map_err(|_| "invalid IV length".to_string())?;
+    cipher
+        .decrypt(
+            &nonce,
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| "authentication failed".to_string())
+}
+
+/// Decrypts the ciphertext of the message with `enc_structure` as additional authenticated
+/// data.
+pub fn decrypt(
+    message: &EncryptedMessage,
+    enc_structure: &[u8],
+    key: &Key,
+) -> Result<Vec<u8>, String> {
+    let algorithm = message.headers.algorithm()?;
+    let Some(Value::Bytes(iv)) = message.headers.get(LABEL_IV) else {
+        return Err("missing IV header parameter".to_string());
+    };
+    let content_key = content_key(message, key)?;
+    let (key, ciphertext) = (&content_key[..], &message.ciphertext[..]);
+    match algorithm {
+        ALG_A128GCM => aead_decrypt::<AesGcm<Aes128, U12>>(key, iv, ciphertext, enc_structure),
+        ALG_A192GCM => aead_decrypt::<AesGcm<Aes192, U12>>(key, iv, ciphertext, enc_structure),
+        ALG_A256GCM => aead_decrypt::<AesGcm<Aes256, U12>>(key, iv, ciphertext, enc_structure),
+        ALG_AES_CCM_16_64_128 => {
+            aead_decrypt::<Ccm<Aes128, U8, U13>>(key, iv, ciphertext, enc_structure)
+        }
+        ALG_AES_CCM_16_64_256 => {
+            aead_decrypt::<Ccm<Aes256, U8, U13>>(key, iv, ciphertext, enc_structure)
+        }
+        ALG_AES_CCM_64_64_128 => {
+            aead_decrypt::<Ccm<Aes128, U8, U7>>(key, iv, ciphertext, enc_structure)
+        }
+        ALG_AES_CCM_64_64_256 => {
+            aead_decrypt::<Ccm<Aes256, U8, U7>>(key, iv, ciphertext, enc_structure)
+        }
+        ALG_AES_CCM_16_128_128 => {
+            aead_decrypt::<Ccm<Aes128, U16, U13>>(key, iv, ciphertext, enc_structure)
+        }
+        ALG_AES_CCM_16_128_256 => {
+            aead_decrypt::<Ccm<Aes256, U16, U13>>(key, iv, ciphertext, enc_structure)
+        }
+        ALG_AES_CCM_64_128_128 => {
+            aead_decrypt::<Ccm<Aes128, U16, U7>>(key, iv, ciphertext, enc_structure)
+        }
+        ALG_AES_CCM_64_128_256 => {
+            aead_decrypt::<Ccm<Aes256, U16, U7>>(key, iv, ciphertext, enc_structure)
+        }
+        ALG_CHACHA20_POLY1305 => {
+            aead_decrypt::<ChaCha20Poly1305>(key, iv, ciphertext, enc_structure)
+        }
+        _ => Err(format!("{} is not supported", algorithm_name(algorithm))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::parse_item;
+
+    const PLAINTEXT: &[u8] = b"\xa1\x01\x65hello";
+
+    fn message(hex: &str, untagged: Option<u64>) -> EncryptedMessage {
+        let object = parse_item(hex);
+        EncryptedMessage::from_value(&Value::from_object(&object).unwrap(), untagged).unwrap()
+    }
+
+    fn decrypt_with(message: &EncryptedMessage, k: Vec<u8>, external_aad: &[u8]) -> Vec<u8> {
+        let enc_structure = message.enc_structure(external_aad);
+        decrypt(message, &enc_structure, &Key::Symmetric { k }).unwrap()
+    }
+
+    #[test]
+    fn decrypt_a128gcm() {
+        let message = message(
+            "d08343a10101a1054c000102030405060708090a0b5818\
+             326dc2a603779b3b761dd3d3a09e6d6437164a8f3b79b591",
+            None,
+        );
+        assert_eq!(decrypt_with(&message, (0..16).collect(), b""), PLAINTEXT);
+        let enc_structure = message.enc_structure(b"aad");
+        assert_eq!(
+            decrypt(
+                &message,
+                &enc_structure,
+                &Key::Symmetric {
+                    k: (0..16).collect()
+                }
+            ),
+            Err("authentication failed".to_string())
+        );
+    }
+
+    #[test]
+    fn decrypt_key_wrap_recipient() {
+        let message = message(
+            "d8608443a1010aa1054d000102030405060708090a0b0c50\
+             c0ccd889f39d1e730aa1ba8789f22a5881\
+             8340a1012258185e82273849554ea0e7f64b1189aa442b71736182d838bb53",
+            None,
+        );
+        assert_eq!(decrypt_with(&message, (16..32).collect(), b""), PLAINTEXT);
+    }
+
+    #[test]
+    fn decrypt_untagged_chacha20_poly1305() {
+        let message = message(
+            "8344a1011818a1054c000102030405060708090a0b581a\
+             f99769694737d125cff78ac94a2ed6e70998fa8062e560f77b76",
+            Some(cose::TAG_COSE_ENCRYPT0),
+        );
+        assert_eq!(
+            decrypt_with(&message, (0..32).collect(), b"x"),
+            b"plain text"
+        );
+    }
+}
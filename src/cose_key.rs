@@ -0,0 +1,618 @@
+//! Keys read from a COSE_Key (RFC 9052 Section 7), JWK (RFC 7517) or PEM file, and the names
+//! of the parameters of COSE_Key maps.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+use ed25519_dalek::pkcs8::{DecodePublicKey as _, EncodePublicKey as _};
+use sha2::{Digest as _, Sha256};
+
+use crate::cbor_object::CborObject;
+use crate::cbor_parser::parse_cbor;
+use crate::context::Context;
+use crate::cose::ALGORITHMS;
+use crate::schema::{Entry, Schema};
+use crate::value::Value;
+
+pub const KTY_OKP: i64 = 1;
+pub const KTY_EC2: i64 = 2;
+pub const KTY_RSA: i64 = 3;
+pub const KTY_SYMMETRIC: i64 = 4;
+
+pub const LABEL_KTY: i64 = 1;
+pub const LABEL_CRV: i64 = -1;
+pub const LABEL_X: i64 = -2;
+pub const LABEL_Y: i64 = -3;
+pub const LABEL_K: i64 = -1;
+pub const LABEL_N: i64 = -1;
+pub const LABEL_E: i64 = -2;
+
+/// COSE Key Types registry.
+const KEY_TYPES: &[(i64, &str)] = &[
+    (1, "OKP"),
+    (2, "EC2"),
+    (3, "RSA"),
+    (4, "Symmetric"),
+    (5, "HSS-LMS"),
+    (6, "WalnutDSA"),
+];
+
+/// COSE Elliptic Curves registry.
+const CURVES: &[(i64, &str)] = &[
+    (1, "P-256"),
+    (2, "P-384"),
+    (3, "P-521"),
+    (4, "X25519"),
+    (5, "X448"),
+    (6, "Ed25519"),
+    (7, "Ed448"),
+    (8, "secp256k1"),
+];
+
+const KEY_OPS: &[(i64, &str)] = &[
+    (1, "sign"),
+    (2, "verify"),
+    (3, "encrypt"),
+    (4, "decrypt"),
+    (5, "wrap key"),
+    (6, "unwrap key"),
+    (7, "derive key"),
+    (8, "derive bits"),
+    (9, "MAC create"),
+    (10, "MAC verify"),
+];
+
+const KTY: Entry = Entry::int(1, "kty", Schema::Values(KEY_TYPES));
+const KID: Entry = Entry::int(2, "kid", Schema::Opaque);
+const ALG: Entry = Entry::int(3, "alg", Schema::Values(ALGORITHMS));
+const OPS: Entry = Entry::int(4, "key_ops", Schema::ArrayOf(&Schema::Values(KEY_OPS)));
+const BASE_IV: Entry = Entry::int(5, "Base IV", Schema::Opaque);
+const CRV: Entry = Entry::int(-1, "crv", Schema::Values(CURVES));
+
+/// The common parameters and the parameters of each key type.
+const OKP_KEY: Schema = Schema::Map(&[
+    KTY,
+    KID,
+    ALG,
+    OPS,
+    BASE_IV,
+    CRV,
+    Entry::int(-2, "x", Schema::Opaque),
+    Entry::int(-4, "d", Schema::Opaque),
+]);
+const EC2_KEY: Schema = Schema::Map(&[
+    KTY,
+    KID,
+    ALG,
+    OPS,
+    BASE_IV,
+    CRV,
+    Entry::int(-2, "x", Schema::Opaque),
+    Entry::int(-3, "y", Schema::Opaque),
+    Entry::int(-4, "d", Schema::Opaque),
+]);
+const RSA_KEY: Schema = Schema::Map(&[
+    KTY,
+    KID,
+    ALG,
+    OPS,
+    BASE_IV,
+    Entry::int(-1, "n", Schema::Opaque),
+    Entry::int(-2, "e", Schema::Opaque),
+    Entry::int(-3, "d", Schema::Opaque),
+    Entry::int(-4, "p", Schema::Opaque),
+    Entry::int(-5, "q", Schema::Opaque),
+    Entry::int(-6, "dP", Schema::Opaque),
+    Entry::int(-7, "dQ", Schema::Opaque),
+    Entry::int(-8, "qInv", Schema::Opaque),
+    Entry::int(-9, "other", Schema::Any),
+]);
+const SYMMETRIC_KEY: Schema = Schema::Map(&[
+    KTY,
+    KID,
+    ALG,
+    OPS,
+    BASE_IV,
+    Entry::int(-1, "k", Schema::Opaque),
+]);
+const OTHER_KEY: Schema = Schema::Map(&[KTY, KID, ALG, OPS, BASE_IV]);
+
+/// Names the parameters of a COSE_Key by its key type and describes the key.
+fn select(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    let value = match Value::from_object(object) {
+        Ok(value @ Value::Map(_)) => value,
+        Ok(_) => {
+            return (
+                Schema::Any,
+                Some("invalid cose key: expected map".to_string()),
+            )
+        }
+        Err(err) => return (Schema::Any, Some(format!("invalid cose key: {}", err))),
+    };
+    let kty = value.get(LABEL_KTY).and_then(Value::as_integer);
+    let schema = match kty.and_then(|kty| i64::try_from(kty).ok()) {
+        Some(KTY_OKP) => OKP_KEY,
+        Some(KTY_EC2) => EC2_KEY,
+        Some(KTY_RSA) => RSA_KEY,
+        Some(KTY_SYMMETRIC) => SYMMETRIC_KEY,
+        _ => OTHER_KEY,
+    };
+    let description = match Key::from_cose_key(&value) {
+        Ok(key) => key.description(),
+        Err(err) => format!("invalid cose key: {}", err),
+    };
+    (schema, Some(description))
+}
+
+pub const COSE_KEY: Schema = Schema::Select(select);
+pub static COSE_KEY_SET: Schema = Schema::ArrayOf(&COSE_KEY);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    P256,
+    P384,
+    P521,
+    X25519,
+    X448,
+    Ed25519,
+    Ed448,
+    Secp256k1,
+}
+
+impl Curve {
+    const ALL: [Curve; 8] = [
+        Curve::P256,
+        Curve::P384,
+        Curve::P521,
+        Curve::X25519,
+        Curve::X448,
+        Curve::Ed25519,
+        Curve::Ed448,
+        Curve::Secp256k1,
+    ];
+
+    fn from_cose(crv: i128) -> Result<Self, String> {
+        Curve::ALL
+            .into_iter()
+            .find(|curve| i128::from(curve.cose()) == crv)
+            .ok_or_else(|| format!("unknown curve {}", crv))
+    }
+
+    fn from_jwk(crv: &str) -> Result<Self, String> {
+        Curve::ALL
+            .into_iter()
+            .find(|curve| curve.name() == crv)
+            .ok_or_else(|| format!("unknown curve {:?}", crv))
+    }
+
+    fn cose(&self) -> u64 {
+        match self {
+            Curve::P256 => 1,
+            Curve::P384 => 2,
+            Curve::P521 => 3,
+            Curve::X25519 => 4,
+            Curve::X448 => 5,
+            Curve::Ed25519 => 6,
+            Curve::Ed448 => 7,
+            Curve::Secp256k1 => 8,
+        }
+    }
+
+    /// The name of the curve, which is the same in the COSE and JOSE registries.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Curve::P256 => "P-256",
+            Curve::P384 => "P-384",
+            Curve::P521 => "P-521",
+            Curve::X25519 => "X25519",
+            Curve::X448 => "X448",
+            Curve::Ed25519 => "Ed25519",
+            Curve::Ed448 => "Ed448",
+            Curve::Secp256k1 => "secp256k1",
+        }
+    }
+
+    fn is_okp(&self) -> bool {
+        matches!(
+            self,
+            Curve::X25519 | Curve::X448 | Curve::Ed25519 | Curve::Ed448
+        )
+    }
+
+    /// The length of a coordinate in bytes.
+    fn coordinate_length(&self) -> usize {
+        match self {
+            Curve::P256 | Curve::Secp256k1 | Curve::X25519 | Curve::Ed25519 => 32,
+            Curve::P384 => 48,
+            Curve::P521 => 66,
+            Curve::X448 => 56,
+            Curve::Ed448 => 57,
+        }
+    }
+
+    fn check_coordinate(&self, name: &str, coordinate: &[u8]) -> Result<(), String> {
+        if coordinate.len() != self.coordinate_length() {
+            return Err(format!(
+                "{} is {} bytes, expected {} for {}",
+                name,
+                coordinate.len(),
+                self.coordinate_length(),
+                self.name()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Key {
+    Ec2 {
+        curve: Curve,
+        x: Vec<u8>,
+        y: Vec<u8>,
+    },
+    Okp {
+        curve: Curve,
+        x: Vec<u8>,
+    },
+    Rsa {
+        n: Vec<u8>,
+        e: Vec<u8>,
+    },
+    Symmetric {
+        k: Vec<u8>,
+    },
+}
+
+impl Key {
+    /// Reads a key from the content of a file, telling the format from its first bytes.
+    pub fn from_file_content(content: &[u8]) -> Result<Self, String> {
+        let text = std::str::from_utf8(content).ok().map(str::trim_start);
+        match text {
+            Some(text) if text.starts_with("-----BEGIN") => Key::from_pem(text),
+            Some(text) if text.starts_with('{') => Key::from_jwk(text),
+            _ => {
+                let Ok((_, object)) = parse_cbor(content) else {
+                    return Err("not a PEM, JWK or COSE_Key file".to_string());
+                };
+                Key::from_cose_key(&Value::from_object(&object)?)
+            }
+        }
+    }
+
+    /// Reads the public or symmetric key of a COSE_Key, checking that it has the parameters
+    /// its key type requires and that they fit its curve.
+    pub fn from_cose_key(key: &Value) -> Result<Self, String> {
+        let bytes = |label: i64, name: &str| {
+            key.get(label)
+                .and_then(Value::as_bytes)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| format!("missing {} parameter", name))
+        };
+        let curve = || {
+            key.get(LABEL_CRV)
+                .and_then(Value::as_integer)
+                .ok_or_else(|| "missing crv parameter".to_string())
+                .and_then(Curve::from_cose)
+        };
+        let Some(kty) = key.get(LABEL_KTY).and_then(Value::as_integer) else {
+            return Err("missing kty parameter".to_string());
+        };
+        let key = match i64::try_from(kty) {
+            Ok(KTY_OKP) => Key::Okp {
+                curve: curve()?,
+                x: bytes(LABEL_X, "x")?,
+            },
+            Ok(KTY_EC2) => {
+                if let Some(Value::Simple(_)) = key.get(LABEL_Y) {
+                    return Err("compressed points are not supported".to_string());
+                }
+                Key::Ec2 {
+                    curve: curve()?,
+                    x: bytes(LABEL_X, "x")?,
+                    y: bytes(LABEL_Y, "y")?,
+                }
+            }
+            Ok(KTY_RSA) => Key::Rsa {
+                n: bytes(LABEL_N, "n")?,
+                e: bytes(LABEL_E, "e")?,
+            },
+            Ok(KTY_SYMMETRIC) => Key::Symmetric {
+                k: bytes(LABEL_K, "k")?,
+            },
+            _ => return Err(format!("unsupported key type {}", kty)),
+        };
+        key.check()?;
+        Ok(key)
+    }
+
+    pub fn from_jwk(text: &str) -> Result<Self, String> {
+        let jwk: serde_json::Value = serde_json::from_str(text).map_err(|err| err.to_string())?;
+        let string = |name: &str| {
+            jwk.get(name)
+                .and_then(serde_json::Value::as_str)
+                .ok_or_else(|| format!("missing {} member", name))
+        };
+        let bytes = |name: &str| {
+            URL_SAFE_NO_PAD
+                .decode(string(name)?)
+                .map_err(|err| format!("invalid {} member: {}", name, err))
+        };
+        let key = match string("kty")? {
+            "OKP" => Key::Okp {
+                curve: Curve::from_jwk(string("crv")?)?,
+                x: bytes("x")?,
+            },
+            "EC" => Key::Ec2 {
+                curve: Curve::from_jwk(string("crv")?)?,
+                x: bytes("x")?,
+                y: bytes("y")?,
+            },
+            "RSA" => Key::Rsa {
+                n: bytes("n")?,
+                e: bytes("e")?,
+            },
+            "oct" => Key::Symmetric { k: bytes("k")? },
+            kty => return Err(format!("unsupported key type {:?}", kty)),
+        };
+        key.check()?;
+        Ok(key)
+    }
+
+    /// Reads a SubjectPublicKeyInfo ("PUBLIC KEY") of a P-256, P-384 or Ed25519 key.
+    pub fn from_pem(text: &str) -> Result<Self, String> {
+        let ec2 = |curve: Curve, point: &[u8]| {
+            // The point is uncompressed: 0x04 || x || y.
+            let (x, y) = point[1..].split_at((point.len() - 1) / 2);
+            Key::Ec2 {
+                curve,
+                x: x.to_vec(),
+                y: y.to_vec(),
+            }
+        };
+        if let Ok(key) = p256::PublicKey::from_public_key_pem(text) {
+            return Ok(ec2(Curve::P256, &key.to_sec1_bytes()));
+        }
+        if let Ok(key) = p384::PublicKey::from_public_key_pem(text) {
+            return Ok(ec2(Curve::P384, &key.to_sec1_bytes()));
+        }
+        if let Ok(key) = ed25519_dalek::VerifyingKey::from_public_key_pem(text) {
+            return Ok(Key::Okp {
+                curve: Curve::Ed25519,
+                x: key.to_bytes().to_vec(),
+            });
+        }
+        Err("unsupported PEM public key".to_string())
+    }
+
+    fn check(&self) -> Result<(), String> {
+        match self {
+            Key::Ec2 { curve, x, y } => {
+                if curve.is_okp() {
+                    return Err(format!("{} is not a curve for EC2 keys", curve.name()));
+                }
+                curve.check_coordinate("x", x)?;
+                curve.check_coordinate("y", y)
+            }
+            Key::Okp { curve, x } => {
+                if !curve.is_okp() {
+                    return Err(format!("{} is not a curve for OKP keys", curve.name()));
+                }
+                curve.check_coordinate("x", x)
+            }
+            Key::Rsa { n, e } if n.is_empty() || e.is_empty() => {
+                Err("empty RSA modulus or exponent".to_string())
+            }
+            Key::Symmetric { k } if k.is_empty() => Err("empty symmetric key".to_string()),
+            Key::Rsa { .. } | Key::Symmetric { .. } => Ok(()),
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Key::Ec2 { curve, .. } => format!("EC2 key on {}", curve.name()),
+            Key::Okp { curve, .. } => format!("OKP key on {}", curve.name()),
+            Key::Rsa { n, .. } => {
+                let leading_zeros = n.iter().take_while(|byte| **byte == 0).count();
+                let n = &n[leading_zeros..];
+                let bits = n.len() * 8 - n.first().map_or(0, |byte| byte.leading_zeros() as usize);
+                format!("RSA key, {} bits", bits)
+            }
+            Key::Symmetric { k } => format!("symmetric key, {} bits", k.len() * 8),
+        }
+    }
+
+    /// Encodes the COSE_Key made of the required parameters of the key type only, in the
+    /// deterministic encoding RFC 9679 Section 3 hashes.
+    fn required_parameters(&self) -> Vec<u8> {
+        let label = |label: i64| match label {
+            0.. => Value::Unsigned(label as u64),
+            _ => Value::Negative((-1 - label) as u64),
+        };
+        let bytes = |bytes: &[u8]| Value::Bytes(bytes.to_vec());
+        let parameters = match self {
+            Key::Ec2 { curve, x, y } => vec![
+                (label(LABEL_KTY), label(KTY_EC2)),
+                (label(LABEL_CRV), Value::Unsigned(curve.cose())),
+                (label(LABEL_X), bytes(x)),
+                (label(LABEL_Y), bytes(y)),
+            ],
+            Key::Okp { curve, x } => vec![
+                (label(LABEL_KTY), label(KTY_OKP)),
+                (label(LABEL_CRV), Value::Unsigned(curve.cose())),
+                (label(LABEL_X), bytes(x)),
+            ],
+            Key::Rsa { n, e } => vec![
+                (label(LABEL_KTY), label(KTY_RSA)),
+                (label(LABEL_N), bytes(n)),
+                (label(LABEL_E), bytes(e)),
+            ],
+            Key::Symmetric { k } => vec![
+                (label(LABEL_KTY), label(KTY_SYMMETRIC)),
+                (label(LABEL_K), bytes(k)),
+            ],
+        };
+        Value::Map(parameters).encode()
+    }
+
+    /// Computes the COSE Key Thumbprint (RFC 9679) with SHA-256.
+    pub fn thumbprint(&self) -> Vec<u8> {
+        Sha256::digest(self.required_parameters()).to_vec()
+    }
+
+    /// Writes the key as a JWK with the required members of its key type.
+    pub fn to_jwk(&self) -> String {
+        let base64 = |bytes: &[u8]| serde_json::Value::from(URL_SAFE_NO_PAD.encode(bytes));
+        let mut jwk = serde_json::Map::new();
+        match self {
+            Key::Ec2 { curve, x, y } => {
+                jwk.insert("kty".to_string(), "EC".into());
+                jwk.insert("crv".to_string(), curve.name().into());
+                jwk.insert("x".to_string(), base64(x));
+                jwk.insert("y".to_string(), base64(y));
+            }
+            Key::Okp { curve, x } => {
+                jwk.insert("kty".to_string(), "OKP".into());
+                jwk.insert("crv".to_string(), curve.name().into());
+                jwk.insert("x".to_string(), base64(x));
+            }
+            Key::Rsa { n, e } => {
+                jwk.insert("kty".to_string(), "RSA".into());
+                jwk.insert("n".to_string(), base64(n));
+                jwk.insert("e".to_string(), base64(e));
+            }
+            Key::Symmetric { k } => {
+                jwk.insert("kty".to_string(), "oct".into());
+                jwk.insert("k".to_string(), base64(k));
+            }
+        }
+        // unwrap safety: a map of strings always serializes
+        serde_json::to_string_pretty(&jwk).unwrap()
+    }
+
+    /// Writes a P-256, P-384 or Ed25519 public key as a PEM SubjectPublicKeyInfo.
+    pub fn to_pem(&self) -> Result<String, String> {
+        let invalid = || format!("invalid {}", self.description());
+        let pem = match self {
+            Key::Ec2 {
+                curve: Curve::P256,
+                x,
+                y,
+            } => p256::PublicKey::from_sec1_bytes(&[&[4], &x[..], &y[..]].concat())
+                .map_err(|_| invalid())?
+                .to_public_key_pem(LineEnding::LF),
+            Key::Ec2 {
+                curve: Curve::P384,
+                x,
+                y,
+            } => p384::PublicKey::from_sec1_bytes(&[&[4], &x[..], &y[..]].concat())
+                .map_err(|_| invalid())?
+                .to_public_key_pem(LineEnding::LF),
+            Key::Okp {
+                curve: Curve::Ed25519,
+                x,
+            } => <&[u8; 32]>::try_from(&x[..])
+                .ok()
+                .and_then(|x| ed25519_dalek::VerifyingKey::from_bytes(x).ok())
+                .ok_or_else(invalid)?
+                .to_public_key_pem(LineEnding::LF),
+            _ => {
+                return Err(format!(
+                    "PEM export of {} is not supported",
+                    self.description()
+                ))
+            }
+        };
+        pem.map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_hex;
+
+    // RFC 9052 Appendix C.7.1, key "11"
+    const X: &str = "usWxHK2PmfnHKwXPS54m0kTcGJ90UiglWiGahtagnv8";
+    const Y: &str = "IBOL-C3BttVivg-lSreASjpkttcsz-1rb7btKLv8EX4";
+    const PEM: &str = "-----BEGIN PUBLIC KEY-----\n\
+        MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEusWxHK2PmfnHKwXPS54m0kTcGJ90\n\
+        UiglWiGahtagnv8gE4v4LcG21WK+D6VKt4BKOmS21yzP7Wtvtu0ou/wRfg==\n\
+        -----END PUBLIC KEY-----\n";
+
+    fn cose_key() -> Vec<u8> {
+        let mut cose_key = vec![0xa4, 0x01, 0x02, 0x20, 0x01, 0x21, 0x58, 0x20];
+        cose_key.extend(URL_SAFE_NO_PAD.decode(X).unwrap());
+        cose_key.extend([0x22, 0x58, 0x20]);
+        cose_key.extend(URL_SAFE_NO_PAD.decode(Y).unwrap());
+        cose_key
+    }
+
+    #[test]
+    fn same_key_in_every_format() {
+        let jwk = format!(r#"{{"kty":"EC","crv":"P-256","x":"{}","y":"{}"}}"#, X, Y);
+        let expected = Key::Ec2 {
+            curve: Curve::P256,
+            x: URL_SAFE_NO_PAD.decode(X).unwrap(),
+            y: URL_SAFE_NO_PAD.decode(Y).unwrap(),
+        };
+
+        assert_eq!(Key::from_file_content(jwk.as_bytes()), Ok(expected.clone()));
+        assert_eq!(Key::from_file_content(PEM.as_bytes()), Ok(expected.clone()));
+        assert_eq!(Key::from_file_content(&cose_key()), Ok(expected));
+    }
+
+    #[test]
+    fn export() {
+        let key = Key::from_file_content(&cose_key()).unwrap();
+        assert_eq!(key.to_pem(), Ok(PEM.to_string()));
+        assert_eq!(Key::from_jwk(&key.to_jwk()), Ok(key.clone()));
+        assert_eq!(
+            Key::Symmetric { k: vec![1; 16] }.to_pem(),
+            Err("PEM export of symmetric key, 128 bits is not supported".to_string())
+        );
+    }
+
+    #[test]
+    fn thumbprint() {
+        // RFC 9679 Section 6
+        let key = Key::Ec2 {
+            curve: Curve::P256,
+            x: parse_hex("65eda5a12577c2bae829437fe338701a10aaa375e1bb5b5de108de439c08551d")
+                .unwrap(),
+            y: parse_hex("1e52ed75701163f7f9e40ddf9f341b3dc9ba860af7e0ca7ca7e9eecd0084d19c")
+                .unwrap(),
+        };
+        assert_eq!(
+            key.thumbprint(),
+            parse_hex("496bd8afadf307e5b08c64b0421bf9dc01528a344a43bda88fadd1669da253ec").unwrap()
+        );
+    }
+
+    #[test]
+    fn inconsistent_keys() {
+        let key = |kty: u64, x_length: usize| {
+            Value::Map(vec![
+                (Value::Unsigned(1), Value::Unsigned(kty)),
+                (Value::Negative(0), Value::Unsigned(1)),
+                (Value::Negative(1), Value::Bytes(vec![0; x_length])),
+            ])
+        };
+        assert_eq!(
+            Key::from_cose_key(&key(2, 32)),
+            Err("missing y parameter".to_string())
+        );
+        assert_eq!(
+            Key::from_cose_key(&key(1, 32)),
+            Err("P-256 is not a curve for OKP keys".to_string())
+        );
+        let mut ec2 = key(2, 31);
+        if let Value::Map(entries) = &mut ec2 {
+            entries.push((Value::Negative(2), Value::Bytes(vec![0; 32])));
+        }
+        assert_eq!(
+            Key::from_cose_key(&ec2),
+            Err("x is 31 bytes, expected 32 for P-256".to_string())
+        );
+    }
+}
@@ -0,0 +1,283 @@
+//! Verification of COSE_Sign1 and COSE_Mac0 messages (RFC 9052 Sections 4.4 and 6.3).
+
+use ed25519_dalek::Verifier as _;
+use hmac::Hmac;
+use sha2::{Sha256, Sha384, Sha512};
+
+use crate::cose::{self, algorithm_name, Headers};
+use crate::cose_key::{Curve, Key};
+use crate::value::Value;
+
+pub const ALG_ES256: i128 = -7;
+pub const ALG_EDDSA: i128 = -8;
+pub const ALG_ED25519: i128 = -19;
+pub const ALG_ES384: i128 = -35;
+pub const ALG_HMAC_256_64: i128 = 4;
+pub const ALG_HMAC_256_256: i128 = 5;
+pub const ALG_HMAC_384_384: i128 = 6;
+pub const ALG_HMAC_512_512: i128 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    Sign1,
+    Mac0,
+}
+
+impl MessageType {
+    /// The name of the structure that is signed or MACed.
+    pub fn structure_name(&self) -> &'static str {
+        match self {
+            MessageType::Sign1 => "Sig_structure",
+            MessageType::Mac0 => "MAC_structure",
+        }
+    }
+
+    /// The name of the last element of the message.
+    pub fn proof_name(&self) -> &'static str {
+        match self {
+            MessageType::Sign1 => "signature",
+            MessageType::Mac0 => "tag",
+        }
+    }
+
+    fn context(&self) -> &'static str {
+        match self {
+            MessageType::Sign1 => "Signature1",
+            MessageType::Mac0 => "MAC0",
+        }
+    }
+}
+
+pub struct Message {
+    pub message_type: MessageType,
+    pub headers: Headers,
+    payload: Vec<u8>,
+    proof: Vec<u8>,
+}
+
+impl Message {
+    /// Reads a COSE_Sign1 or COSE_Mac0 message, which is of the type of the tag `untagged`
+    /// if it is not tagged.
+    pub fn from_value(value: &Value, untagged: Option<u64>) -> Result<Self, String> {
+        let (tag, content) = cose::message_content(value, untagged)?;
+        let message_type = match tag {
+            cose::TAG_COSE_SIGN1 => MessageType::Sign1,
+            cose::TAG_COSE_MAC0 => MessageType::Mac0,
+            _ => return Err("not a COSE_Sign1 or COSE_Mac0 message".to_string()),
+        };
+        let Value::Array(items) = content else {
+            return Err("expected array".to_string());
+        };
+        let [protected, unprotected, payload, Value::Bytes(proof)] = &items[..] else {
+            return Err(format!(
+                "expected [protected, unprotected, payload, {}]",
+                message_type.proof_name()
+            ));
+        };
+        let payload = match payload {
+            Value::Bytes(payload) => payload.clone(),
+            Value::Simple(_) => return Err("detached payload is not supported".to_string()),
+            _ => return Err("expected byte string payload".to_string()),
+        };
+        Ok(Message {
+            message_type,
+            headers: Headers::new(protected, unprotected)?,
+            payload,
+            proof: proof.clone(),
+        })
+    }
+
+    /// Encodes the `Sig_structure` or `MAC_structure` of the message.
+    pub fn to_be_signed(&self, external_aad: &[u8]) -> Vec<u8> {
+        Value::Array(vec![
+            Value::Text(self.message_type.context().to_string()),
+            Value::Bytes(self.headers.protected.clone()),
+            Value::Bytes(external_aad.to_vec()),
+            Value::Bytes(self.payload.clone()),
+        ])
+        .encode()
+    }
+}
+
+fn verify_hmac<M: hmac::Mac + hmac::KeyInit>(
+    k: &[u8],
+    to_be_maced: &[u8],
+    tag: &[u8],
+    tag_length: usize,
+) -> Result<(), String> {
+    if tag.len() != tag_length {
+        return Err(format!("expected {} bytes tag", tag_length));
+    }
+    let mut mac = <M as hmac::KeyInit>::new_from_slice(k).map_err(|err| err.to_string())?;
+    mac.update(to_be_maced);
+    mac.verify_truncated_left(tag)
+        .map_err(|_| "tag mismatch".to_string())
+}
+
+/// Checks the signature or tag of the message against `to_be_signed` with `key`.
+pub fn verify(message: &Message, to_be_signed: &[u8], key: &Key) -> Result<(), String> {
+    let algorithm = message.headers.algorithm()?;
+    let proof = &message.proof[..];
+    let signature_mismatch = |_| "signature mismatch".to_string();
+    match (message.message_type, algorithm, key) {
+        (
+            MessageType::Sign1,
+            ALG_ES256,
+            Key::Ec2 {
+                curve: Curve::P256,
+                x,
+                y,
+            },
+        ) => {
+            let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&[&[4], &x[..], &y[..]].concat())
+                .map_err(|_| "invalid P-256 public key".to_string())?;
+            let signature = p256::ecdsa::Signature::from_slice(proof)
+                .map_err(|_| "invalid ES256 signature".to_string())?;
+            key.verify(to_be_signed, &signature)
+                .map_err(signature_mismatch)
+        }
+        (
+            MessageType::Sign1,
+            ALG_ES384,
+            Key::Ec2 {
+                curve: Curve::P384,
+                x,
+                y,
+            },
+        ) => {
+            let key = p384::ecdsa::VerifyingKey::from_sec1_bytes(&[&[4], &x[..], &y[..]].concat())
+                .map_err(|_| "invalid P-384 public key".to_string())?;
+            let signature = p384::ecdsa::Signature::from_slice(proof)
+                .map_err(|_| "invalid ES384 signature".to_string())?;
+            key.verify(to_be_signed, &signature)
+                .map_err(signature_mismatch)
+        }
+        (
+            MessageType::Sign1,
+            ALG_EDDSA | ALG_ED25519,
+            Key::Okp {
+                curve: Curve::Ed25519,
+                x,
+            },
+        ) => {
+            let key = <&[u8; 32]>::try_from(&x[..])
+                .ok()
+                .and_then(|x| ed25519_dalek::VerifyingKey::from_bytes(x).ok())
+                .ok_or_else(|| "invalid Ed25519 public key".to_string())?;
+            let signature = ed25519_dalek::Signature::from_slice(proof)
+                .map_err(|_| "invalid Ed25519 signature".to_string())?;
+            key.verify(to_be_signed, &signature)
+                .map_err(signature_mismatch)
+        }
+        (MessageType::Mac0, ALG_HMAC_256_64, Key::Symmetric { k }) => {
+            verify_hmac::<Hmac<Sha256>>(k, to_be_signed, proof, 8)
+        }
+        (MessageType::Mac0, ALG_HMAC_256_256, Key::Symmetric { k }) => {
+            verify_hmac::<Hmac<Sha256>>(k, to_be_signed, proof, 32)
+        }
+        (MessageType::Mac0, ALG_HMAC_384_384, Key::Symmetric { k }) => {
+            verify_hmac::<Hmac<Sha384>>(k, to_be_signed, proof, 48)
+        }
+        (MessageType::Mac0, ALG_HMAC_512_512, Key::Symmetric { k }) => {
+            verify_hmac::<Hmac<Sha512>>(k, to_be_signed, proof, 64)
+        }
+        (
+            _,
+            ALG_ES256 | ALG_ES384 | ALG_EDDSA | ALG_ED25519 | ALG_HMAC_256_64 | ALG_HMAC_256_256
+            | ALG_HMAC_384_384 | ALG_HMAC_512_512,
+            _,
+        ) => Err(format!(
+            "{} does not go with this message type or key",
+            algorithm_name(algorithm)
+        )),
+        _ => Err(format!("{} is not supported", algorithm_name(algorithm))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::Signer as _;
+
+    use super::*;
+    use crate::test_helpers::parse_item;
+
+    fn message(hex: &str, untagged: Option<u64>) -> Message {
+        let object = parse_item(hex);
+        Message::from_value(&Value::from_object(&object).unwrap(), untagged).unwrap()
+    }
+
+    #[test]
+    fn verify_es256() {
+        // RFC 9052 Appendix C.2.1
+        let message = message(
+            "d28443a10126a10442313154546869732069732074686520636f6e74656e742e5840\
+             8eb33e4ca31d1c465ab05aac34cc6b23d58fef5c083106c4d25a91aef0b0117e\
+             2af9a291aa32e14ab834dc56ed2a223444547e01f11d3b0916e5a4c345cacb36",
+            None,
+        );
+        let key = Key::from_jwk(
+            r#"{"kty":"EC","crv":"P-256",
+                "x":"usWxHK2PmfnHKwXPS54m0kTcGJ90UiglWiGahtagnv8",
+                "y":"IBOL-C3BttVivg-lSreASjpkttcsz-1rb7btKLv8EX4"}"#,
+        )
+        .unwrap();
+        assert_eq!(verify(&message, &message.to_be_signed(b""), &key), Ok(()));
+        assert_eq!(
+            verify(&message, &message.to_be_signed(b"aad"), &key),
+            Err("signature mismatch".to_string())
+        );
+    }
+
+    #[test]
+    fn verify_hmac_256() {
+        let message = message(
+            "d18443a10105a0477061796c6f61645820\
+             93f713c49c32ae91134294bade2bbf937d1fc85c05dfd6dc8c29d74cec817645",
+            None,
+        );
+        let key = Key::Symmetric {
+            k: (0..32).collect(),
+        };
+        assert_eq!(message.message_type, MessageType::Mac0);
+        assert_eq!(verify(&message, &message.to_be_signed(b""), &key), Ok(()));
+        let key = Key::Symmetric {
+            k: (1..33).collect(),
+        };
+        assert_eq!(
+            verify(&message, &message.to_be_signed(b""), &key),
+            Err("tag mismatch".to_string())
+        );
+    }
+
+    #[test]
+    fn verify_eddsa_untagged() {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let unsigned = message("8443a10127a0477061796c6f616440", Some(cose::TAG_COSE_SIGN1));
+        let signature = signing_key.sign(&unsigned.to_be_signed(b""));
+        let hex = format!(
+            "8443a10127a0477061796c6f61645840{}",
+            crate::format_hex(&signature.to_bytes())
+        );
+        let message = message(&hex, Some(cose::TAG_COSE_SIGN1));
+        let key = Key::Okp {
+            curve: Curve::Ed25519,
+            x: signing_key.verifying_key().to_bytes().to_vec(),
+        };
+        assert_eq!(verify(&message, &message.to_be_signed(b""), &key), Ok(()));
+        assert_eq!(
+            verify(
+                &message,
+                &message.to_be_signed(b""),
+                &Key::Symmetric { k: vec![] }
+            ),
+            Err("EdDSA does not go with this message type or key".to_string())
+        );
+    }
+
+    #[test]
+    fn untagged_without_type() {
+        let object = parse_item("8443a10127a0477061796c6f616440");
+        let value = Value::from_object(&object).unwrap();
+        assert!(Message::from_value(&value, None).is_err());
+    }
+}
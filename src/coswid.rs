@@ -0,0 +1,169 @@
+//! Concise Software Identification Tags (RFC 9393): one index of keys shared by the tag and
+//! all the maps nested in it.
+
+use crate::cbor_object::CborObject;
+use crate::context::Context;
+use crate::schema::{Entry, Field, Schema};
+
+pub const TAG_COSWID: u64 = 1398229316;
+
+/// `application/swid+cbor`.
+pub const CONTENT_FORMAT_COSWID: u64 = 258;
+
+/// Named Information Hash Algorithm registry, which CoSWID and CoRIM digests use.
+pub const HASH_ALGORITHMS: &[(i64, &str)] = &[
+    (1, "sha-256"),
+    (2, "sha-256-128"),
+    (3, "sha-256-120"),
+    (4, "sha-256-96"),
+    (5, "sha-256-64"),
+    (6, "sha-256-32"),
+    (7, "sha-384"),
+    (8, "sha-512"),
+    (9, "sha3-224"),
+    (10, "sha3-256"),
+    (11, "sha3-384"),
+    (12, "sha3-512"),
+];
+
+/// A hash-entry: a named information hash algorithm and a digest.
+pub const HASH_ENTRY: Schema = Schema::Array(&[
+    Field {
+        name: "hash-alg-id",
+        schema: Schema::Values(HASH_ALGORITHMS),
+    },
+    Field {
+        name: "hash-value",
+        schema: Schema::Opaque,
+    },
+]);
+
+const ROLES: &[(i64, &str)] = &[
+    (1, "tag-creator"),
+    (2, "software-creator"),
+    (3, "aggregator"),
+    (4, "distributor"),
+    (5, "licensor"),
+    (6, "maintainer"),
+];
+
+pub const VERSION_SCHEMES: &[(i64, &str)] = &[
+    (1, "multipartnumeric"),
+    (2, "multipartnumeric+suffix"),
+    (3, "alphanumeric"),
+    (4, "decimal"),
+    (16384, "semver"),
+];
+
+const OWNERSHIP: &[(i64, &str)] = &[(1, "abandon"), (2, "private"), (3, "shared")];
+
+const RELATIONS: &[(i64, &str)] = &[
+    (1, "ancestor"),
+    (2, "component"),
+    (3, "feature"),
+    (4, "installationmedia"),
+    (5, "packageinstaller"),
+    (6, "parent"),
+    (7, "patches"),
+    (8, "requires"),
+    (9, "see-also"),
+    (10, "supersedes"),
+    (11, "supplemental"),
+];
+
+const USES: &[(i64, &str)] = &[(1, "optional"), (2, "required"), (3, "recommended")];
+
+/// Most members hold one item or an array of them.
+fn select_maps(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    match object {
+        CborObject::Array(_) => (Schema::ArrayOf(&COSWID), None),
+        _ => (COSWID, None),
+    }
+}
+
+/// A role is one integer or an array of them.
+fn select_roles(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    if let CborObject::Array(_) = object {
+        return (Schema::ArrayOf(&Schema::Values(ROLES)), None);
+    }
+    let name = object.as_integer().and_then(|role| {
+        ROLES
+            .iter()
+            .find(|(id, _)| i128::from(*id) == role)
+            .map(|(_, name)| name.to_string())
+    });
+    (Schema::Any, name)
+}
+
+const MAPS: Schema = Schema::Select(select_maps);
+const ROLE: Schema = Schema::Select(select_roles);
+
+/// A concise-swid-tag, and any of the entity, link, software-meta, payload, evidence and
+/// resource maps in it.
+pub static COSWID: Schema = Schema::Map(&[
+    Entry::int(0, "tag-id", Schema::Any),
+    Entry::int(1, "software-name", Schema::Any),
+    Entry::int(2, "entity", MAPS),
+    Entry::int(3, "evidence", MAPS),
+    Entry::int(4, "link", MAPS),
+    Entry::int(5, "software-meta", MAPS),
+    Entry::int(6, "payload", MAPS),
+    Entry::int(7, "hash", HASH_ENTRY),
+    Entry::int(8, "corpus", Schema::Any),
+    Entry::int(9, "patch", Schema::Any),
+    Entry::int(10, "media", Schema::Any),
+    Entry::int(11, "supplemental", Schema::Any),
+    Entry::int(12, "tag-version", Schema::Any),
+    Entry::int(13, "software-version", Schema::Any),
+    Entry::int(14, "version-scheme", Schema::Values(VERSION_SCHEMES)),
+    Entry::int(15, "lang", Schema::Any),
+    Entry::int(16, "directory", MAPS),
+    Entry::int(17, "file", MAPS),
+    Entry::int(18, "process", MAPS),
+    Entry::int(19, "resource", MAPS),
+    Entry::int(20, "size", Schema::Any),
+    Entry::int(21, "file-version", Schema::Any),
+    Entry::int(22, "key", Schema::Any),
+    Entry::int(23, "location", Schema::Any),
+    Entry::int(24, "fs-name", Schema::Any),
+    Entry::int(25, "root", Schema::Any),
+    Entry::int(26, "path-elements", MAPS),
+    Entry::int(27, "process-name", Schema::Any),
+    Entry::int(28, "pid", Schema::Any),
+    Entry::int(29, "type", Schema::Any),
+    Entry::int(31, "entity-name", Schema::Any),
+    Entry::int(32, "reg-id", Schema::Any),
+    Entry::int(33, "role", ROLE),
+    Entry::int(34, "thumbprint", HASH_ENTRY),
+    Entry::int(35, "date", Schema::Any),
+    Entry::int(36, "device-id", Schema::Any),
+    Entry::int(37, "artifact", Schema::Any),
+    Entry::int(38, "href", Schema::Any),
+    Entry::int(39, "ownership", Schema::Values(OWNERSHIP)),
+    Entry::int(40, "rel", Schema::Values(RELATIONS)),
+    Entry::int(41, "media-type", Schema::Any),
+    Entry::int(42, "use", Schema::Values(USES)),
+    Entry::int(43, "activation-status", Schema::Any),
+    Entry::int(44, "channel-type", Schema::Any),
+    Entry::int(45, "colloquial-version", Schema::Any),
+    Entry::int(46, "description", Schema::Any),
+    Entry::int(47, "edition", Schema::Any),
+    Entry::int(48, "entitlement-data-required", Schema::Any),
+    Entry::int(49, "entitlement-key", Schema::Any),
+    Entry::int(50, "generator", Schema::Any),
+    Entry::int(51, "persistent-id", Schema::Any),
+    Entry::int(52, "product", Schema::Any),
+    Entry::int(53, "product-family", Schema::Any),
+    Entry::int(54, "revision", Schema::Any),
+    Entry::int(55, "summary", Schema::Any),
+    Entry::int(56, "unspsc-code", Schema::Any),
+    Entry::int(57, "unspsc-version", Schema::Any),
+]);
+
+/// Returns the name of the CoSWID tag and the structure of its content.
+pub fn tag_schema(tag: u64) -> Option<(&'static str, Schema)> {
+    match tag {
+        TAG_COSWID => Some(("coswid", COSWID)),
+        _ => None,
+    }
+}
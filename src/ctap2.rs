@@ -0,0 +1,524 @@
+//! CTAP2 messages (Client to Authenticator Protocol 2.1, Sections 6 and 8): a command or
+//! status byte followed by a CBOR map with integer keys.
+
+use crate::cose::ALGORITHMS;
+use crate::cose_key;
+use crate::schema::{Entry, Schema};
+use crate::webauthn;
+
+const RP: Schema = Schema::Map(&[
+    Entry::text("id", "relying party id", Schema::Any),
+    Entry::text("name", "relying party name", Schema::Any),
+]);
+const USER: Schema = Schema::Map(&[
+    Entry::text("id", "user handle", Schema::Opaque),
+    Entry::text("name", "user name", Schema::Any),
+    Entry::text("displayName", "user display name", Schema::Any),
+]);
+const CREDENTIAL_PARAMETERS: Schema = Schema::Map(&[
+    Entry::text("alg", "algorithm", Schema::Values(ALGORITHMS)),
+    Entry::text("type", "credential type", Schema::Any),
+]);
+const CREDENTIAL_DESCRIPTOR: Schema = Schema::Map(&[
+    Entry::text("type", "credential type", Schema::Any),
+    Entry::text("id", "credential id", Schema::Opaque),
+    Entry::text("transports", "transports", Schema::Any),
+]);
+
+const MAKE_CREDENTIAL: Schema = Schema::Map(&[
+    Entry::int(1, "clientDataHash", Schema::Opaque),
+    Entry::int(2, "rp", RP),
+    Entry::int(3, "user", USER),
+    Entry::int(
+        4,
+        "pubKeyCredParams",
+        Schema::ArrayOf(&CREDENTIAL_PARAMETERS),
+    ),
+    Entry::int(5, "excludeList", Schema::ArrayOf(&CREDENTIAL_DESCRIPTOR)),
+    Entry::int(6, "extensions", Schema::Any),
+    Entry::int(7, "options", Schema::Any),
+    Entry::int(8, "pinUvAuthParam", Schema::Opaque),
+    Entry::int(9, "pinUvAuthProtocol", Schema::Any),
+    Entry::int(10, "enterpriseAttestation", Schema::Any),
+]);
+
+const GET_ASSERTION: Schema = Schema::Map(&[
+    Entry::int(1, "rpId", Schema::Any),
+    Entry::int(2, "clientDataHash", Schema::Opaque),
+    Entry::int(3, "allowList", Schema::ArrayOf(&CREDENTIAL_DESCRIPTOR)),
+    Entry::int(4, "extensions", Schema::Any),
+    Entry::int(5, "options", Schema::Any),
+    Entry::int(6, "pinUvAuthParam", Schema::Opaque),
+    Entry::int(7, "pinUvAuthProtocol", Schema::Any),
+]);
+const GET_ASSERTION_RESPONSE: Schema = Schema::Map(&[
+    Entry::int(1, "credential", CREDENTIAL_DESCRIPTOR),
+    Entry::int(2, "authData", webauthn::AUTH_DATA),
+    Entry::int(3, "signature", Schema::Opaque),
+    Entry::int(4, "user", USER),
+    Entry::int(5, "numberOfCredentials", Schema::Any),
+    Entry::int(6, "userSelected", Schema::Any),
+    Entry::int(7, "largeBlobKey", Schema::Opaque),
+]);
+
+const GET_INFO_RESPONSE: Schema = Schema::Map(&[
+    Entry::int(1, "versions", Schema::Any),
+    Entry::int(2, "extensions", Schema::Any),
+    Entry::int(3, "aaguid", Schema::Opaque),
+    Entry::int(4, "options", Schema::Any),
+    Entry::int(5, "maxMsgSize", Schema::Any),
+    Entry::int(6, "pinUvAuthProtocols", Schema::Any),
+    Entry::int(7, "maxCredentialCountInList", Schema::Any),
+    Entry::int(8, "maxCredentialIdLength", Schema::Any),
+    Entry::int(9, "transports", Schema::Any),
+    Entry::int(10, "algorithms", Schema::ArrayOf(&CREDENTIAL_PARAMETERS)),
+    Entry::int(11, "maxSerializedLargeBlobArray", Schema::Any),
+    Entry::int(12, "forcePINChange", Schema::Any),
+    Entry::int(13, "minPINLength", Schema::Any),
+    Entry::int(14, "firmwareVersion", Schema::Any),
+    Entry::int(15, "maxCredBlobLength", Schema::Any),
+    Entry::int(16, "maxRPIDsForSetMinPINLength", Schema::Any),
+    Entry::int(17, "preferredPlatformUvAttempts", Schema::Any),
+    Entry::int(18, "uvModality", Schema::Any),
+    Entry::int(19, "certifications", Schema::Any),
+    Entry::int(20, "remainingDiscoverableCredentials", Schema::Any),
+    Entry::int(21, "vendorPrototypeConfigCommands", Schema::Any),
+]);
+
+const CLIENT_PIN_SUBCOMMANDS: &[(i64, &str)] = &[
+    (1, "getPINRetries"),
+    (2, "getKeyAgreement"),
+    (3, "setPIN"),
+    (4, "changePIN"),
+    (5, "getPinToken"),
+    (6, "getPinUvAuthTokenUsingUvWithPermissions"),
+    (7, "getUVRetries"),
+    (9, "getPinUvAuthTokenUsingPinWithPermissions"),
+];
+const CLIENT_PIN: Schema = Schema::Map(&[
+    Entry::int(1, "pinUvAuthProtocol", Schema::Any),
+    Entry::int(2, "subCommand", Schema::Values(CLIENT_PIN_SUBCOMMANDS)),
+    Entry::int(3, "keyAgreement", cose_key::COSE_KEY),
+    Entry::int(4, "pinUvAuthParam", Schema::Opaque),
+    Entry::int(5, "newPinEnc", Schema::Opaque),
+    Entry::int(6, "pinHashEnc", Schema::Opaque),
+    Entry::int(9, "permissions", Schema::Any),
+    Entry::int(10, "rpId", Schema::Any),
+]);
+const CLIENT_PIN_RESPONSE: Schema = Schema::Map(&[
+    Entry::int(1, "keyAgreement", cose_key::COSE_KEY),
+    Entry::int(2, "pinUvAuthToken", Schema::Opaque),
+    Entry::int(3, "pinRetries", Schema::Any),
+    Entry::int(4, "powerCycleState", Schema::Any),
+    Entry::int(5, "uvRetries", Schema::Any),
+]);
+
+const BIO_ENROLLMENT: Schema = Schema::Map(&[
+    Entry::int(1, "modality", Schema::Any),
+    Entry::int(2, "subCommand", Schema::Any),
+    Entry::int(3, "subCommandParams", Schema::Any),
+    Entry::int(4, "pinUvAuthProtocol", Schema::Any),
+    Entry::int(5, "pinUvAuthParam", Schema::Opaque),
+    Entry::int(6, "getModality", Schema::Any),
+]);
+const BIO_ENROLLMENT_RESPONSE: Schema = Schema::Map(&[
+    Entry::int(1, "modality", Schema::Any),
+    Entry::int(2, "fingerprintKind", Schema::Any),
+    Entry::int(3, "maxCaptureSamplesRequiredForEnroll", Schema::Any),
+    Entry::int(4, "templateId", Schema::Opaque),
+    Entry::int(5, "lastEnrollSampleStatus", Schema::Any),
+    Entry::int(6, "remainingSamples", Schema::Any),
+    Entry::int(7, "templateInfos", Schema::Any),
+    Entry::int(8, "maxTemplateFriendlyName", Schema::Any),
+]);
+
+const CREDENTIAL_MANAGEMENT_SUBCOMMANDS: &[(i64, &str)] = &[
+    (1, "getCredsMetadata"),
+    (2, "enumerateRPsBegin"),
+    (3, "enumerateRPsGetNextRP"),
+    (4, "enumerateCredentialsBegin"),
+    (5, "enumerateCredentialsGetNextCredential"),
+    (6, "deleteCredential"),
+    (7, "updateUserInformation"),
+];
+const CREDENTIAL_MANAGEMENT: Schema = Schema::Map(&[
+    Entry::int(
+        1,
+        "subCommand",
+        Schema::Values(CREDENTIAL_MANAGEMENT_SUBCOMMANDS),
+    ),
+    Entry::int(
+        2,
+        "subCommandParams",
+        Schema::Map(&[
+            Entry::int(1, "rpIDHash", Schema::Opaque),
+            Entry::int(2, "credentialID", CREDENTIAL_DESCRIPTOR),
+            Entry::int(3, "user", USER),
+        ]),
+    ),
+    Entry::int(3, "pinUvAuthProtocol", Schema::Any),
+    Entry::int(4, "pinUvAuthParam", Schema::Opaque),
+]);
+const CREDENTIAL_MANAGEMENT_RESPONSE: Schema = Schema::Map(&[
+    Entry::int(1, "existingResidentCredentialsCount", Schema::Any),
+    Entry::int(
+        2,
+        "maxPossibleRemainingResidentCredentialsCount",
+        Schema::Any,
+    ),
+    Entry::int(3, "rp", RP),
+    Entry::int(4, "rpIDHash", Schema::Opaque),
+    Entry::int(5, "totalRPs", Schema::Any),
+    Entry::int(6, "user", USER),
+    Entry::int(7, "credentialID", CREDENTIAL_DESCRIPTOR),
+    Entry::int(8, "publicKey", cose_key::COSE_KEY),
+    Entry::int(9, "totalCredentials", Schema::Any),
+    Entry::int(10, "credProtect", Schema::Any),
+    Entry::int(11, "largeBlobKey", Schema::Opaque),
+]);
+
+const LARGE_BLOBS: Schema = Schema::Map(&[
+    Entry::int(1, "get", Schema::Any),
+    Entry::int(2, "set", Schema::Opaque),
+    Entry::int(3, "offset", Schema::Any),
+    Entry::int(4, "length", Schema::Any),
+    Entry::int(5, "pinUvAuthParam", Schema::Opaque),
+    Entry::int(6, "pinUvAuthProtocol", Schema::Any),
+]);
+const LARGE_BLOBS_RESPONSE: Schema = Schema::Map(&[Entry::int(1, "config", Schema::Opaque)]);
+
+const CONFIG_SUBCOMMANDS: &[(i64, &str)] = &[
+    (1, "enableEnterpriseAttestation"),
+    (2, "toggleAlwaysUv"),
+    (3, "setMinPINLength"),
+    (0xff, "vendorPrototype"),
+];
+const CONFIG: Schema = Schema::Map(&[
+    Entry::int(1, "subCommand", Schema::Values(CONFIG_SUBCOMMANDS)),
+    Entry::int(2, "subCommandParams", Schema::Any),
+    Entry::int(3, "pinUvAuthProtocol", Schema::Any),
+    Entry::int(4, "pinUvAuthParam", Schema::Opaque),
+]);
+
+pub const MAKE_CREDENTIAL_COMMAND: u8 = 0x01;
+pub const GET_ASSERTION_COMMAND: u8 = 0x02;
+pub const GET_INFO_COMMAND: u8 = 0x04;
+pub const CLIENT_PIN_COMMAND: u8 = 0x06;
+pub const GET_NEXT_ASSERTION_COMMAND: u8 = 0x08;
+pub const BIO_ENROLLMENT_COMMAND: u8 = 0x09;
+pub const CREDENTIAL_MANAGEMENT_COMMAND: u8 = 0x0a;
+pub const LARGE_BLOBS_COMMAND: u8 = 0x0c;
+pub const CONFIG_COMMAND: u8 = 0x0d;
+
+pub struct Command {
+    pub code: u8,
+    pub name: &'static str,
+    pub parameters: Schema,
+    pub response: Schema,
+}
+
+/// Authenticator API commands of Section 6, with the prototype commands of CTAP 2.1-PRE.
+pub const COMMANDS: &[Command] = &[
+    Command {
+        code: MAKE_CREDENTIAL_COMMAND,
+        name: "authenticatorMakeCredential",
+        parameters: MAKE_CREDENTIAL,
+        response: webauthn::ATTESTATION_OBJECT,
+    },
+    Command {
+        code: GET_ASSERTION_COMMAND,
+        name: "authenticatorGetAssertion",
+        parameters: GET_ASSERTION,
+        response: GET_ASSERTION_RESPONSE,
+    },
+    Command {
+        code: GET_INFO_COMMAND,
+        name: "authenticatorGetInfo",
+        parameters: Schema::Any,
+        response: GET_INFO_RESPONSE,
+    },
+    Command {
+        code: CLIENT_PIN_COMMAND,
+        name: "authenticatorClientPIN",
+        parameters: CLIENT_PIN,
+        response: CLIENT_PIN_RESPONSE,
+    },
+    Command {
+        code: 0x07,
+        name: "authenticatorReset",
+        parameters: Schema::Any,
+        response: Schema::Any,
+    },
+    Command {
+        code: GET_NEXT_ASSERTION_COMMAND,
+        name: "authenticatorGetNextAssertion",
+        parameters: Schema::Any,
+        response: GET_ASSERTION_RESPONSE,
+    },
+    Command {
+        code: BIO_ENROLLMENT_COMMAND,
+        name: "authenticatorBioEnrollment",
+        parameters: BIO_ENROLLMENT,
+        response: BIO_ENROLLMENT_RESPONSE,
+    },
+    Command {
+        code: CREDENTIAL_MANAGEMENT_COMMAND,
+        name: "authenticatorCredentialManagement",
+        parameters: CREDENTIAL_MANAGEMENT,
+        response: CREDENTIAL_MANAGEMENT_RESPONSE,
+    },
+    Command {
+        code: 0x0b,
+        name: "authenticatorSelection",
+        parameters: Schema::Any,
+        response: Schema::Any,
+    },
+    Command {
+        code: LARGE_BLOBS_COMMAND,
+        name: "authenticatorLargeBlobs",
+        parameters: LARGE_BLOBS,
+        response: LARGE_BLOBS_RESPONSE,
+    },
+    Command {
+        code: CONFIG_COMMAND,
+        name: "authenticatorConfig",
+        parameters: CONFIG,
+        response: Schema::Any,
+    },
+    Command {
+        code: 0x40,
+        name: "authenticatorBioEnrollment (prototype)",
+        parameters: BIO_ENROLLMENT,
+        response: BIO_ENROLLMENT_RESPONSE,
+    },
+    Command {
+        code: 0x41,
+        name: "authenticatorCredentialManagement (prototype)",
+        parameters: CREDENTIAL_MANAGEMENT,
+        response: CREDENTIAL_MANAGEMENT_RESPONSE,
+    },
+];
+
+pub fn command(code: u8) -> Option<&'static Command> {
+    COMMANDS.iter().find(|command| command.code == code)
+}
+
+/// Status codes of Section 8.2.
+const STATUS_CODES: &[(u8, &str)] = &[
+    (0x00, "CTAP2_OK"),
+    (0x01, "CTAP1_ERR_INVALID_COMMAND"),
+    (0x02, "CTAP1_ERR_INVALID_PARAMETER"),
+    (0x03, "CTAP1_ERR_INVALID_LENGTH"),
+    (0x04, "CTAP1_ERR_INVALID_SEQ"),
+    (0x05, "CTAP1_ERR_TIMEOUT"),
+    (0x06, "CTAP1_ERR_CHANNEL_BUSY"),
+    (0x0a, "CTAP1_ERR_LOCK_REQUIRED"),
+    (0x0b, "CTAP1_ERR_INVALID_CHANNEL"),
+    (0x11, "CTAP2_ERR_CBOR_UNEXPECTED_TYPE"),
+    (0x12, "CTAP2_ERR_INVALID_CBOR"),
+    (0x14, "CTAP2_ERR_MISSING_PARAMETER"),
+    (0x15, "CTAP2_ERR_LIMIT_EXCEEDED"),
+    (0x17, "CTAP2_ERR_FP_DATABASE_FULL"),
+    (0x18, "CTAP2_ERR_LARGE_BLOB_STORAGE_FULL"),
+    (0x19, "CTAP2_ERR_CREDENTIAL_EXCLUDED"),
+    (0x21, "CTAP2_ERR_PROCESSING"),
+    (0x22, "CTAP2_ERR_INVALID_CREDENTIAL"),
+    (0x23, "CTAP2_ERR_USER_ACTION_PENDING"),
+    (0x24, "CTAP2_ERR_OPERATION_PENDING"),
+    (0x25, "CTAP2_ERR_NO_OPERATIONS"),
+    (0x26, "CTAP2_ERR_UNSUPPORTED_ALGORITHM"),
+    (0x27, "CTAP2_ERR_OPERATION_DENIED"),
+    (0x28, "CTAP2_ERR_KEY_STORE_FULL"),
+    (0x2b, "CTAP2_ERR_UNSUPPORTED_OPTION"),
+    (0x2c, "CTAP2_ERR_INVALID_OPTION"),
+    (0x2d, "CTAP2_ERR_KEEPALIVE_CANCEL"),
+    (0x2e, "CTAP2_ERR_NO_CREDENTIALS"),
+    (0x2f, "CTAP2_ERR_USER_ACTION_TIMEOUT"),
+    (0x30, "CTAP2_ERR_NOT_ALLOWED"),
+    (0x31, "CTAP2_ERR_PIN_INVALID"),
+    (0x32, "CTAP2_ERR_PIN_BLOCKED"),
+    (0x33, "CTAP2_ERR_PIN_AUTH_INVALID"),
+    (0x34, "CTAP2_ERR_PIN_AUTH_BLOCKED"),
+    (0x35, "CTAP2_ERR_PIN_NOT_SET"),
+    (0x36, "CTAP2_ERR_PUAT_REQUIRED"),
+    (0x37, "CTAP2_ERR_PIN_POLICY_VIOLATION"),
+    (0x39, "CTAP2_ERR_REQUEST_TOO_LARGE"),
+    (0x3a, "CTAP2_ERR_ACTION_TIMEOUT"),
+    (0x3b, "CTAP2_ERR_UP_REQUIRED"),
+    (0x3c, "CTAP2_ERR_UV_BLOCKED"),
+    (0x3d, "CTAP2_ERR_INTEGRITY_FAILURE"),
+    (0x3e, "CTAP2_ERR_INVALID_SUBCOMMAND"),
+    (0x3f, "CTAP2_ERR_UV_INVALID"),
+    (0x40, "CTAP2_ERR_UNAUTHORIZED_PERMISSION"),
+    (0x7f, "CTAP1_ERR_OTHER"),
+];
+
+pub fn status_name(status: u8) -> Option<&'static str> {
+    STATUS_CODES
+        .iter()
+        .find(|(code, _)| *code == status)
+        .map(|(_, name)| *name)
+}
+
+/// Reads the head of the item at the start of `bytes`: its major type, argument and the
+/// length of the head, with `None` as argument for an indefinite length.
+pub fn head(bytes: &[u8]) -> Result<(u8, Option<u64>, usize), String> {
+    let Some(&initial_byte) = bytes.first() else {
+        return Err("truncated item".to_string());
+    };
+    let major_type = initial_byte >> 5;
+    let (argument, length) = match initial_byte & 0x1f {
+        info @ 0..24 => (Some(u64::from(info)), 1),
+        info @ 24..28 => {
+            let length = 1 << (info - 24);
+            let Some(argument) = bytes.get(1..1 + length) else {
+                return Err("truncated item".to_string());
+            };
+            let argument = argument
+                .iter()
+                .fold(0, |acc, byte| (acc << 8) | u64::from(*byte));
+            (Some(argument), 1 + length)
+        }
+        31 => (None, 1),
+        _ => return Err("reserved additional information".to_string()),
+    };
+    Ok((major_type, argument, length))
+}
+
+/// Checks that the item at `offset` is in the CTAP2 canonical CBOR encoding of Section 8,
+/// adding the problems it finds to `issues`, and returns the offset of the next item.
+fn check_canonical(bytes: &[u8], offset: usize, issues: &mut Vec<String>) -> Result<usize, String> {
+    let (major_type, argument, head_length) = head(&bytes[offset..])?;
+    let mut next = offset + head_length;
+    let Some(argument) = argument else {
+        issues.push(format!("indefinite length at offset {}", offset));
+        // Skip the chunks or elements up to the break.
+        while bytes.get(next) != Some(&0xff) {
+            if next >= bytes.len() {
+                return Err("truncated item".to_string());
+            }
+            next = check_canonical(bytes, next, issues)?;
+        }
+        return Ok(next + 1);
+    };
+    let minimal_length = match argument {
+        0..24 => 1,
+        24..0x100 => 2,
+        0x100..0x10000 => 3,
+        0x10000..0x100000000 => 5,
+        _ => 9,
+    };
+    // Floats keep their encoding; the argument of major type 7 is not a number to shorten.
+    if major_type != 7 && head_length != minimal_length {
+        issues.push(format!(
+            "argument not in shortest form at offset {}",
+            offset
+        ));
+    }
+    match major_type {
+        2 | 3 => {
+            let end = usize::try_from(argument)
+                .ok()
+                .and_then(|length| next.checked_add(length))
+                .filter(|end| *end <= bytes.len())
+                .ok_or_else(|| "truncated item".to_string())?;
+            Ok(end)
+        }
+        4 => {
+            for _ in 0..argument {
+                next = check_canonical(bytes, next, issues)?;
+            }
+            Ok(next)
+        }
+        5 => {
+            let mut previous_key: Option<&[u8]> = None;
+            for _ in 0..argument {
+                let key_offset = next;
+                next = check_canonical(bytes, next, issues)?;
+                let key = &bytes[key_offset..next];
+                if let Some(previous_key) = previous_key {
+                    // Lower major types first, then shorter keys, then lower bytes.
+                    let order = |key: &[u8]| (key[0] >> 5, key.len(), key.to_vec());
+                    if order(previous_key) >= order(key) {
+                        issues.push(format!("map key out of order at offset {}", key_offset));
+                    }
+                }
+                previous_key = Some(key);
+                next = check_canonical(bytes, next, issues)?;
+            }
+            Ok(next)
+        }
+        6 => {
+            issues.push(format!("tag at offset {}", offset));
+            check_canonical(bytes, next, issues)
+        }
+        _ => Ok(next),
+    }
+}
+
+/// Lists the ways in which the item at the start of `bytes` departs from the CTAP2
+/// canonical CBOR encoding, with offsets counted from `base_offset`.
+pub fn canonical_issues(bytes: &[u8], base_offset: usize) -> Vec<String> {
+    let mut issues = vec![];
+    if let Err(err) = check_canonical(bytes, 0, &mut issues) {
+        issues.push(err);
+    }
+    // Report offsets in the frame rather than in the item.
+    issues
+        .into_iter()
+        .map(|issue| match issue.rsplit_once("at offset ") {
+            Some((problem, offset)) => match offset.parse::<usize>() {
+                Ok(offset) => format!("{}at offset {}", problem, base_offset + offset),
+                Err(_) => issue,
+            },
+            None => issue,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_hex;
+
+    fn issues(hex: &str) -> Vec<String> {
+        canonical_issues(&parse_hex(hex).unwrap(), 1)
+    }
+
+    #[test]
+    fn canonical_encoding() {
+        assert_eq!(issues("a201020304"), Vec::<String>::new());
+        assert_eq!(
+            issues("a203040102"),
+            vec!["map key out of order at offset 4"]
+        );
+        assert_eq!(
+            issues("a2616101186402"),
+            vec!["map key out of order at offset 5"]
+        );
+        assert_eq!(
+            issues("a1011801"),
+            vec!["argument not in shortest form at offset 3"]
+        );
+        // Integers sort before text strings, and shorter text strings first.
+        assert_eq!(issues("a3010261620362616104"), Vec::<String>::new());
+        assert_eq!(
+            issues("a3010262616103616204"),
+            vec!["map key out of order at offset 8"]
+        );
+        assert_eq!(issues("bf0102ff"), vec!["indefinite length at offset 1"]);
+        assert_eq!(issues("c101"), vec!["tag at offset 1"]);
+        assert_eq!(issues("a10102a1"), Vec::<String>::new());
+        assert_eq!(issues("a201"), vec!["truncated item"]);
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            command(MAKE_CREDENTIAL_COMMAND).map(|command| command.name),
+            Some("authenticatorMakeCredential")
+        );
+        assert!(command(0x03).is_none());
+        assert_eq!(status_name(0x2e), Some("CTAP2_ERR_NO_CREDENTIALS"));
+    }
+}
@@ -0,0 +1,182 @@
+//! CBOR Web Tokens (RFC 8392): names of the claims and the validity of a token at a time.
+
+use crate::cbor_object::CborObject;
+use crate::context::Context;
+use crate::cose;
+use crate::cose_key;
+use crate::eat;
+use crate::hcert;
+use crate::schema::{Entry, Schema};
+use crate::time;
+use crate::value::Value;
+
+pub const TAG_CWT: u64 = 61;
+/// Unprotected CWT Claims Set (RFC 9781).
+pub const TAG_UCCS: u64 = 601;
+
+pub const CLAIM_EXP: i64 = 4;
+pub const CLAIM_NBF: i64 = 5;
+
+/// Confirmation methods of the `cnf` claim (RFC 8747 Section 3.1).
+const CONFIRMATION: Schema = Schema::Map(&[
+    Entry::int(1, "COSE_Key", cose_key::COSE_KEY),
+    Entry::int(2, "Encrypted_COSE_Key", Schema::Any),
+    Entry::int(3, "kid", Schema::Opaque),
+]);
+
+/// CBOR Web Token (CWT) Claims registry, with the claims of Entity Attestation Tokens.
+const CLAIMS_MAP: Schema = Schema::Map(&[
+    Entry::int(1, "iss", Schema::Any),
+    Entry::int(2, "sub", Schema::Any),
+    Entry::int(3, "aud", Schema::Any),
+    Entry::int(CLAIM_EXP, "exp", Schema::Time),
+    Entry::int(CLAIM_NBF, "nbf", Schema::Time),
+    Entry::int(6, "iat", Schema::Time),
+    Entry::int(7, "cti", Schema::Opaque),
+    Entry::int(8, "cnf", CONFIRMATION),
+    Entry::int(9, "scope", Schema::Any),
+    Entry::int(10, "eat_nonce", Schema::Opaque),
+    Entry::int(38, "ace_profile", Schema::Any),
+    Entry::int(39, "cnonce", Schema::Opaque),
+    Entry::int(40, "exi", Schema::Any),
+    Entry::int(256, "ueid", eat::UEID),
+    Entry::int(257, "sueids", Schema::MapOf(&eat::UEID)),
+    Entry::int(258, "oemid", Schema::Any),
+    Entry::int(259, "hwmodel", Schema::Opaque),
+    Entry::int(260, "hwversion", Schema::Any),
+    Entry::int(261, "uptime", Schema::Any),
+    Entry::int(262, "oemboot", Schema::Any),
+    Entry::int(263, "dbgstat", Schema::Values(eat::DEBUG_STATUS)),
+    Entry::int(264, "location", eat::LOCATION),
+    Entry::int(265, "eat_profile", Schema::Any),
+    Entry::int(266, "submods", eat::SUBMODULES),
+    Entry::int(267, "bootcount", Schema::Any),
+    Entry::int(268, "bootseed", Schema::Opaque),
+    Entry::int(269, "dloas", Schema::Any),
+    Entry::int(270, "swname", Schema::Any),
+    Entry::int(271, "swversion", Schema::Any),
+    Entry::int(272, "manifests", eat::FORMATS),
+    Entry::int(273, "measurements", eat::FORMATS),
+    Entry::int(274, "measres", Schema::Any),
+    Entry::int(275, "intuse", Schema::Values(eat::INTENDED_USES)),
+    Entry::int(-260, "hcert", hcert::HCERT),
+]);
+
+/// Names the claims and, when the context has a time to check them against, tells whether
+/// the token is valid at that time.
+fn select_claims(object: &CborObject, context: &Context) -> (Schema, Option<String>) {
+    let Some(now) = context.now else {
+        return (CLAIMS_MAP, None);
+    };
+    let verdict = Value::from_object(object).and_then(|claims| validity(&claims, now));
+    let description = match verdict {
+        Ok(verdict) => verdict,
+        Err(err) => format!("invalid cwt claims: {}", err),
+    };
+    (CLAIMS_MAP, Some(description))
+}
+
+/// Checks the `exp` and `nbf` claims (RFC 8392 Sections 3.1.4 and 3.1.5) at `now`.
+fn validity(claims: &Value, now: i64) -> Result<String, String> {
+    let time_claim = |label: i64, name: &str| match claims.get(label) {
+        Some(value) => time::numeric_date(value)
+            .map(Some)
+            .ok_or_else(|| format!("{} is not a NumericDate", name)),
+        None => Ok(None),
+    };
+    let exp = time_claim(CLAIM_EXP, "exp")?;
+    let nbf = time_claim(CLAIM_NBF, "nbf")?;
+    let now_date = time::format_numeric_date(now as f64);
+    if let Some(exp) = exp.filter(|exp| now as f64 >= *exp) {
+        return Ok(format!(
+            "expired at {}, checked at {}",
+            time::format_numeric_date(exp),
+            now_date
+        ));
+    }
+    if let Some(nbf) = nbf.filter(|nbf| (now as f64) < *nbf) {
+        return Ok(format!(
+            "not valid before {}, checked at {}",
+            time::format_numeric_date(nbf),
+            now_date
+        ));
+    }
+    Ok(format!("valid at {}", now_date))
+}
+
+pub const CLAIMS: Schema = Schema::Select(select_claims);
+
+/// Picks the COSE message schema whose payload is a claims set, or names the claims of an
+/// unprotected claims set.
+pub fn select_token(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    match object {
+        CborObject::Tag(tag) => match tag.tag() {
+            Some(TAG_CWT) => (Schema::Any, None),
+            Some(tag) => match cose::cwt_schema(tag) {
+                Some(schema) => (schema, None),
+                None => (
+                    Schema::Any,
+                    Some("invalid cwt: expected a COSE message".to_string()),
+                ),
+            },
+            None => (Schema::Any, None),
+        },
+        CborObject::Map(_) => (CLAIMS, None),
+        _ => (
+            Schema::Any,
+            Some("invalid cwt: expected a COSE message".to_string()),
+        ),
+    }
+}
+
+/// A CWT: a tagged COSE message or a claims set.
+pub const CWT: Schema = Schema::Select(select_token);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(exp: Option<u64>, nbf: Option<u64>) -> Value {
+        let mut claims = vec![(
+            Value::Unsigned(1),
+            Value::Text("coap://as.example.com".into()),
+        )];
+        if let Some(exp) = exp {
+            claims.push((Value::Unsigned(4), Value::Unsigned(exp)));
+        }
+        if let Some(nbf) = nbf {
+            claims.push((Value::Unsigned(5), Value::Unsigned(nbf)));
+        }
+        Value::Map(claims)
+    }
+
+    #[test]
+    fn time_claims() {
+        // RFC 8392 Appendix A.1
+        let token = claims(Some(1444064944), Some(1443944944));
+        assert_eq!(
+            validity(&token, 1444000000),
+            Ok("valid at 2015-10-04T23:06:40Z".to_string())
+        );
+        assert_eq!(
+            validity(&token, 1444064944),
+            Ok("expired at 2015-10-05T17:09:04Z, checked at 2015-10-05T17:09:04Z".to_string())
+        );
+        assert_eq!(
+            validity(&token, 1443944943),
+            Ok(
+                "not valid before 2015-10-04T07:49:04Z, checked at 2015-10-04T07:49:03Z"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            validity(&claims(None, None), 0),
+            Ok("valid at 1970-01-01T00:00:00Z".to_string())
+        );
+        let token = Value::Map(vec![(Value::Unsigned(4), Value::Text("tomorrow".into()))]);
+        assert_eq!(
+            validity(&token, 0),
+            Err("exp is not a NumericDate".to_string())
+        );
+    }
+}
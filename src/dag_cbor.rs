@@ -0,0 +1,333 @@
+//! IPLD content identifiers (tag 42) and the strict subset of CBOR that DAG-CBOR allows
+//! (https://ipld.io/specs/codecs/dag-cbor/spec/).
+
+use crate::cbor_object::CborObject;
+use crate::ctap2;
+
+pub const TAG_CID: u64 = 42;
+
+/// Multibase prefix of a CID in binary form.
+const MULTIBASE_IDENTITY: u8 = 0x00;
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Multihash of a CIDv0: a SHA-256 digest of a dag-pb block.
+const CID_V0_PREFIX: [u8; 2] = [0x12, 0x20];
+const CID_V0_LENGTH: usize = 34;
+
+const CODECS: &[(u64, &str)] = &[
+    (0x51, "cbor"),
+    (0x55, "raw"),
+    (0x70, "dag-pb"),
+    (0x71, "dag-cbor"),
+    (0x72, "libp2p-key"),
+    (0x78, "git-raw"),
+    (0x0129, "dag-json"),
+    (0x0200, "json"),
+];
+
+const MULTIHASHES: &[(u64, &str)] = &[
+    (0x00, "identity"),
+    (0x11, "sha1"),
+    (0x12, "sha2-256"),
+    (0x13, "sha2-512"),
+    (0x14, "sha3-512"),
+    (0x16, "sha3-256"),
+    (0x1b, "keccak-256"),
+    (0x1e, "blake3"),
+    (0xb220, "blake2b-256"),
+];
+
+pub fn tag_name(tag: u64) -> Option<&'static str> {
+    match tag {
+        TAG_CID => Some("cid"),
+        _ => None,
+    }
+}
+
+/// Decodes the CID in the payload of tag 42 into its string form.
+pub fn describe(tag: u64, payload: &CborObject) -> Result<String, String> {
+    if tag != TAG_CID {
+        return Err(format!("unknown tag {}", tag));
+    }
+    let bytes = payload
+        .as_bytes()
+        .ok_or_else(|| "expected bstr".to_string())?;
+    match bytes.split_first() {
+        Some((&MULTIBASE_IDENTITY, cid)) => describe_cid(cid),
+        Some((prefix, _)) => Err(format!("unexpected multibase prefix {:#04x}", prefix)),
+        None => Err("empty bstr".to_string()),
+    }
+}
+
+fn name(names: &[(u64, &'static str)], code: u64) -> String {
+    match names.iter().find(|(id, _)| *id == code) {
+        Some((_, name)) => name.to_string(),
+        None => format!("{:#x}", code),
+    }
+}
+
+fn describe_cid(cid: &[u8]) -> Result<String, String> {
+    if cid.len() == CID_V0_LENGTH && cid.starts_with(&CID_V0_PREFIX) {
+        return Ok(format!("{} (v0, dag-pb, sha2-256)", base58btc(cid)));
+    }
+    let mut rest = cid;
+    let version = read_varint(&mut rest)?;
+    if version != 1 {
+        return Err(format!("unsupported CID version {}", version));
+    }
+    let codec = read_varint(&mut rest)?;
+    let hash = read_varint(&mut rest)?;
+    let length = read_varint(&mut rest)?;
+    if rest.len() as u64 != length {
+        return Err(format!(
+            "multihash digest has {} bytes, expected {}",
+            rest.len(),
+            length
+        ));
+    }
+    // Multibase prefix `b`: lowercase base32 without padding.
+    Ok(format!(
+        "b{} (v1, {}, {})",
+        base32(cid),
+        name(CODECS, codec),
+        name(MULTIHASHES, hash)
+    ))
+}
+
+/// Reads an unsigned varint (multiformats unsigned-varint) from the front of `bytes`.
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, String> {
+    let mut value = 0u64;
+    for (index, &byte) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[index + 1..];
+            return Ok(value);
+        }
+    }
+    Err("truncated varint".to_string())
+}
+
+fn base32(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            text.push(BASE32_ALPHABET[(buffer >> bits) as usize & 0x1f] as char);
+        }
+    }
+    if bits > 0 {
+        text.push(BASE32_ALPHABET[(buffer << (5 - bits)) as usize & 0x1f] as char);
+    }
+    text
+}
+
+fn base58btc(bytes: &[u8]) -> String {
+    // Base 58 digits, least significant first.
+    let mut digits: Vec<u8> = vec![];
+    for &byte in bytes {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|digit| BASE58_ALPHABET[*digit as usize] as char),
+        )
+        .collect()
+}
+
+/// Checks that the item at `offset` is in the DAG-CBOR subset, adding the problems it finds
+/// to `issues` with the offsets of the items they concern, and returns the offset of the
+/// next item.
+fn check_strict(
+    bytes: &[u8],
+    offset: usize,
+    issues: &mut Vec<(usize, String)>,
+) -> Result<usize, String> {
+    let (major_type, argument, head_length) = ctap2::head(&bytes[offset..])?;
+    let mut next = offset + head_length;
+    let Some(argument) = argument else {
+        issues.push((offset, "indefinite length".to_string()));
+        while bytes.get(next) != Some(&0xff) {
+            if next >= bytes.len() {
+                return Err("truncated item".to_string());
+            }
+            next = check_strict(bytes, next, issues)?;
+        }
+        return Ok(next + 1);
+    };
+    let minimal_length = match argument {
+        0..24 => 1,
+        24..0x100 => 2,
+        0x100..0x10000 => 3,
+        0x10000..0x100000000 => 5,
+        _ => 9,
+    };
+    if major_type != 7 && head_length != minimal_length {
+        issues.push((offset, "argument not in shortest form".to_string()));
+    }
+    match major_type {
+        2 | 3 => usize::try_from(argument)
+            .ok()
+            .and_then(|length| next.checked_add(length))
+            .filter(|end| *end <= bytes.len())
+            .ok_or_else(|| "truncated item".to_string()),
+        4 => {
+            for _ in 0..argument {
+                next = check_strict(bytes, next, issues)?;
+            }
+            Ok(next)
+        }
+        5 => {
+            let mut previous_key: Option<&[u8]> = None;
+            for _ in 0..argument {
+                let key_offset = next;
+                next = check_strict(bytes, next, issues)?;
+                let key = &bytes[key_offset..next];
+                if key[0] >> 5 != 3 {
+                    issues.push((key_offset, "map key is not a tstr".to_string()));
+                }
+                if let Some(previous_key) = previous_key {
+                    // Shorter keys first, then lower bytes.
+                    let order = |key: &[u8]| (key.len(), key.to_vec());
+                    if order(previous_key) == order(key) {
+                        issues.push((key_offset, "duplicate map key".to_string()));
+                    } else if order(previous_key) > order(key) {
+                        issues.push((key_offset, "map key out of order".to_string()));
+                    }
+                }
+                previous_key = Some(key);
+                next = check_strict(bytes, next, issues)?;
+            }
+            Ok(next)
+        }
+        6 => {
+            if argument != TAG_CID {
+                issues.push((offset, format!("tag {}", argument)));
+            }
+            check_strict(bytes, next, issues)
+        }
+        7 => {
+            match bytes[offset] & 0x1f {
+                20..=22 => {}
+                23 => issues.push((offset, "undefined".to_string())),
+                25 | 26 => issues.push((offset, "float not in 64 bits".to_string())),
+                27 if !f64::from_bits(argument).is_finite() => {
+                    issues.push((offset, "NaN or infinity".to_string()))
+                }
+                27 => {}
+                _ => issues.push((offset, "simple value".to_string())),
+            }
+            Ok(next)
+        }
+        _ => Ok(next),
+    }
+}
+
+/// Lists the ways in which the item at the start of `bytes` departs from DAG-CBOR, each with
+/// the offset of the item it concerns, counted from the start of the item. An item that
+/// cannot be read to its end is reported at offset 0.
+pub fn strictness_issues(bytes: &[u8]) -> Vec<(usize, String)> {
+    let mut issues = vec![];
+    if let Err(err) = check_strict(bytes, 0, &mut issues) {
+        issues.push((0, err));
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_hex;
+    use crate::test_helpers::parse_item;
+
+    fn cid(hex: &str) -> Result<String, String> {
+        describe(TAG_CID, &parse_item(hex))
+    }
+
+    fn issues(hex: &str) -> Vec<(usize, String)> {
+        strictness_issues(&parse_hex(hex).unwrap())
+    }
+
+    #[test]
+    fn cids() {
+        // The dag-cbor CIDv1 of the empty map.
+        assert_eq!(
+            cid("5825 00 01711220 \
+                 c19a797fa1fd590cd2e5b42d1cf5f246e29b91684e2f87404b81dc345c7a56a0"),
+            Ok(
+                "bafyreigbtj4x7ip5legnfznufuopl4sg4knzc2cof6duas4b3q2fy6swua \
+                 (v1, dag-cbor, sha2-256)"
+                    .to_string()
+            )
+        );
+        // A CIDv0, which is a bare SHA-256 multihash.
+        assert_eq!(
+            cid("5823 00 1220 \
+                 e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            Ok("QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n (v0, dag-pb, sha2-256)".to_string())
+        );
+        assert_eq!(
+            cid("45 00 01550003"),
+            Err("multihash digest has 0 bytes, expected 3".to_string())
+        );
+        assert_eq!(
+            cid("43 01 0155"),
+            Err("unexpected multibase prefix 0x01".to_string())
+        );
+        assert_eq!(
+            cid("43 00 0255"),
+            Err("unsupported CID version 2".to_string())
+        );
+        assert_eq!(cid("01"), Err("expected bstr".to_string()));
+    }
+
+    #[test]
+    fn strictness() {
+        assert_eq!(issues("a2 616101 62616202 d82a420001"), vec![]);
+        assert_eq!(
+            issues("a2 62616202 616101"),
+            vec![(5, "map key out of order".to_string())]
+        );
+        assert_eq!(
+            issues("a2 616101 616101"),
+            vec![(4, "duplicate map key".to_string())]
+        );
+        assert_eq!(
+            issues("a10102"),
+            vec![(1, "map key is not a tstr".to_string())]
+        );
+        assert_eq!(issues("9f01ff"), vec![(0, "indefinite length".to_string())]);
+        assert_eq!(issues("c101"), vec![(0, "tag 1".to_string())]);
+        assert_eq!(
+            issues("83 f7 f93c00 fb7ff0000000000000"),
+            vec![
+                (1, "undefined".to_string()),
+                (2, "float not in 64 bits".to_string()),
+                (5, "NaN or infinity".to_string())
+            ]
+        );
+        assert_eq!(
+            issues("82 f4 1801"),
+            vec![(2, "argument not in shortest form".to_string())]
+        );
+        assert_eq!(issues("82f4"), vec![(0, "truncated item".to_string())]);
+    }
+}
@@ -0,0 +1,403 @@
+use std::fmt::Write as _;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use bstr::ByteSlice as _;
+use half::f16;
+
+use crate::cbor_object::CborObject;
+use crate::edn;
+use crate::format_hex;
+use crate::type_byte_string::ByteStringWithEmbedded;
+use crate::type_simple_or_float::{
+    SIMPLE_VALUE_FALSE, SIMPLE_VALUE_NULL, SIMPLE_VALUE_TRUE, SIMPLE_VALUE_UNDEFINED,
+};
+use crate::type_tag::Tag;
+
+/// Width up to which arrays, maps and tags are kept on one line when pretty-printing.
+const LINE_WIDTH: usize = 72;
+
+pub fn bytes_diagnostic(bytes: &[u8]) -> String {
+    format!("h'{}'", format_hex(bytes))
+}
+
+pub fn text_diagnostic(text: &[u8]) -> String {
+    let mut escaped = String::from("\"");
+    for c in text.to_str_lossy().chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn float(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+/// The encoding indicator of a float encoded on `length` bytes when a shorter encoding
+/// would hold the same value.
+fn float_indicator(value: f64, length: usize) -> Option<&'static str> {
+    let preferred_length = if value.is_nan() || f64::from(f16::from_f64(value)) == value {
+        2
+    } else if f64::from(value as f32) == value {
+        4
+    } else {
+        8
+    };
+    match length {
+        _ if length <= preferred_length => None,
+        4 => Some("_2"),
+        8 => Some("_3"),
+        _ => None,
+    }
+}
+
+fn is_indefinite(items: &[CborObject]) -> bool {
+    items.last().is_some_and(|item| item.is_break())
+}
+
+/// How items are written in extended diagnostic notation (RFC 8610 Appendix G).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiagnosticOptions {
+    /// Mark the heads that are longer than needed with `_0` to `_3`.
+    pub encoding_indicators: bool,
+    /// Write byte strings as `b64'...'` instead of `h'...'`.
+    pub base64: bool,
+}
+
+impl DiagnosticOptions {
+    fn indicator(&self, indicator: Option<&'static str>) -> &'static str {
+        match indicator {
+            Some(indicator) if self.encoding_indicators => indicator,
+            _ => "",
+        }
+    }
+
+    fn bytes(&self, bytes: &[u8]) -> String {
+        match self.base64 {
+            true => format!("b64'{}'", STANDARD.encode(bytes)),
+            false => bytes_diagnostic(bytes),
+        }
+    }
+
+    /// An embedded item as `<<item>>`, with `content` rendering it from its single-line
+    /// rendering, when encoding the rendered item gives back the byte string, or else the
+    /// bytes. Rendering the item on a single line only once keeps nested embedded items from
+    /// taking exponential time.
+    fn embedded(
+        &self,
+        x: &ByteStringWithEmbedded,
+        content: impl FnOnce(String) -> String,
+    ) -> String {
+        let indicator = self.indicator(x.encoding_indicator());
+        let line = self.line(x.value());
+        match edn::encode(&line) {
+            Ok(encoded) if encoded == x.raw_value() => {
+                format!("<<{}>>{}", content(line), indicator)
+            }
+            _ => format!("{}{}", self.bytes(x.raw_value()), indicator),
+        }
+    }
+
+    /// The opening bracket or brace of an array or a map, with its indicator.
+    fn open(&self, bracket: char, items: &[CborObject], indicator: Option<&'static str>) -> String {
+        match is_indefinite(items) {
+            true => format!("{}_ ", bracket),
+            false => match self.indicator(indicator) {
+                "" => bracket.to_string(),
+                indicator => format!("{}{} ", bracket, indicator),
+            },
+        }
+    }
+
+    /// Renders an item on a single line.
+    fn line(&self, object: &CborObject) -> String {
+        let join = |items: &[CborObject]| {
+            let items: Vec<_> = items
+                .iter()
+                .filter(|item| !item.is_break())
+                .map(|item| self.line(item))
+                .collect();
+            items.join(", ")
+        };
+        match object {
+            CborObject::UnsignedInteger(x) => match x.value() {
+                Some(value) => format!("{}{}", value, self.indicator(x.encoding_indicator())),
+                None => "?".to_string(),
+            },
+            CborObject::NegativeInteger(x) => match x.value() {
+                Some(value) => format!(
+                    "{}{}",
+                    -1 - i128::from(value),
+                    self.indicator(x.encoding_indicator())
+                ),
+                None => "?".to_string(),
+            },
+            CborObject::ByteString(x) => format!(
+                "{}{}",
+                self.bytes(x.value()),
+                self.indicator(x.encoding_indicator())
+            ),
+            CborObject::ByteStringWithEmbedded(x) => self.embedded(x, |line| line),
+            CborObject::IndefiniteByteString(x) => match join(x.chunks()).as_str() {
+                "" => "''_".to_string(),
+                chunks => format!("(_ {})", chunks),
+            },
+            CborObject::TextString(x) => format!(
+                "{}{}",
+                text_diagnostic(x.value()),
+                self.indicator(x.encoding_indicator())
+            ),
+            CborObject::IndefiniteTextString(x) => match join(x.chunks()).as_str() {
+                "" => "\"\"_".to_string(),
+                chunks => format!("(_ {})", chunks),
+            },
+            CborObject::Array(x) => format!(
+                "{}{}]",
+                self.open('[', x.value(), x.encoding_indicator()),
+                join(x.value())
+            ),
+            CborObject::Map(x) => {
+                let items: Vec<_> = x.value().iter().filter(|x| !x.is_break()).collect();
+                let pairs: Vec<_> = items
+                    .chunks(2)
+                    .map(|pair| match pair {
+                        [key, value] => format!("{}: {}", self.line(key), self.line(value)),
+                        [key] => format!("{}: ?", self.line(key)),
+                        // unreachable safety: chunks(2) yields one or two items
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                format!(
+                    "{}{}}}",
+                    self.open('{', x.value(), x.encoding_indicator()),
+                    pairs.join(", ")
+                )
+            }
+            CborObject::Tag(x) => format!("{}({})", self.tag(x), self.line(x.payload())),
+            CborObject::SimpleValue(x) => match x.value() {
+                SIMPLE_VALUE_FALSE => "false".to_string(),
+                SIMPLE_VALUE_TRUE => "true".to_string(),
+                SIMPLE_VALUE_NULL => "null".to_string(),
+                SIMPLE_VALUE_UNDEFINED => "undefined".to_string(),
+                value => format!("simple({})", value),
+            },
+            CborObject::HalfPrecisionFloat(x) => float(x.value().into()),
+            CborObject::SinglePrecisionFloat(x) => {
+                let value = x.value().into();
+                format!(
+                    "{}{}",
+                    float(value),
+                    self.indicator(float_indicator(value, 4))
+                )
+            }
+            CborObject::DoublePrecisionFloat(x) => {
+                let value = x.value();
+                format!(
+                    "{}{}",
+                    float(value),
+                    self.indicator(float_indicator(value, 8))
+                )
+            }
+            CborObject::ReservedSimpleOrFloat(x) => {
+                format!("reserved({})", x.additional_info_argument())
+            }
+            CborObject::Break(_) => "break".to_string(),
+        }
+    }
+
+    fn tag(&self, tag: &Tag) -> String {
+        match tag.tag() {
+            Some(number) => format!("{}{}", number, self.indicator(tag.encoding_indicator())),
+            None => "?".to_string(),
+        }
+    }
+
+    /// Renders an item indented by `indent` spaces, breaking the arrays, maps and
+    /// indefinite-length strings that do not fit on the line into one item per line.
+    fn pretty(&self, object: &CborObject, indent: usize) -> String {
+        let line = self.line(object);
+        if indent + line.chars().count() <= LINE_WIDTH {
+            return line;
+        }
+        let lines = |open: String, items: Vec<String>, close: &str| {
+            let inner = " ".repeat(indent + 2);
+            let items: Vec<_> = items
+                .iter()
+                .map(|item| format!("{}{}", inner, item))
+                .collect();
+            format!(
+                "{}\n{}\n{}{}",
+                open.trim_end(),
+                items.join(",\n"),
+                " ".repeat(indent),
+                close
+            )
+        };
+        let children = |items: &[CborObject]| {
+            items
+                .iter()
+                .filter(|item| !item.is_break())
+                .map(|item| self.pretty(item, indent + 2))
+                .collect()
+        };
+        match object {
+            CborObject::ByteStringWithEmbedded(x) => {
+                self.embedded(x, |_| self.pretty(x.value(), indent))
+            }
+            CborObject::IndefiniteByteString(x) => {
+                lines("(_".to_string(), children(x.chunks()), ")")
+            }
+            CborObject::IndefiniteTextString(x) => {
+                lines("(_".to_string(), children(x.chunks()), ")")
+            }
+            CborObject::Array(x) => lines(
+                self.open('[', x.value(), x.encoding_indicator()),
+                children(x.value()),
+                "]",
+            ),
+            CborObject::Map(x) => {
+                let items: Vec<_> = x.value().iter().filter(|x| !x.is_break()).collect();
+                let pairs = items
+                    .chunks(2)
+                    .map(|pair| match pair {
+                        [key, value] => {
+                            format!("{}: {}", self.line(key), self.pretty(value, indent + 2))
+                        }
+                        [key] => format!("{}: ?", self.line(key)),
+                        // unreachable safety: chunks(2) yields one or two items
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                lines(
+                    self.open('{', x.value(), x.encoding_indicator()),
+                    pairs,
+                    "}",
+                )
+            }
+            CborObject::Tag(x) => format!("{}({})", self.tag(x), self.pretty(x.payload(), indent)),
+            _ => line,
+        }
+    }
+}
+
+/// Renders an item on a single line in diagnostic notation (RFC 8949 Section 8).
+pub fn diagnostic(object: &CborObject) -> String {
+    DiagnosticOptions::default().line(object)
+}
+
+/// Renders an item in extended diagnostic notation, over several indented lines when it
+/// does not fit on one.
+pub fn pretty_diagnostic(object: &CborObject, options: &DiagnosticOptions) -> String {
+    options.pretty(object, 0)
+}
+
+/// Like [`diagnostic`], but cut short so that it fits in a tree comment.
+pub fn short_diagnostic(object: &CborObject) -> String {
+    const MAX_CHARS: usize = 64;
+    let text = diagnostic(object);
+    if text.chars().count() > MAX_CHARS {
+        let mut text: String = text.chars().take(MAX_CHARS).collect();
+        text.push_str("...");
+        text
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::parse_item;
+
+    fn pretty(hex: &str, options: DiagnosticOptions) -> String {
+        pretty_diagnostic(&parse_item(hex), &options)
+    }
+
+    #[test]
+    fn encoding_indicators() {
+        let options = DiagnosticOptions {
+            encoding_indicators: true,
+            ..Default::default()
+        };
+        // {1: 5 with a one-byte argument, "a": [24, -1 with a two-byte argument]}
+        assert_eq!(
+            pretty("a2 01 1805 6161 82 1818 390000", options),
+            "{1: 5_0, \"a\": [24, -1_1]}"
+        );
+        assert_eq!(pretty("98 01 f9 3e00", options), "[_0 1.5]");
+        assert_eq!(pretty("9f fa 3fc00000 ff", options), "[_ 1.5_2]");
+        assert_eq!(pretty("d9 0001 fb 3ff199999999999a", options), "1_1(1.1)");
+        assert_eq!(pretty("1805", DiagnosticOptions::default()), "5");
+    }
+
+    #[test]
+    fn byte_strings_and_indentation() {
+        let options = DiagnosticOptions {
+            base64: true,
+            ..Default::default()
+        };
+        assert_eq!(pretty("43 010203", options), "b64'AQID'");
+        assert_eq!(pretty("d8 18 43 a1 01 02", options), "24(<<{1: 2}>>)");
+        let long = format!("78 28 {}", "61".repeat(40));
+        assert_eq!(
+            pretty(&format!("a1 01 82 {} {}", long, long), options),
+            format!(
+                "{{\n  1: [\n    \"{}\",\n    \"{}\"\n  ]\n}}",
+                "a".repeat(40),
+                "a".repeat(40)
+            )
+        );
+        assert_eq!(pretty("62 0a22", options), "\"\\n\\\"\"",);
+    }
+
+    #[test]
+    fn embedded_items_and_empty_strings() {
+        let options = DiagnosticOptions::default();
+        assert_eq!(pretty("d8 18 42 1817", options), "24(h'1817')");
+        assert_eq!(pretty("d8 18 41 5e", options), "24(h'5e')");
+        assert_eq!(pretty("d8 18 43 62971a", options), "24(h'62971a')");
+        assert_eq!(pretty("d8 18 43 f97e00", options), "24(<<NaN>>)");
+        let indicators = DiagnosticOptions {
+            encoding_indicators: true,
+            ..Default::default()
+        };
+        assert_eq!(pretty("d8 18 42 1817", indicators), "24(<<23_0>>)");
+        assert_eq!(pretty("5f ff", options), "''_");
+        assert_eq!(pretty("7f ff", options), "\"\"_");
+        assert_eq!(pretty("5f 42 0102 ff", options), "(_ h'0102')");
+    }
+
+    #[test]
+    fn deeply_nested_embedded_items() {
+        let mut hex = "01".to_string();
+        for _ in 0..40 {
+            let len = hex.len() / 2;
+            hex = match len {
+                0..24 => format!("{:02x}{}", 0x40 + len, hex),
+                _ => format!("58{:02x}{}", len, hex),
+            };
+        }
+        let expected = format!("{}1{}", "<<".repeat(40), ">>".repeat(40));
+        let object = parse_item(&hex);
+        assert_eq!(diagnostic(&object), expected);
+        assert_eq!(
+            pretty_diagnostic(&object, &DiagnosticOptions::default()),
+            expected
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Names of private tags and map keys, declared by the user in a TOML file:
+//!
+//! ```toml
+//! [tags]
+//! 60000 = "sensor reading"
+//!
+//! [keys]
+//! 1 = "device_id"
+//!
+//! # Keys of the maps inside tag 60000.
+//! [keys."tag(60000)"]
+//! 1 = "sensor"
+//!
+//! # Keys of the maps in the array under key 4 of the top-level map.
+//! [keys."/4/*"]
+//! 1 = "unit"
+//! ```
+//!
+//! A key is named by the first path scope that matches the map, then by the innermost
+//! enclosing tag with a scope, then by the top-level `[keys]` table.
+
+use std::collections::BTreeMap;
+
+use crate::cbor_object::CborObject;
+
+/// Path segment that matches any map key or array index.
+const WILDCARD: &str = "*";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Label {
+    Int(i128),
+    Text(String),
+}
+
+impl Label {
+    fn parse(text: &str) -> Label {
+        match text.parse() {
+            Ok(value) => Label::Int(value),
+            Err(_) => Label::Text(text.to_string()),
+        }
+    }
+
+    fn matches(&self, key: &CborObject) -> bool {
+        match self {
+            Label::Int(label) => key.as_integer() == Some(*label),
+            Label::Text(label) => key.as_text().as_deref() == Some(label.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Scope {
+    Global,
+    Tag(u64),
+    Path(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KeyNames {
+    scope: Scope,
+    names: Vec<(Label, String)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dictionary {
+    tags: BTreeMap<u64, String>,
+    keys: Vec<KeyNames>,
+}
+
+/// Returns the path segment of a map key or array element: integers and text strings as
+/// they are, anything else as `?`.
+pub fn segment(key: &CborObject) -> String {
+    match (key.as_integer(), key.as_text()) {
+        (Some(value), _) => value.to_string(),
+        (_, Some(text)) => text,
+        _ => "?".to_string(),
+    }
+}
+
+fn parse_scope(text: &str) -> Result<Scope, String> {
+    if let Some(path) = text.strip_prefix('/') {
+        let segments = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect();
+        return Ok(Scope::Path(segments));
+    }
+    let tag = text
+        .strip_prefix("tag(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .and_then(|tag| tag.trim().parse().ok());
+    match tag {
+        Some(tag) => Ok(Scope::Tag(tag)),
+        None => Err(format!(
+            "unknown scope {:?}: expected a path such as \"/1/*\" or \"tag(N)\"",
+            text
+        )),
+    }
+}
+
+fn parse_names(scope: Scope, table: &toml::Table) -> Result<KeyNames, String> {
+    let names = table
+        .iter()
+        .map(|(label, name)| match name.as_str() {
+            Some(name) => Ok((Label::parse(label), name.to_string())),
+            None => Err(format!("name of key {} is not a string", label)),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(KeyNames { scope, names })
+}
+
+impl Dictionary {
+    pub fn parse(text: &str) -> Result<Dictionary, String> {
+        let table: toml::Table = text
+            .parse()
+            .map_err(|err: toml::de::Error| err.message().to_string())?;
+        let mut dictionary = Dictionary::default();
+        for (section, content) in &table {
+            let Some(content) = content.as_table() else {
+                return Err(format!("{} is not a table", section));
+            };
+            match section.as_str() {
+                "tags" => {
+                    for (tag, name) in content {
+                        let tag = tag
+                            .parse()
+                            .map_err(|_| format!("tag {:?} is not an unsigned integer", tag))?;
+                        let name = name
+                            .as_str()
+                            .ok_or_else(|| format!("name of tag {} is not a string", tag))?;
+                        dictionary.tags.insert(tag, name.to_string());
+                    }
+                }
+                "keys" => {
+                    let (scopes, global): (toml::Table, toml::Table) = content
+                        .clone()
+                        .into_iter()
+                        .partition(|(_, value)| value.is_table());
+                    dictionary.keys.push(parse_names(Scope::Global, &global)?);
+                    for (scope, names) in &scopes {
+                        // unwrap safety: partitioned on being a table
+                        let names = names.as_table().unwrap();
+                        dictionary
+                            .keys
+                            .push(parse_names(parse_scope(scope)?, names)?);
+                    }
+                }
+                _ => return Err(format!("unknown section [{}]", section)),
+            }
+        }
+        Ok(dictionary)
+    }
+
+    pub fn tag_name(&self, tag: u64) -> Option<&str> {
+        self.tags.get(&tag).map(String::as_str)
+    }
+
+    /// Names `key` of the map at `path` inside the tags `enclosing_tags`, innermost last.
+    pub fn key_name(
+        &self,
+        key: &CborObject,
+        path: &[String],
+        enclosing_tags: &[u64],
+    ) -> Option<&str> {
+        let path_matches = |segments: &[String]| {
+            segments.len() == path.len()
+                && segments
+                    .iter()
+                    .zip(path)
+                    .all(|(segment, step)| segment == WILDCARD || segment == step)
+        };
+        self.scoped_key_name(
+            key,
+            |scope| matches!(scope, Scope::Path(segments) if path_matches(segments)),
+        )
+        .or_else(|| {
+            enclosing_tags
+                .iter()
+                .rev()
+                .find_map(|tag| self.scoped_key_name(key, |scope| *scope == Scope::Tag(*tag)))
+        })
+        .or_else(|| self.scoped_key_name(key, |scope| *scope == Scope::Global))
+    }
+
+    fn scoped_key_name(&self, key: &CborObject, in_scope: impl Fn(&Scope) -> bool) -> Option<&str> {
+        self.keys
+            .iter()
+            .filter(|keys| in_scope(&keys.scope))
+            .find_map(|keys| keys.names.iter().find(|(label, _)| label.matches(key)))
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::parse_item;
+
+    const DICTIONARY: &str = r#"
+        [tags]
+        60000 = "sensor reading"
+
+        [keys]
+        1 = "device_id"
+        name = "name"
+
+        [keys."tag(60000)"]
+        1 = "sensor"
+
+        [keys."/4/*"]
+        1 = "unit"
+    "#;
+
+    #[test]
+    fn names() {
+        let dictionary = Dictionary::parse(DICTIONARY).unwrap();
+        assert_eq!(dictionary.tag_name(60000), Some("sensor reading"));
+        assert_eq!(dictionary.tag_name(60001), None);
+
+        let one = parse_item("01");
+        let path = |segments: &[&str]| segments.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(dictionary.key_name(&one, &[], &[]), Some("device_id"));
+        assert_eq!(dictionary.key_name(&one, &[], &[60000]), Some("sensor"));
+        assert_eq!(
+            dictionary.key_name(&one, &path(&["4", "0"]), &[60000]),
+            Some("unit")
+        );
+        assert_eq!(
+            dictionary.key_name(&one, &path(&["4"]), &[]),
+            Some("device_id")
+        );
+        assert_eq!(
+            dictionary.key_name(&parse_item("646e616d65"), &[], &[60000]),
+            Some("name")
+        );
+        assert_eq!(dictionary.key_name(&parse_item("02"), &[], &[]), None);
+    }
+
+    #[test]
+    fn invalid_dictionaries() {
+        assert_eq!(
+            Dictionary::parse("[keys]\n1 = 2"),
+            Err("name of key 1 is not a string".to_string())
+        );
+        assert_eq!(
+            Dictionary::parse("[tags]\nx = \"y\""),
+            Err("tag \"x\" is not an unsigned integer".to_string())
+        );
+        assert_eq!(
+            Dictionary::parse("[keys.\"3\"]\n1 = \"x\""),
+            Err("unknown scope \"3\": expected a path such as \"/1/*\" or \"tag(N)\"".to_string())
+        );
+        assert_eq!(
+            Dictionary::parse("[names]"),
+            Err("unknown section [names]".to_string())
+        );
+    }
+}
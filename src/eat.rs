@@ -0,0 +1,130 @@
+//! Entity Attestation Tokens (RFC 9711): the values of the claims that EAT adds to CWT
+//! claims sets.
+
+use crate::cbor_object::CborObject;
+use crate::context::Context;
+use crate::cose;
+use crate::coswid;
+use crate::cwt;
+use crate::schema::{Entry, Field, Schema};
+
+pub const DEBUG_STATUS: &[(i64, &str)] = &[
+    (0, "enabled"),
+    (1, "disabled"),
+    (2, "disabled-since-boot"),
+    (3, "disabled-permanently"),
+    (4, "disabled-fully-and-permanently"),
+];
+
+pub const INTENDED_USES: &[(i64, &str)] = &[
+    (1, "generic"),
+    (2, "registration"),
+    (3, "provisioning"),
+    (4, "csr"),
+    (5, "pop"),
+];
+
+pub const LOCATION: Schema = Schema::Map(&[
+    Entry::int(1, "latitude", Schema::Any),
+    Entry::int(2, "longitude", Schema::Any),
+    Entry::int(3, "altitude", Schema::Any),
+    Entry::int(4, "accuracy", Schema::Any),
+    Entry::int(5, "altitude-accuracy", Schema::Any),
+    Entry::int(6, "heading", Schema::Any),
+    Entry::int(7, "speed", Schema::Any),
+    Entry::int(8, "timestamp", Schema::Time),
+    Entry::int(9, "age", Schema::Any),
+]);
+
+/// Names the type of a UEID from its first byte (RFC 9711 Section 4.2.1).
+fn select_ueid(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    let Some(bytes) = object.as_bytes() else {
+        return (
+            Schema::Any,
+            Some("invalid ueid: expected a bstr".to_string()),
+        );
+    };
+    let description = match bytes.first() {
+        Some(0x01) => format!("RAND, {} bytes", bytes.len() - 1),
+        Some(0x02) => format!("IEEE EUI, {} bytes", bytes.len() - 1),
+        Some(0x03) => format!("IMEI, {} bytes", bytes.len() - 1),
+        Some(other) => format!("invalid ueid: unknown type {:#04x}", other),
+        None => "invalid ueid: empty".to_string(),
+    };
+    (Schema::Opaque, Some(description))
+}
+
+pub const UEID: Schema = Schema::Select(select_ueid);
+
+/// Shows a measurement or manifest with the structure of its Content-Format when it is
+/// known, such as a CoSWID.
+fn select_format(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    let content_type = match object {
+        CborObject::Array(array) => array.value().first().and_then(CborObject::as_integer),
+        _ => None,
+    };
+    match content_type {
+        Some(content_type) if content_type == i128::from(coswid::CONTENT_FORMAT_COSWID) => {
+            (FORMAT_COSWID, None)
+        }
+        _ => (FORMAT_OPAQUE, None),
+    }
+}
+
+const fn format(content: &'static Schema) -> [Field; 2] {
+    [
+        Field {
+            name: "content-type",
+            schema: Schema::Any,
+        },
+        Field {
+            name: "content-format",
+            schema: Schema::Embedded(content),
+        },
+    ]
+}
+
+static FORMAT_COSWID: Schema = Schema::Array(&format(&coswid::COSWID));
+const FORMAT_OPAQUE: Schema = Schema::Array(&[
+    Field {
+        name: "content-type",
+        schema: Schema::Any,
+    },
+    Field {
+        name: "content-format",
+        schema: Schema::Opaque,
+    },
+]);
+
+pub const FORMATS: Schema = Schema::ArrayOf(&Schema::Select(select_format));
+
+/// A submodule is a claims set, a nested token or the digest of a detached claims set.
+fn select_submodule(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    match object {
+        CborObject::Map(_) => (cwt::CLAIMS, None),
+        CborObject::Array(_) => (DETACHED_DIGEST, None),
+        _ if object.as_bytes().is_some() => (Schema::Embedded(&cwt::CWT), None),
+        _ => (Schema::Any, None),
+    }
+}
+
+const DETACHED_DIGEST: Schema = Schema::Array(&[
+    Field {
+        name: "algorithm",
+        schema: Schema::Values(cose::ALGORITHMS),
+    },
+    Field {
+        name: "digest",
+        schema: Schema::Opaque,
+    },
+]);
+
+pub const SUBMODULES: Schema = Schema::MapOf(&Schema::Select(select_submodule));
+
+/// Returns the name of the tag of unprotected claims sets and the structure of its content.
+pub fn tag_schema(tag: u64) -> Option<(&'static str, Schema)> {
+    match tag {
+        cwt::TAG_UCCS => Some(("uccs", cwt::CLAIMS)),
+        _ => None,
+    }
+}
@@ -0,0 +1,528 @@
+//! Encoding of items written in extended diagnostic notation (RFC 8949 Section 8 and
+//! RFC 8610 Appendix G), keeping the encoding indicators and indefinite lengths they ask for.
+
+use half::f16;
+
+use crate::cbor_parser::{
+    MAJOR_ARRAY, MAJOR_BYTE_STRING, MAJOR_MAP, MAJOR_NEGATIVE_INTEGER, MAJOR_SIMPLE_OR_FLOAT,
+    MAJOR_TAG, MAJOR_TEXT_STRING, MAJOR_UNSIGNED_INTEGER,
+};
+use crate::cddl::{decode_base64, decode_hex, unescape};
+use crate::value::{encode_float, encode_head};
+
+const BREAK: u8 = 0xff;
+
+/// Encodes a head with the argument length given by an encoding indicator `_0` to `_3`, or
+/// else with the preferred one.
+pub fn head(
+    major_type: u8,
+    argument: u64,
+    indicator: Option<u8>,
+    output: &mut Vec<u8>,
+) -> Result<(), String> {
+    let Some(indicator) = indicator else {
+        encode_head(major_type, argument, output);
+        return Ok(());
+    };
+    let length = 1usize << indicator;
+    if length < 8 && argument >> (8 * length) != 0 {
+        return Err(format!("{} does not fit in _{}", argument, indicator));
+    }
+    output.push((major_type << 5) | (24 + indicator));
+    output.extend(&argument.to_be_bytes()[8 - length..]);
+    Ok(())
+}
+
+fn float(value: f64, indicator: Option<u8>, output: &mut Vec<u8>) -> Result<(), String> {
+    let fits = |narrowed: f64| narrowed == value || (value.is_nan() && narrowed.is_nan());
+    match indicator {
+        None => encode_float(value, output),
+        Some(1) if fits(f16::from_f64(value).into()) => {
+            output.push(0xf9);
+            output.extend(f16::from_f64(value).to_be_bytes());
+        }
+        Some(2) if fits((value as f32).into()) => {
+            output.push(0xfa);
+            output.extend((value as f32).to_be_bytes());
+        }
+        Some(3) => {
+            output.push(0xfb);
+            output.extend(value.to_be_bytes());
+        }
+        Some(indicator) => {
+            return Err(format!("{:?} does not fit in _{}", value, indicator));
+        }
+    }
+    Ok(())
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn error(&self, message: impl Into<String>) -> String {
+        let line = self.text[..self.position].matches('\n').count() + 1;
+        format!("line {}: {}", line, message.into())
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        self.skip_space()?;
+        if self.eat(token) {
+            Ok(())
+        } else if self.rest().is_empty() {
+            Err(format!("end of text: expected {}", token))
+        } else {
+            Err(self.error(format!("expected {}", token)))
+        }
+    }
+
+    /// Skips white space and comments, both `# ...` to the end of the line and `/ ... /`.
+    fn skip_space(&mut self) -> Result<(), String> {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+            if self.eat("#") {
+                let length = self.rest().find('\n').unwrap_or(self.rest().len());
+                self.position += length;
+            } else if self.rest().starts_with('/') {
+                let Some(length) = self.rest()[1..].find('/') else {
+                    return Err(self.error("unterminated comment"));
+                };
+                self.position += length + 2;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// An encoding indicator right after an item or an opening bracket, as in `1_0`.
+    fn indicator(&mut self) -> Option<u8> {
+        let mut chars = self.rest().chars();
+        match (chars.next(), chars.next()) {
+            (Some('_'), Some(digit @ '0'..='3')) => {
+                self.position += 2;
+                Some(digit as u8 - b'0')
+            }
+            _ => None,
+        }
+    }
+
+    /// Items separated by commas, up to `end` or to the end of the text.
+    fn sequence(
+        &mut self,
+        end: Option<&str>,
+        depth: usize,
+        output: &mut Vec<u8>,
+    ) -> Result<usize, String> {
+        let mut count = 0;
+        loop {
+            self.skip_space()?;
+            match end {
+                Some(end) if self.eat(end) => return Ok(count),
+                None if self.rest().is_empty() => return Ok(count),
+                _ => {}
+            }
+            if count > 0 {
+                self.expect(",")?;
+                self.skip_space()?;
+            }
+            self.item(depth, output)?;
+            count += 1;
+        }
+    }
+
+    /// The length indicator after `[` or `{`, which is `None` for an indefinite length.
+    fn container_indicator(&mut self) -> Option<Option<u8>> {
+        if let Some(indicator) = self.indicator() {
+            return Some(Some(indicator));
+        }
+        match self.eat("_") {
+            true => None,
+            false => Some(None),
+        }
+    }
+
+    fn item(&mut self, depth: usize, output: &mut Vec<u8>) -> Result<(), String> {
+        if depth > 256 {
+            return Err(self.error("too deeply nested"));
+        }
+        self.skip_space()?;
+        if self.eat("[") {
+            let indicator = self.container_indicator();
+            let mut items = vec![];
+            let count = self.sequence(Some("]"), depth + 1, &mut items)? as u64;
+            match indicator {
+                Some(indicator) => {
+                    head(MAJOR_ARRAY, count, indicator, output).map_err(|err| self.error(err))?
+                }
+                None => output.push((MAJOR_ARRAY << 5) | 31),
+            }
+            output.extend(items);
+            if indicator.is_none() {
+                output.push(BREAK);
+            }
+            Ok(())
+        } else if self.eat("{") {
+            let indicator = self.container_indicator();
+            let mut pairs = vec![];
+            let mut count = 0;
+            loop {
+                self.skip_space()?;
+                if self.eat("}") {
+                    break;
+                }
+                if count > 0 {
+                    self.expect(",")?;
+                }
+                self.item(depth + 1, &mut pairs)?;
+                self.expect(":")?;
+                self.item(depth + 1, &mut pairs)?;
+                count += 1;
+            }
+            match indicator {
+                Some(indicator) => {
+                    head(MAJOR_MAP, count, indicator, output).map_err(|err| self.error(err))?
+                }
+                None => output.push((MAJOR_MAP << 5) | 31),
+            }
+            output.extend(pairs);
+            if indicator.is_none() {
+                output.push(BREAK);
+            }
+            Ok(())
+        } else if self.eat("(") {
+            self.indefinite_string(depth, output)
+        } else if self.eat("<<") {
+            let mut embedded = vec![];
+            self.sequence(Some(">>"), depth + 1, &mut embedded)?;
+            self.string(MAJOR_BYTE_STRING, &embedded, output)
+        } else if self.eat("\"") {
+            let text = self.quoted('"')?;
+            self.string(MAJOR_TEXT_STRING, text.as_bytes(), output)
+        } else if self.eat("'") {
+            let text = self.quoted('\'')?;
+            self.string(MAJOR_BYTE_STRING, text.as_bytes(), output)
+        } else if self.eat("h'") {
+            let content = self.raw_quoted()?;
+            let hex = strip_comments(&content).map_err(|err| self.error(err))?;
+            let bytes = decode_hex(&hex).map_err(|err| self.error(err))?;
+            self.string(MAJOR_BYTE_STRING, &bytes, output)
+        } else if self.eat("b64'") {
+            let content = self.raw_quoted()?;
+            let bytes = decode_base64(&content).map_err(|err| self.error(err))?;
+            self.string(MAJOR_BYTE_STRING, &bytes, output)
+        } else if self.peek().is_some_and(|c| c.is_ascii_digit() || c == '-') {
+            self.number(depth, output)
+        } else {
+            self.word(output)
+        }
+    }
+
+    /// The content of a string up to the closing `quote`, with its escapes resolved.
+    fn quoted(&mut self, quote: char) -> Result<String, String> {
+        let mut escaped = false;
+        let Some(length) = self.rest().find(|c| {
+            let end = c == quote && !escaped;
+            escaped = c == '\\' && !escaped;
+            end
+        }) else {
+            return Err(self.error("unterminated string"));
+        };
+        let content = &self.rest()[..length];
+        let text = unescape(content).map_err(|err| self.error(err))?;
+        self.position += length + 1;
+        Ok(text)
+    }
+
+    /// The content of `h'...'` or `b64'...'` up to the closing quote.
+    fn raw_quoted(&mut self) -> Result<String, String> {
+        let Some(length) = self.rest().find('\'') else {
+            return Err(self.error("unterminated byte string"));
+        };
+        let content = self.rest()[..length].to_string();
+        self.position += length + 1;
+        Ok(content)
+    }
+
+    /// A definite-length string with the encoding indicator that follows it, or an empty
+    /// indefinite-length string such as `''_`.
+    fn string(&mut self, major_type: u8, bytes: &[u8], output: &mut Vec<u8>) -> Result<(), String> {
+        let indicator = self.indicator();
+        if indicator.is_none() && bytes.is_empty() && self.eat("_") {
+            output.extend([(major_type << 5) | 31, BREAK]);
+            return Ok(());
+        }
+        head(major_type, bytes.len() as u64, indicator, output).map_err(|err| self.error(err))?;
+        output.extend(bytes);
+        Ok(())
+    }
+
+    /// `(_ chunk, ...)`, an indefinite-length string of the type of its chunks.
+    fn indefinite_string(&mut self, depth: usize, output: &mut Vec<u8>) -> Result<(), String> {
+        self.skip_space()?;
+        if !self.eat("_") {
+            return Err(self.error("expected _ after ("));
+        }
+        let mut chunks = vec![];
+        let mut major_type = None;
+        loop {
+            self.skip_space()?;
+            if self.eat(")") {
+                break;
+            }
+            if major_type.is_some() {
+                self.expect(",")?;
+            }
+            let start = chunks.len();
+            self.item(depth + 1, &mut chunks)?;
+            let chunk_type = chunks[start] >> 5;
+            let definite = chunks[start] & 0x1f != 31;
+            if !definite || major_type.is_some_and(|major_type| major_type != chunk_type) {
+                return Err(
+                    self.error("the chunks of a string must be definite strings of its type")
+                );
+            }
+            if chunk_type != MAJOR_BYTE_STRING && chunk_type != MAJOR_TEXT_STRING {
+                return Err(self.error("the chunks of a string must be strings"));
+            }
+            major_type = Some(chunk_type);
+        }
+        let Some(major_type) = major_type else {
+            return Err(self.error("an empty (_ ) has no string type, write ''_ or \"\"_"));
+        };
+        output.push((major_type << 5) | 31);
+        output.extend(chunks);
+        output.push(BREAK);
+        Ok(())
+    }
+
+    fn number(&mut self, depth: usize, output: &mut Vec<u8>) -> Result<(), String> {
+        let negative = self.eat("-");
+        if self.eat("Infinity") {
+            let indicator = self.indicator();
+            return float(f64::NEG_INFINITY, indicator, output).map_err(|err| self.error(err));
+        }
+        let radix = if self.eat("0x") {
+            16
+        } else if self.eat("0o") {
+            8
+        } else if self.eat("0b") {
+            2
+        } else {
+            10
+        };
+        let length = self
+            .rest()
+            .find(|c: char| !(c.is_digit(radix) || radix == 10 && ".eE+-".contains(c)))
+            .unwrap_or(self.rest().len());
+        let literal = self.rest()[..length].to_string();
+        // A sign only belongs to the number right after an exponent.
+        let literal = match literal
+            .char_indices()
+            .skip(1)
+            .find(|(index, c)| "+-".contains(*c) && !literal[..*index].ends_with(['e', 'E']))
+        {
+            Some((index, _)) => &literal[..index],
+            None => &literal,
+        };
+        if literal.is_empty() {
+            return Err(self.error("expected a number"));
+        }
+        self.position += literal.len();
+        let is_float = radix == 10 && literal.contains(['.', 'e', 'E']);
+        let indicator = self.indicator();
+        if is_float {
+            let value: f64 = literal
+                .parse()
+                .map_err(|_| self.error(format!("invalid number {}", literal)))?;
+            let value = if negative { -value } else { value };
+            return float(value, indicator, output).map_err(|err| self.error(err));
+        }
+        let magnitude = u128::from_str_radix(literal, radix)
+            .map_err(|_| self.error(format!("invalid number {}", literal)))?;
+        // -n is encoded as n - 1, and -0 as 0.
+        let (major_type, value) = match negative && magnitude > 0 {
+            true => (MAJOR_NEGATIVE_INTEGER, magnitude - 1),
+            false => (MAJOR_UNSIGNED_INTEGER, magnitude),
+        };
+        let value =
+            u64::try_from(value).map_err(|_| self.error(format!("{} is out of range", literal)))?;
+        if !negative && self.eat("(") {
+            head(MAJOR_TAG, value, indicator, output).map_err(|err| self.error(err))?;
+            self.item(depth + 1, output)?;
+            return self.expect(")");
+        }
+        head(major_type, value, indicator, output).map_err(|err| self.error(err))
+    }
+
+    fn word(&mut self, output: &mut Vec<u8>) -> Result<(), String> {
+        let simple = |value: u8| (MAJOR_SIMPLE_OR_FLOAT << 5) | value;
+        for (word, byte) in [
+            ("false", simple(20)),
+            ("true", simple(21)),
+            ("null", simple(22)),
+            ("undefined", simple(23)),
+        ] {
+            if self.eat(word) {
+                output.push(byte);
+                return Ok(());
+            }
+        }
+        if self.eat("Infinity") {
+            let indicator = self.indicator();
+            return float(f64::INFINITY, indicator, output).map_err(|err| self.error(err));
+        }
+        if self.eat("NaN") {
+            let indicator = self.indicator();
+            return float(f64::NAN, indicator, output).map_err(|err| self.error(err));
+        }
+        if self.eat("simple(") {
+            self.skip_space()?;
+            let length = self
+                .rest()
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(self.rest().len());
+            let value = self.rest()[..length].parse::<u8>().ok();
+            self.position += length;
+            self.expect(")")?;
+            match value {
+                Some(value @ 0..24) => output.push(simple(value)),
+                Some(value @ 32..) => output.extend([simple(24), value]),
+                _ => return Err(self.error("invalid simple value")),
+            }
+            return Ok(());
+        }
+        match self.peek() {
+            Some(c) => Err(self.error(format!("unexpected {:?}", c))),
+            None => Err(self.error("unexpected end of text")),
+        }
+    }
+}
+
+/// Removes the comments from the content of `h'...'`.
+fn strip_comments(content: &str) -> Result<String, String> {
+    let mut stripped = String::new();
+    let mut rest = content;
+    while let Some(start) = rest.find(['#', '/']) {
+        stripped.push_str(&rest[..start]);
+        let end = match &rest[start..start + 1] {
+            "#" => rest[start..]
+                .find('\n')
+                .map_or(rest.len(), |end| start + end),
+            _ => match rest[start + 1..].find('/') {
+                Some(end) => start + end + 2,
+                None => return Err("unterminated comment".to_string()),
+            },
+        };
+        rest = &rest[end..];
+    }
+    stripped.push_str(rest);
+    Ok(stripped)
+}
+
+/// Encodes the items of `text`, separated by commas, one after the other.
+pub fn encode(text: &str) -> Result<Vec<u8>, String> {
+    let mut parser = Parser { text, position: 0 };
+    let mut output = vec![];
+    if parser.sequence(None, 0, &mut output)? == 0 {
+        return Err("no item".to_string());
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(text: &str) -> String {
+        crate::format_hex(&encode(text).unwrap())
+    }
+
+    #[test]
+    fn preferred_encoding() {
+        assert_eq!(
+            hex("[1, -1, 24, -500, 1.5, 100000.0]"),
+            "86012018183901f3f93e00fa47c35000"
+        );
+        assert_eq!(hex("{\"a\": h'01 02', 'b': true}"), "a261614201024162f5");
+        assert_eq!(hex("1(1700000000)"), "c11a6553f100");
+        assert_eq!(hex("-18446744073709551616"), "3bffffffffffffffff");
+        assert_eq!(
+            hex("simple(99), null, undefined, NaN, -Infinity"),
+            "f863f6f7f97e00f9fc00"
+        );
+        assert_eq!(hex("\"\\ud83d\\ude00\""), "64f09f9880");
+        assert_eq!(hex("b64'AQID'"), "43010203");
+    }
+
+    #[test]
+    fn encoding_indicators_and_indefinite_lengths() {
+        assert_eq!(hex("1_0, 1_3"), "18011b0000000000000001");
+        assert_eq!(hex("[_1 1.5_3]"), "990001fb3ff8000000000000");
+        assert_eq!(hex("\"a\"_0, 1_0(2)"), "780161d80102");
+        assert_eq!(hex("[_ 1, {_ 2: 3}]"), "9f01bf0203ffff");
+        assert_eq!(
+            hex("(_ h'01', h'0203'), (_ \"a\")"),
+            "5f4101420203ff7f6161ff"
+        );
+        assert_eq!(hex("<<1, 2>>, <<[]>>_1"), "42010259000180");
+        assert_eq!(hex("''_, \"\"_, [h''_]"), "5fff7fff815fff");
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            hex("[1, # one\n / two / 2, h'01 # first byte\n 02 /second/']"),
+            "830102420102"
+        );
+    }
+
+    #[test]
+    fn invalid_text() {
+        assert_eq!(encode("[1, 2").unwrap_err(), "end of text: expected ,");
+        assert_eq!(encode("[1 2]").unwrap_err(), "line 1: expected ,");
+        assert_eq!(
+            encode("256_0").unwrap_err(),
+            "line 1: 256 does not fit in _0"
+        );
+        assert_eq!(
+            encode("1.1_1").unwrap_err(),
+            "line 1: 1.1 does not fit in _1"
+        );
+        assert_eq!(
+            encode("(_ h'01', \"a\")").unwrap_err(),
+            "line 1: the chunks of a string must be definite strings of its type"
+        );
+        assert_eq!(encode("\n\nfoo").unwrap_err(), "line 3: unexpected 'f'");
+        assert_eq!(encode(" ").unwrap_err(), "no item");
+        assert!(encode(&format!("{}{}", "[".repeat(257), "]".repeat(257))).is_ok());
+        assert_eq!(
+            encode(&"[".repeat(258)).unwrap_err(),
+            "line 1: too deeply nested"
+        );
+        assert_eq!(
+            encode(&"<<".repeat(100_000)).unwrap_err(),
+            "line 1: too deeply nested"
+        );
+    }
+}
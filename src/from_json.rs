@@ -0,0 +1,552 @@
+//! Encoding of JSON texts, or JSON Lines, as CBOR items, with a choice of integer and float
+//! widths, map ordering and the strings that stand for byte strings.
+
+use std::collections::BTreeSet;
+
+use half::f16;
+
+use crate::cbor_parser::{
+    MAJOR_ARRAY, MAJOR_BYTE_STRING, MAJOR_MAP, MAJOR_NEGATIVE_INTEGER, MAJOR_SIMPLE_OR_FLOAT,
+    MAJOR_TAG, MAJOR_TEXT_STRING, MAJOR_UNSIGNED_INTEGER,
+};
+use crate::cddl::{decode_base64, unescape};
+use crate::edn;
+use crate::json::Json;
+use crate::value::{encode_float, encode_head};
+
+const TAG_POSITIVE_BIGNUM: u64 = 2;
+const TAG_NEGATIVE_BIGNUM: u64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatWidth {
+    Half,
+    Single,
+    Double,
+}
+
+/// How JSON values are encoded.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct JsonEncodeOptions {
+    /// Number of bytes (1, 2, 4 or 8) of the argument of every integer, instead of the
+    /// fewest that hold it.
+    pub integer_length: Option<usize>,
+    /// Width of every float, rounding the values that it cannot hold exactly, instead of the
+    /// narrowest that holds the value. Values too large for it are an error.
+    pub float_width: Option<FloatWidth>,
+    /// Sort the members of objects by the encoding of their keys (RFC 8949 Section 4.2.1).
+    pub deterministic: bool,
+    /// JSON pointers (RFC 6901) to the base64 or base64url strings that are encoded as byte
+    /// strings, where `*` matches any member name or array index.
+    pub byte_strings: Vec<String>,
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.position..]
+    }
+
+    fn error(&self, message: impl Into<String>) -> String {
+        if self.rest().trim().is_empty() {
+            return format!("end of text: {}", message.into());
+        }
+        let line = self.text[..self.position].matches('\n').count() + 1;
+        format!("line {}: {}", line, message.into())
+    }
+
+    fn skip_space(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_space();
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(self.error(format!("expected {}", token))),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > 256 {
+            return Err(self.error("too deeply nested"));
+        }
+        if self.eat("{") {
+            let mut members = vec![];
+            let mut names = BTreeSet::new();
+            if !self.eat("}") {
+                loop {
+                    self.expect("\"")?;
+                    let name = self.string()?;
+                    if !names.insert(name.clone()) {
+                        return Err(self.error(format!("duplicate member name {:?}", name)));
+                    }
+                    self.expect(":")?;
+                    members.push((name, self.value(depth + 1)?));
+                    if self.eat("}") {
+                        break;
+                    }
+                    self.expect(",")?;
+                }
+            }
+            return Ok(Json::Object(members));
+        }
+        if self.eat("[") {
+            let mut items = vec![];
+            if !self.eat("]") {
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    if self.eat("]") {
+                        break;
+                    }
+                    self.expect(",")?;
+                }
+            }
+            return Ok(Json::Array(items));
+        }
+        if self.eat("\"") {
+            return self.string().map(Json::String);
+        }
+        for (word, value) in [
+            ("null", Json::Null),
+            ("true", Json::Bool(true)),
+            ("false", Json::Bool(false)),
+        ] {
+            if self.eat(word) {
+                return Ok(value);
+            }
+        }
+        self.number()
+    }
+
+    /// The rest of a string after its opening quote.
+    fn string(&mut self) -> Result<String, String> {
+        let mut escaped = false;
+        let Some(length) = self.rest().find(|c| {
+            let end = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            end
+        }) else {
+            return Err(self.error("unterminated string"));
+        };
+        let content = &self.rest()[..length];
+        let mut chars = content.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' | 'u') | None => {}
+                    Some(c) => return Err(self.error(format!("invalid escape \\{}", c))),
+                },
+                '\0'..' ' => return Err(self.error(format!("unescaped control character {:?}", c))),
+                _ => {}
+            }
+        }
+        let text = unescape(content).map_err(|err| self.error(err))?;
+        self.position += length + 1;
+        Ok(text)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let rest = self.rest();
+        let length = rest
+            .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+            .unwrap_or(rest.len());
+        let number = rest[..length].to_string();
+        if !is_number(&number) {
+            return match self.rest().chars().next() {
+                Some(c) => Err(self.error(format!("unexpected {:?}", c))),
+                None => Err(self.error("expected a value")),
+            };
+        }
+        if is_float(&number) && !number.parse::<f64>().is_ok_and(f64::is_finite) {
+            return Err(self.error(format!("{} does not fit in a double", number)));
+        }
+        self.position += length;
+        Ok(Json::Number(number))
+    }
+}
+
+/// Whether `number` follows the grammar of JSON numbers (RFC 8259 Section 6).
+fn is_number(number: &str) -> bool {
+    let digits = |text: &str| {
+        text.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len())
+    };
+    let rest = number.strip_prefix('-').unwrap_or(number);
+    let integer = digits(rest);
+    if integer == 0 || integer > 1 && rest.starts_with('0') {
+        return false;
+    }
+    let mut rest = &rest[integer..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let length = digits(fraction);
+        if length == 0 {
+            return false;
+        }
+        rest = &fraction[length..];
+    }
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        let length = digits(exponent);
+        if length == 0 {
+            return false;
+        }
+        rest = &exponent[length..];
+    }
+    rest.is_empty()
+}
+
+/// Whether a JSON number has a fraction or an exponent, which makes it a float.
+fn is_float(number: &str) -> bool {
+    number.contains(['.', 'e', 'E'])
+}
+
+/// Multiplies a big-endian number by 10 and adds `digit`.
+fn push_digit(number: &mut Vec<u8>, digit: u8) {
+    let mut carry = u32::from(digit);
+    for byte in number.iter_mut().rev() {
+        let value = u32::from(*byte) * 10 + carry;
+        *byte = value as u8;
+        carry = value >> 8;
+    }
+    if carry > 0 {
+        number.insert(0, carry as u8);
+    }
+}
+
+/// Subtracts one from a big-endian number that is not zero.
+fn decrement(number: &mut Vec<u8>) {
+    for byte in number.iter_mut().rev() {
+        let (value, borrow) = byte.overflowing_sub(1);
+        *byte = value;
+        if !borrow {
+            break;
+        }
+    }
+    let zeros = number.iter().take_while(|byte| **byte == 0).count();
+    number.drain(..zeros);
+}
+
+/// Whether the path of a value is matched by the JSON pointer `pointer`.
+fn pointer_matches(pointer: &str, path: &[String]) -> bool {
+    if pointer.is_empty() {
+        return path.is_empty();
+    }
+    let Some(pointer) = pointer.strip_prefix('/') else {
+        return false;
+    };
+    let segments: Vec<_> = pointer
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    segments.len() == path.len()
+        && segments
+            .iter()
+            .zip(path)
+            .all(|(segment, name)| segment == "*" || segment == name)
+}
+
+struct Encoder<'o> {
+    options: &'o JsonEncodeOptions,
+    /// The additional information of integer heads, less 24, for a fixed length.
+    indicator: Option<u8>,
+}
+
+impl Encoder<'_> {
+    fn integer(&self, major_type: u8, argument: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        edn::head(major_type, argument, self.indicator, output).map_err(|_| {
+            format!(
+                "{} does not fit in {} bytes",
+                argument,
+                self.options.integer_length.unwrap_or_default()
+            )
+        })
+    }
+
+    fn float(&self, number: &str, output: &mut Vec<u8>) -> Result<(), String> {
+        // unwrap safety: the parser only keeps numbers that parse
+        let value: f64 = number.parse().unwrap();
+        let simple = MAJOR_SIMPLE_OR_FLOAT << 5;
+        match self.options.float_width {
+            None => encode_float(value, output),
+            Some(FloatWidth::Half) => {
+                let half = f16::from_f64(value);
+                if half.is_infinite() {
+                    return Err(format!("{} does not fit in a half", number));
+                }
+                output.push(simple | 25);
+                output.extend(half.to_be_bytes());
+            }
+            Some(FloatWidth::Single) => {
+                let single = value as f32;
+                if single.is_infinite() {
+                    return Err(format!("{} does not fit in a single", number));
+                }
+                output.push(simple | 26);
+                output.extend(single.to_be_bytes());
+            }
+            Some(FloatWidth::Double) => {
+                output.push(simple | 27);
+                output.extend(value.to_be_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    fn number(&self, number: &str, output: &mut Vec<u8>) -> Result<(), String> {
+        if is_float(number) {
+            return self.float(number, output);
+        }
+        let (negative, digits) = match number.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, number),
+        };
+        let mut magnitude = vec![];
+        for digit in digits.bytes() {
+            push_digit(&mut magnitude, digit - b'0');
+        }
+        // -n is encoded as n - 1, and -0 as 0.
+        let negative = negative && !magnitude.is_empty();
+        if negative {
+            decrement(&mut magnitude);
+        }
+        let major_type = match negative {
+            true => MAJOR_NEGATIVE_INTEGER,
+            false => MAJOR_UNSIGNED_INTEGER,
+        };
+        if magnitude.len() <= 8 {
+            let argument = magnitude
+                .iter()
+                .fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte));
+            return self.integer(major_type, argument, output);
+        }
+        // Integers beyond 64 bits are bignums (RFC 8949 Section 3.4.3).
+        let tag = match negative {
+            true => TAG_NEGATIVE_BIGNUM,
+            false => TAG_POSITIVE_BIGNUM,
+        };
+        encode_head(MAJOR_TAG, tag, output);
+        encode_head(MAJOR_BYTE_STRING, magnitude.len() as u64, output);
+        output.extend(magnitude);
+        Ok(())
+    }
+
+    fn value(
+        &self,
+        json: &Json,
+        path: &mut Vec<String>,
+        output: &mut Vec<u8>,
+    ) -> Result<(), String> {
+        match json {
+            Json::Null => output.push((MAJOR_SIMPLE_OR_FLOAT << 5) | 22),
+            Json::Bool(false) => output.push((MAJOR_SIMPLE_OR_FLOAT << 5) | 20),
+            Json::Bool(true) => output.push((MAJOR_SIMPLE_OR_FLOAT << 5) | 21),
+            Json::Number(number) => self.number(number, output)?,
+            Json::String(text) => {
+                let is_bytes = self
+                    .options
+                    .byte_strings
+                    .iter()
+                    .any(|pointer| pointer_matches(pointer, path));
+                if is_bytes {
+                    let bytes = decode_base64(text)
+                        .map_err(|err| format!("/{}: {}", path.join("/"), err))?;
+                    encode_head(MAJOR_BYTE_STRING, bytes.len() as u64, output);
+                    output.extend(bytes);
+                } else {
+                    encode_head(MAJOR_TEXT_STRING, text.len() as u64, output);
+                    output.extend(text.as_bytes());
+                }
+            }
+            Json::Array(items) => {
+                encode_head(MAJOR_ARRAY, items.len() as u64, output);
+                for (index, item) in items.iter().enumerate() {
+                    path.push(index.to_string());
+                    self.value(item, path, output)?;
+                    path.pop();
+                }
+            }
+            Json::Object(members) => {
+                let mut pairs = vec![];
+                for (name, value) in members {
+                    let mut key = vec![];
+                    encode_head(MAJOR_TEXT_STRING, name.len() as u64, &mut key);
+                    key.extend(name.as_bytes());
+                    let mut encoded = vec![];
+                    path.push(name.clone());
+                    self.value(value, path, &mut encoded)?;
+                    path.pop();
+                    pairs.push((key, encoded));
+                }
+                if self.options.deterministic {
+                    pairs.sort();
+                }
+                encode_head(MAJOR_MAP, pairs.len() as u64, output);
+                for (key, value) in pairs {
+                    output.extend(key);
+                    output.extend(value);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Encodes the JSON values of `text`, one after the other as in JSON Lines.
+pub fn encode(text: &str, options: &JsonEncodeOptions) -> Result<Vec<u8>, String> {
+    let mut parser = Parser { text, position: 0 };
+    let indicator = match options.integer_length {
+        None => None,
+        Some(1) => Some(0),
+        Some(2) => Some(1),
+        Some(4) => Some(2),
+        Some(8) => Some(3),
+        Some(length) => return Err(format!("integers cannot be {} bytes long", length)),
+    };
+    let encoder = Encoder { options, indicator };
+    let mut output = vec![];
+    parser.skip_space();
+    if parser.rest().is_empty() {
+        return Err("no value".to_string());
+    }
+    while !parser.rest().is_empty() {
+        let line = parser.text[..parser.position].matches('\n').count() + 1;
+        let json = parser.value(0)?;
+        encoder
+            .value(&json, &mut vec![], &mut output)
+            .map_err(|err| format!("line {}: {}", line, err))?;
+        let end = parser.position;
+        parser.skip_space();
+        // As in JSON Lines, each value ends its line.
+        if !parser.rest().is_empty() && !parser.text[end..parser.position].contains('\n') {
+            return Err(parser.error("expected a new line after a value"));
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(text: &str, options: &JsonEncodeOptions) -> String {
+        crate::format_hex(&encode(text, options).unwrap())
+    }
+
+    #[test]
+    fn preferred_encoding() {
+        let options = JsonEncodeOptions::default();
+        assert_eq!(
+            hex(r#"{"b": [1, -1, 500, 1.5, 0.1], "a": null}"#, &options),
+            "a261628501201901f4f93e00fb3fb999999999999a6161f6"
+        );
+        assert_eq!(
+            hex("18446744073709551616\n-18446744073709551617\n-0", &options),
+            "c249010000000000000000c34901000000000000000000"
+        );
+        assert_eq!(hex("\"\\u00e9\"\n[true, false]", &options), "62c3a982f5f4");
+    }
+
+    #[test]
+    fn encoding_choices() {
+        let options = JsonEncodeOptions {
+            integer_length: Some(2),
+            float_width: Some(FloatWidth::Single),
+            deterministic: true,
+            byte_strings: vec!["/k/*".to_string(), "/~1".to_string()],
+        };
+        assert_eq!(
+            hex(
+                r#"{"z": 1, "k": ["AQI", "AQI="], "/": "-w", "a": 1.0}"#,
+                &options
+            ),
+            "a4612f41fb6161fa3f800000616b82420102420102617a190001"
+        );
+        let options = JsonEncodeOptions {
+            integer_length: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            encode("[1, 256]", &options).unwrap_err(),
+            "line 1: 256 does not fit in 1 bytes"
+        );
+        let options = JsonEncodeOptions {
+            float_width: Some(FloatWidth::Half),
+            ..Default::default()
+        };
+        assert_eq!(hex("[65504.0, 1e-10]", &options), "82f97bfff90000");
+        assert_eq!(
+            encode("100000.0", &options).unwrap_err(),
+            "line 1: 100000.0 does not fit in a half"
+        );
+        let options = JsonEncodeOptions {
+            float_width: Some(FloatWidth::Single),
+            ..Default::default()
+        };
+        assert_eq!(
+            encode("1e300", &options).unwrap_err(),
+            "line 1: 1e300 does not fit in a single"
+        );
+    }
+
+    #[test]
+    fn invalid_json() {
+        let options = JsonEncodeOptions::default();
+        assert_eq!(
+            encode("[1, 2", &options).unwrap_err(),
+            "end of text: expected ,"
+        );
+        assert_eq!(
+            encode("{1: 2}", &options).unwrap_err(),
+            "line 1: expected \""
+        );
+        assert_eq!(
+            encode("\n[01]", &options).unwrap_err(),
+            "line 2: unexpected '0'"
+        );
+        assert_eq!(
+            encode("1.", &options).unwrap_err(),
+            "line 1: unexpected '1'"
+        );
+        assert_eq!(
+            encode("1e400", &options).unwrap_err(),
+            "line 1: 1e400 does not fit in a double"
+        );
+        assert_eq!(
+            encode("\"a\tb\"", &options).unwrap_err(),
+            "line 1: unescaped control character '\\t'"
+        );
+        assert_eq!(
+            encode(r#""\a""#, &options).unwrap_err(),
+            "line 1: invalid escape \\a"
+        );
+        assert_eq!(
+            encode("1 2", &options).unwrap_err(),
+            "line 1: expected a new line after a value"
+        );
+        assert_eq!(
+            encode(r#"{"a": 1, "a": 2}"#, &options).unwrap_err(),
+            "line 1: duplicate member name \"a\""
+        );
+        let options = JsonEncodeOptions {
+            byte_strings: vec!["/a".to_string()],
+            ..Default::default()
+        };
+        assert!(encode(r#"{"a": "!"}"#, &options)
+            .unwrap_err()
+            .starts_with("line 1: /a: "));
+    }
+}
@@ -0,0 +1,130 @@
+//! Health certificates (the `hcert` claim of CWTs), such as the EU Digital COVID Certificate,
+//! and the `HC1:` text form they are shared in as QR codes.
+
+use crate::cbor_object::CborObject;
+use crate::context::Context;
+use crate::cose;
+use crate::cwt;
+use crate::schema::{Entry, Schema};
+
+const HC1_PREFIX: &str = "HC1:";
+
+/// First byte of zlib data compressed with the deflate method and a 32K window.
+const ZLIB_HEADER: u8 = 0x78;
+
+const DISEASE: Entry = Entry::text("tg", "disease or agent targeted", Schema::Any);
+const COUNTRY: Entry = Entry::text("co", "member state or third country", Schema::Any);
+const ISSUER: Entry = Entry::text("is", "certificate issuer", Schema::Any);
+const CERTIFICATE_ID: Entry = Entry::text("ci", "unique certificate identifier", Schema::Any);
+
+const NAME: Schema = Schema::Map(&[
+    Entry::text("fn", "surname", Schema::Any),
+    Entry::text("fnt", "standardised surname", Schema::Any),
+    Entry::text("gn", "forename", Schema::Any),
+    Entry::text("gnt", "standardised forename", Schema::Any),
+]);
+
+const VACCINATION: Schema = Schema::Map(&[
+    DISEASE,
+    Entry::text("vp", "vaccine or prophylaxis", Schema::Any),
+    Entry::text("mp", "vaccine medicinal product", Schema::Any),
+    Entry::text(
+        "ma",
+        "marketing authorisation holder or manufacturer",
+        Schema::Any,
+    ),
+    Entry::text("dn", "dose number", Schema::Any),
+    Entry::text("sd", "total series of doses", Schema::Any),
+    Entry::text("dt", "date of vaccination", Schema::Any),
+    COUNTRY,
+    ISSUER,
+    CERTIFICATE_ID,
+]);
+
+const TEST: Schema = Schema::Map(&[
+    DISEASE,
+    Entry::text("tt", "type of test", Schema::Any),
+    Entry::text("nm", "test name", Schema::Any),
+    Entry::text("ma", "test device identifier", Schema::Any),
+    Entry::text("sc", "date and time of sample collection", Schema::Any),
+    Entry::text("tr", "test result", Schema::Any),
+    Entry::text("tc", "testing centre or facility", Schema::Any),
+    COUNTRY,
+    ISSUER,
+    CERTIFICATE_ID,
+]);
+
+const RECOVERY: Schema = Schema::Map(&[
+    DISEASE,
+    Entry::text("fr", "date of first positive test result", Schema::Any),
+    COUNTRY,
+    ISSUER,
+    Entry::text("df", "certificate valid from", Schema::Any),
+    Entry::text("du", "certificate valid until", Schema::Any),
+    CERTIFICATE_ID,
+]);
+
+/// EU Digital COVID Certificate (eHealth Network, DCC JSON schema 1.3).
+const DIGITAL_COVID_CERTIFICATE: Schema = Schema::Map(&[
+    Entry::text("ver", "schema version", Schema::Any),
+    Entry::text("nam", "name", NAME),
+    Entry::text("dob", "date of birth", Schema::Any),
+    Entry::text("v", "vaccination", Schema::ArrayOf(&VACCINATION)),
+    Entry::text("t", "test", Schema::ArrayOf(&TEST)),
+    Entry::text("r", "recovery", Schema::ArrayOf(&RECOVERY)),
+]);
+
+/// Value of the `hcert` claim: health certificates by their type.
+pub const HCERT: Schema = Schema::Map(&[Entry::int(1, "eu_dgc_v1", DIGITAL_COVID_CERTIFICATE)]);
+
+/// Takes an untagged message as a COSE_Sign1 message, which health certificates usually are.
+fn select_certificate(object: &CborObject, context: &Context) -> (Schema, Option<String>) {
+    match object {
+        CborObject::Array(_) => (cose::CWT_SIGN1, None),
+        _ => cwt::select_token(object, context),
+    }
+}
+
+/// A health certificate: a CWT in a COSE_Sign1 message, tagged or not.
+pub const HEALTH_CERTIFICATE: Schema = Schema::Select(select_certificate);
+
+/// Reads the COSE message of a health certificate from its `HC1:` text form: Base45 over
+/// zlib-compressed CBOR.
+pub fn decode_hc1(text: &str) -> Result<Vec<u8>, String> {
+    let Some(payload) = text.trim().strip_prefix(HC1_PREFIX) else {
+        return Err(format!("expected the {} prefix", HC1_PREFIX));
+    };
+    let compressed = base45::decode(payload).map_err(|err| err.to_string())?;
+    // Compression is optional, and a COSE message, which is an array or a tag, never starts
+    // with this byte.
+    if compressed.first() != Some(&ZLIB_HEADER) {
+        return Ok(compressed);
+    }
+    miniz_oxide::inflate::decompress_to_vec_zlib(&compressed)
+        .map_err(|err| format!("invalid zlib data: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hc1() {
+        // The zlib compression of 0x84 0x40 0xa0 0xf6 0x40.
+        let compressed = [
+            0x78, 0xda, 0x6b, 0x71, 0x58, 0xf0, 0xcd, 0x01, 0x00, 0x07, 0xa5, 0x02, 0x9b,
+        ];
+        let text = format!("HC1:{}\n", base45::encode(compressed));
+        assert_eq!(decode_hc1(&text), Ok(vec![0x84, 0x40, 0xa0, 0xf6, 0x40]));
+        let text = format!("HC1:{}", base45::encode([0x84, 0x40, 0xa0, 0xf6, 0x40]));
+        assert_eq!(decode_hc1(&text), Ok(vec![0x84, 0x40, 0xa0, 0xf6, 0x40]));
+        assert_eq!(
+            decode_hc1("6BFOXN"),
+            Err("expected the HC1: prefix".to_string())
+        );
+        assert_eq!(
+            decode_hc1("HC1:a!"),
+            Err("Invalid base45 string".to_string())
+        );
+    }
+}
@@ -0,0 +1,497 @@
+//! Conversion of items to JSON, following RFC 8949 Section 6.1, or into envelopes that keep
+//! their type and encoding.
+
+use crate::cbor_object::CborObject;
+use crate::type_byte_string::ExpectedConversion;
+use crate::type_simple_or_float::{
+    SIMPLE_VALUE_FALSE, SIMPLE_VALUE_NULL, SIMPLE_VALUE_TRUE, SIMPLE_VALUE_UNDEFINED,
+};
+
+const TAG_POSITIVE_BIGNUM: u64 = 2;
+const TAG_NEGATIVE_BIGNUM: u64 = 3;
+
+/// How items are converted to JSON.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// Keep tags as `{"tag": n, "value": ...}` objects instead of dropping them.
+    pub keep_tags: bool,
+    /// Write bignums as strings of decimal digits instead of numbers.
+    pub bignum_strings: bool,
+    /// Write each item as an envelope that keeps its type and encoding details.
+    pub envelope: bool,
+}
+
+/// A JSON value, with its members in order.
+pub enum Json {
+    Null,
+    Bool(bool),
+    /// The JSON text of a number.
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+fn quote(text: &str) -> String {
+    // unwrap safety: a string always serializes
+    serde_json::to_string(text).unwrap()
+}
+
+impl Json {
+    fn object<const N: usize>(members: [(&str, Json); N]) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        )
+    }
+
+    fn write(&self, indent: usize, output: &mut String) {
+        let inner = " ".repeat(indent + 2);
+        match self {
+            Json::Null => output.push_str("null"),
+            Json::Bool(value) => output.push_str(if *value { "true" } else { "false" }),
+            Json::Number(text) => output.push_str(text),
+            Json::String(text) => output.push_str(&quote(text)),
+            Json::Array(items) if items.is_empty() => output.push_str("[]"),
+            Json::Array(items) => {
+                output.push_str("[\n");
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        output.push_str(",\n");
+                    }
+                    output.push_str(&inner);
+                    item.write(indent + 2, output);
+                }
+                output.push('\n');
+                output.push_str(&" ".repeat(indent));
+                output.push(']');
+            }
+            Json::Object(members) if members.is_empty() => output.push_str("{}"),
+            Json::Object(members) => {
+                output.push_str("{\n");
+                for (index, (name, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        output.push_str(",\n");
+                    }
+                    output.push_str(&inner);
+                    output.push_str(&quote(name));
+                    output.push_str(": ");
+                    value.write(indent + 2, output);
+                }
+                output.push('\n');
+                output.push_str(&" ".repeat(indent));
+                output.push('}');
+            }
+        }
+    }
+
+    /// The text of a map key: strings as they are, and other keys as their JSON text on
+    /// one line, such as `1`, `true` or `[1,2]`.
+    fn into_key(self) -> String {
+        match self {
+            Json::String(text) => text,
+            Json::Array(items) => {
+                let items: Vec<_> = items.into_iter().map(Json::into_compact).collect();
+                format!("[{}]", items.join(","))
+            }
+            Json::Object(members) => {
+                let members: Vec<_> = members
+                    .into_iter()
+                    .map(|(name, value)| format!("{}:{}", quote(&name), value.into_compact()))
+                    .collect();
+                format!("{{{}}}", members.join(","))
+            }
+            json => json.into_compact(),
+        }
+    }
+
+    fn into_compact(self) -> String {
+        match self {
+            Json::String(text) => quote(&text),
+            Json::Array(_) | Json::Object(_) => self.into_key(),
+            json => {
+                let mut output = String::new();
+                json.write(0, &mut output);
+                output
+            }
+        }
+    }
+}
+
+/// The decimal digits of an unsigned big-endian number.
+fn decimal(bytes: &[u8]) -> String {
+    let mut number = bytes.to_vec();
+    let mut digits = vec![];
+    while number.iter().any(|byte| *byte != 0) {
+        let mut remainder = 0u32;
+        for byte in number.iter_mut() {
+            let value = (remainder << 8) | u32::from(*byte);
+            *byte = (value / 10) as u8;
+            remainder = value % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    digits.reverse();
+    // unwrap safety: the digits are ASCII
+    String::from_utf8(digits).unwrap()
+}
+
+/// Adds one to an unsigned big-endian number.
+fn increment(bytes: &[u8]) -> Vec<u8> {
+    let mut number = bytes.to_vec();
+    for byte in number.iter_mut().rev() {
+        let (value, carry) = byte.overflowing_add(1);
+        *byte = value;
+        if !carry {
+            return number;
+        }
+    }
+    number.insert(0, 1);
+    number
+}
+
+fn float(value: f64, text: String) -> Json {
+    match value.is_finite() {
+        true => Json::Number(text),
+        false => Json::Null,
+    }
+}
+
+fn convert(object: &CborObject, options: &JsonOptions, conversion: ExpectedConversion) -> Json {
+    match object {
+        CborObject::UnsignedInteger(_) | CborObject::NegativeInteger(_) => {
+            match object.as_integer() {
+                Some(value) => Json::Number(value.to_string()),
+                None => Json::Null,
+            }
+        }
+        CborObject::ByteString(_)
+        | CborObject::ByteStringWithEmbedded(_)
+        | CborObject::IndefiniteByteString(_) => match object.as_bytes() {
+            Some(bytes) => Json::String(conversion.encode(&bytes)),
+            None => Json::Null,
+        },
+        CborObject::TextString(_) | CborObject::IndefiniteTextString(_) => {
+            object.as_text().map_or(Json::Null, Json::String)
+        }
+        CborObject::Array(x) => Json::Array(
+            x.value()
+                .iter()
+                .filter(|item| !item.is_break())
+                .map(|item| convert(item, options, conversion))
+                .collect(),
+        ),
+        CborObject::Map(_) => Json::Object(
+            object
+                .as_map()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        convert(key, options, conversion).into_key(),
+                        convert(value, options, conversion),
+                    )
+                })
+                .collect(),
+        ),
+        CborObject::Tag(x) => {
+            let Some(tag) = x.tag() else {
+                return convert(x.payload(), options, conversion);
+            };
+            if let (TAG_POSITIVE_BIGNUM | TAG_NEGATIVE_BIGNUM, Some(bytes)) =
+                (tag, x.payload().as_bytes())
+            {
+                let digits = match tag {
+                    TAG_POSITIVE_BIGNUM => decimal(&bytes),
+                    _ => format!("-{}", decimal(&increment(&bytes))),
+                };
+                return match options.bignum_strings {
+                    true => Json::String(digits),
+                    false => Json::Number(digits),
+                };
+            }
+            let conversion = ExpectedConversion::from_tag(tag).unwrap_or(conversion);
+            let value = convert(x.payload(), options, conversion);
+            match options.keep_tags {
+                true => Json::object([("tag", Json::Number(tag.to_string())), ("value", value)]),
+                false => value,
+            }
+        }
+        CborObject::SimpleValue(x) => match x.value() {
+            SIMPLE_VALUE_FALSE => Json::Bool(false),
+            SIMPLE_VALUE_TRUE => Json::Bool(true),
+            _ => Json::Null,
+        },
+        CborObject::HalfPrecisionFloat(x) => {
+            float(x.value().into(), format!("{:?}", f32::from(x.value())))
+        }
+        CborObject::SinglePrecisionFloat(x) => float(x.value().into(), format!("{:?}", x.value())),
+        CborObject::DoublePrecisionFloat(x) => float(x.value(), format!("{:?}", x.value())),
+        CborObject::ReservedSimpleOrFloat(_) | CborObject::Break(_) => Json::Null,
+    }
+}
+
+fn typed(kind: &str, mut members: Vec<(String, Json)>, indicator: Option<&str>) -> Json {
+    members.insert(0, ("type".to_string(), Json::String(kind.to_string())));
+    if let Some(indicator) = indicator {
+        members.push(("encoding".to_string(), Json::String(indicator.to_string())));
+    }
+    Json::Object(members)
+}
+
+fn member(name: &str, value: Json) -> (String, Json) {
+    (name.to_string(), value)
+}
+
+fn items(items: &[CborObject]) -> Vec<Json> {
+    items
+        .iter()
+        .filter(|item| !item.is_break())
+        .map(envelope)
+        .collect()
+}
+
+fn envelope_float(precision: &str, value: f64, text: String) -> Json {
+    let value = if value.is_nan() {
+        Json::String("NaN".to_string())
+    } else if value.is_infinite() {
+        Json::String(if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string())
+    } else {
+        Json::Number(text)
+    };
+    typed(
+        "float",
+        vec![
+            member("precision", Json::String(precision.to_string())),
+            member("value", value),
+        ],
+        None,
+    )
+}
+
+/// Wraps an item in an object that tells its type, with `"encoding"` for a head that is
+/// longer than needed and `"indefinite"` or `"chunks"` for indefinite lengths.
+fn envelope(object: &CborObject) -> Json {
+    let base64url = |bytes: &[u8]| Json::String(ExpectedConversion::Base64Url.encode(bytes));
+    let integer = || {
+        object
+            .as_integer()
+            .map_or(Json::Null, |value| Json::Number(value.to_string()))
+    };
+    let indefinite = |items: &[CborObject]| {
+        items
+            .last()
+            .is_some_and(CborObject::is_break)
+            .then(|| member("indefinite", Json::Bool(true)))
+    };
+    match object {
+        CborObject::UnsignedInteger(x) => typed(
+            "unsigned",
+            vec![member("value", integer())],
+            x.encoding_indicator(),
+        ),
+        CborObject::NegativeInteger(x) => typed(
+            "negative",
+            vec![member("value", integer())],
+            x.encoding_indicator(),
+        ),
+        CborObject::ByteString(x) => typed(
+            "bytes",
+            vec![member("value", base64url(x.value()))],
+            x.encoding_indicator(),
+        ),
+        CborObject::ByteStringWithEmbedded(x) => typed(
+            "bytes",
+            vec![
+                member("value", base64url(x.raw_value())),
+                member("embedded", envelope(x.value())),
+            ],
+            x.encoding_indicator(),
+        ),
+        CborObject::IndefiniteByteString(x) => typed(
+            "bytes",
+            vec![member("chunks", Json::Array(items(x.chunks())))],
+            None,
+        ),
+        CborObject::TextString(x) => {
+            let mut members = vec![member(
+                "value",
+                object.as_text().map_or(Json::Null, Json::String),
+            )];
+            // Text that is not valid UTF-8 also keeps its bytes.
+            if std::str::from_utf8(x.value()).is_err() {
+                members.push(member("bytes", base64url(x.value())));
+            }
+            typed("text", members, x.encoding_indicator())
+        }
+        CborObject::IndefiniteTextString(x) => typed(
+            "text",
+            vec![member("chunks", Json::Array(items(x.chunks())))],
+            None,
+        ),
+        CborObject::Array(x) => {
+            let mut members = vec![member("value", Json::Array(items(x.value())))];
+            members.extend(indefinite(x.value()));
+            typed("array", members, x.encoding_indicator())
+        }
+        CborObject::Map(x) => {
+            let items: Vec<_> = x.value().iter().filter(|item| !item.is_break()).collect();
+            let pairs = items
+                .chunks(2)
+                .map(|pair| Json::Array(pair.iter().map(|item| envelope(item)).collect()))
+                .collect();
+            let mut members = vec![member("value", Json::Array(pairs))];
+            members.extend(indefinite(x.value()));
+            typed("map", members, x.encoding_indicator())
+        }
+        CborObject::Tag(x) => typed(
+            "tag",
+            vec![
+                member(
+                    "tag",
+                    x.tag()
+                        .map_or(Json::Null, |tag| Json::Number(tag.to_string())),
+                ),
+                member("value", envelope(x.payload())),
+            ],
+            x.encoding_indicator(),
+        ),
+        CborObject::SimpleValue(x) => match x.value() {
+            SIMPLE_VALUE_FALSE => typed("bool", vec![member("value", Json::Bool(false))], None),
+            SIMPLE_VALUE_TRUE => typed("bool", vec![member("value", Json::Bool(true))], None),
+            SIMPLE_VALUE_NULL => typed("null", vec![], None),
+            SIMPLE_VALUE_UNDEFINED => typed("undefined", vec![], None),
+            value => typed(
+                "simple",
+                vec![member("value", Json::Number(value.to_string()))],
+                None,
+            ),
+        },
+        CborObject::HalfPrecisionFloat(x) => envelope_float(
+            "half",
+            x.value().into(),
+            format!("{:?}", f32::from(x.value())),
+        ),
+        CborObject::SinglePrecisionFloat(x) => {
+            envelope_float("single", x.value().into(), format!("{:?}", x.value()))
+        }
+        CborObject::DoublePrecisionFloat(x) => {
+            envelope_float("double", x.value(), format!("{:?}", x.value()))
+        }
+        CborObject::ReservedSimpleOrFloat(x) => typed(
+            "reserved",
+            vec![member(
+                "value",
+                Json::Number(x.additional_info_argument().to_string()),
+            )],
+            None,
+        ),
+        CborObject::Break(_) => typed("break", vec![], None),
+    }
+}
+
+/// Converts an item to pretty-printed JSON.
+pub fn to_json(object: &CborObject, options: &JsonOptions) -> String {
+    let json = match options.envelope {
+        true => envelope(object),
+        false => convert(object, options, ExpectedConversion::Base64Url),
+    };
+    let mut output = String::new();
+    json.write(0, &mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::parse_item;
+
+    fn json(hex: &str, options: JsonOptions) -> String {
+        let object = parse_item(hex);
+        match options.envelope {
+            true => envelope(&object),
+            false => convert(&object, &options, ExpectedConversion::Base64Url),
+        }
+        .into_compact()
+    }
+
+    #[test]
+    fn conversion() {
+        let options = JsonOptions::default();
+        // [1, -1, h'fb', "a", 1.5, NaN, null, undefined]
+        assert_eq!(
+            json("88 01 20 41fb 6161 f93e00 f97e00 f6 f7", options),
+            r#"[1,-1,"-w","a",1.5,null,null,null]"#
+        );
+        // {1: 2, h'01': 22(h'fb'), [1]: 23(h'fb')}
+        assert_eq!(
+            json("a3 01 02 4101 d6 41fb 8101 d7 41fb", options),
+            r#"{"1":2,"AQ":"+w==","[1]":"fb"}"#
+        );
+        // [2(h'010000000000000000'), 3(h'ffffffffffffffffff'), 1(0)]
+        assert_eq!(
+            json(
+                "83 c2 49010000000000000000 c3 49ffffffffffffffffff c1 00",
+                options
+            ),
+            r#"[18446744073709551616,-4722366482869645213696,0]"#
+        );
+        let options = JsonOptions {
+            keep_tags: true,
+            bignum_strings: true,
+            envelope: false,
+        };
+        assert_eq!(
+            json("82 c2 4101 c1 00", options),
+            r#"["1",{"tag":1,"value":0}]"#
+        );
+    }
+
+    #[test]
+    fn envelopes() {
+        let options = JsonOptions {
+            envelope: true,
+            ..Default::default()
+        };
+        // [_ 1 with a one-byte argument, 1.5 in single precision]
+        assert_eq!(
+            json("9f 1801 fa3fc00000 ff", options),
+            concat!(
+                r#"{"type":"array","value":[{"type":"unsigned","value":1,"encoding":"_0"},"#,
+                r#"{"type":"float","precision":"single","value":1.5}],"indefinite":true}"#
+            )
+        );
+        // {"a": 24(<<true>>)}
+        assert_eq!(
+            json("a1 6161 d818 41f5", options),
+            concat!(
+                r#"{"type":"map","value":[[{"type":"text","value":"a"},{"type":"tag","tag":24,"#,
+                r#""value":{"type":"bytes","value":"9Q","embedded":{"type":"bool","value":true}}}]]}"#
+            )
+        );
+        // (_ "a", "\xff")
+        assert_eq!(
+            json("7f 6161 61ff ff", options),
+            concat!(
+                r#"{"type":"text","chunks":[{"type":"text","value":"a"},"#,
+                r#"{"type":"text","value":""#,
+                "\u{fffd}",
+                r#"","bytes":"_w"}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn pretty_printing() {
+        let object = parse_item("a2 6161 80 6162 8201 a0");
+        assert_eq!(
+            to_json(&object, &JsonOptions::default()),
+            "{\n  \"a\": [],\n  \"b\": [\n    1,\n    {}\n  ]\n}"
+        );
+    }
+}
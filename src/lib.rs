@@ -1,6 +1,35 @@
 mod cbor_object;
 mod cbor_parser;
+mod cddl;
+mod cddl_validate;
+mod context;
+mod corim;
+mod cose;
+mod cose_decrypt;
+mod cose_key;
+mod cose_verify;
+mod coswid;
+mod ctap2;
+mod cwt;
+mod dag_cbor;
+mod diagnostic;
+mod dictionary;
+mod eat;
+mod edn;
+mod from_json;
+mod hcert;
 mod hex_parser;
+mod json;
+mod mdoc;
+mod packed;
+mod schema;
+mod senml;
+mod suit;
+mod tag_encoded_text;
+mod tag_identifier;
+mod tag_magic;
+mod tag_reference;
+mod time;
 mod tree;
 mod type_array;
 mod type_byte_string;
@@ -11,16 +40,31 @@ mod type_simple_or_float;
 mod type_tag;
 mod type_text_string;
 mod type_unsigned;
+mod value;
+mod webauthn;
 
+#[cfg(test)]
+mod test_helpers;
 #[cfg(test)]
 mod test_parse_cbor_and_build_tree;
 
 use std::fmt::Write;
+use std::rc::Rc;
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 
 use crate::cbor_object::ToTree;
 use crate::cbor_parser::parse_cbor;
+use crate::cddl_validate::Names;
+use crate::context::Context;
+use crate::schema::Schema;
+use crate::value::Value;
+
+pub use crate::cddl::Cddl;
+pub use crate::diagnostic::DiagnosticOptions;
+pub use crate::dictionary::Dictionary;
+pub use crate::from_json::{FloatWidth, JsonEncodeOptions};
+pub use crate::json::JsonOptions;
 
 pub fn parse_hex(hex_content: &str) -> Result<Vec<u8>> {
     let Ok((_, bytes_content)) = hex_parser::parse_hex(hex_content) else {
@@ -29,25 +73,633 @@ pub fn parse_hex(hex_content: &str) -> Result<Vec<u8>> {
     Ok(bytes_content)
 }
 
-pub fn dump_cbor_tree(bytes_content: &[u8]) -> Result<String> {
-    let Ok((rest, object)) = parse_cbor(bytes_content) else {
+/// Encodes the items written in extended diagnostic notation in `text`, keeping the
+/// encoding indicators and indefinite lengths it gives.
+pub fn encode_diagnostic(text: &str) -> Result<Vec<u8>> {
+    edn::encode(text).map_err(|err| anyhow!("Error reading diagnostic notation: {}", err))
+}
+
+/// Encodes the JSON values in `text`, one after the other as in JSON Lines, as a sequence
+/// of CBOR items.
+pub fn encode_json(text: &str, options: &JsonEncodeOptions) -> Result<Vec<u8>> {
+    from_json::encode(text, options).map_err(|err| anyhow!("Error reading JSON: {}", err))
+}
+
+/// Replaces the packed CBOR references in the item by the items they refer to and returns
+/// the plain CBOR encoding of the result.
+pub fn unpack_cbor(bytes_content: &[u8]) -> Result<Vec<u8>> {
+    let Ok((_, object)) = parse_cbor(bytes_content) else {
+        bail!("Error parsing CBOR data");
+    };
+    let unpacked = Value::from_object(&object)
+        .and_then(|value| packed::unpack(&value, &[]))
+        .map_err(|err| anyhow!("Error unpacking CBOR data: {}", err))?;
+    Ok(unpacked.encode())
+}
+
+/// Structure of the input when it is not marked by a tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    CoseSign1,
+    CoseSign,
+    CoseMac0,
+    CoseMac,
+    CoseEncrypt0,
+    CoseEncrypt,
+    CoseKey,
+    CoseKeySet,
+    Cwt,
+    WebauthnAttestation,
+    Mdoc,
+    Hcert,
+    Senml,
+    Suit,
+    Corim,
+    Coswid,
+    DagCbor,
+}
+
+impl Profile {
+    fn schema(&self) -> Schema {
+        match self {
+            Profile::CoseSign1 => cose::COSE_SIGN1,
+            Profile::CoseSign => cose::COSE_SIGN,
+            Profile::CoseMac0 => cose::COSE_MAC0,
+            Profile::CoseMac => cose::COSE_MAC,
+            Profile::CoseEncrypt0 => cose::COSE_ENCRYPT0,
+            Profile::CoseEncrypt => cose::COSE_ENCRYPT,
+            Profile::CoseKey => cose_key::COSE_KEY,
+            Profile::CoseKeySet => cose_key::COSE_KEY_SET,
+            Profile::Cwt => cwt::CWT,
+            Profile::WebauthnAttestation => webauthn::ATTESTATION_OBJECT,
+            Profile::Mdoc => mdoc::MDOC,
+            Profile::Hcert => hcert::HEALTH_CERTIFICATE,
+            Profile::Senml => senml::PACK,
+            Profile::Suit => suit::SUIT,
+            Profile::Corim => corim::CORIM,
+            Profile::Coswid => coswid::COSWID,
+            Profile::DagCbor => Schema::Any,
+        }
+    }
+
+    /// The tag of the COSE messages of the profile.
+    fn cose_tag(&self) -> Option<u64> {
+        match self {
+            Profile::CoseSign1 => Some(cose::TAG_COSE_SIGN1),
+            Profile::CoseSign => Some(cose::TAG_COSE_SIGN),
+            Profile::CoseMac0 => Some(cose::TAG_COSE_MAC0),
+            Profile::CoseMac => Some(cose::TAG_COSE_MAC),
+            Profile::CoseEncrypt0 => Some(cose::TAG_COSE_ENCRYPT0),
+            Profile::CoseEncrypt => Some(cose::TAG_COSE_ENCRYPT),
+            Profile::Hcert => Some(cose::TAG_COSE_SIGN1),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DumpOptions {
+    /// Decode the input as a CBOR sequence (RFC 8742) rather than a single item.
+    pub sequence: bool,
+    /// Structure of the untagged items of the input.
+    pub profile: Option<Profile>,
+    /// Time in seconds since the epoch at which the validity of CWTs is checked.
+    pub now: Option<i64>,
+    /// Names of private tags and map keys.
+    pub dictionary: Option<Rc<Dictionary>>,
+    /// CDDL rule after which the members and elements of the items are named.
+    pub cddl: Option<CddlRule>,
+    /// How the items are written.
+    pub format: Format,
+}
+
+/// How [`dump_cbor_tree`] writes the items of the input.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The bytes of each item next to what they encode.
+    #[default]
+    Tree,
+    /// Extended diagnostic notation (RFC 8610 Appendix G).
+    Diagnostic(DiagnosticOptions),
+    /// JSON (RFC 8949 Section 6.1).
+    Json(JsonOptions),
+}
+
+/// A rule of a CDDL file that the items of the input are expected to match.
+#[derive(Debug, Clone)]
+pub struct CddlRule {
+    cddl: Rc<Cddl>,
+    rule: String,
+}
+
+impl CddlRule {
+    /// Checks that `rule` is a type defined in `cddl`.
+    pub fn new(cddl: Cddl, rule: &str) -> Result<Self> {
+        match cddl.rule(rule) {
+            Some(cddl::Definition::Type(_)) => Ok(CddlRule {
+                cddl: Rc::new(cddl),
+                rule: rule.to_string(),
+            }),
+            Some(cddl::Definition::Group(_)) => {
+                bail!("Error reading CDDL: rule {} is a group, not a type", rule)
+            }
+            None => bail!("Error reading CDDL: rule {} is not defined", rule),
+        }
+    }
+
+    fn names(&self, bytes: &[u8], object: &cbor_object::CborObject) -> Option<Rc<Names>> {
+        cddl_validate::Validator::new(&self.cddl, bytes)
+            .validate(&self.rule, object, 0)
+            .ok()
+            .map(|report| Rc::new(report.names))
+    }
+}
+
+impl DumpOptions {
+    /// Where what is reported about the items goes: after them in the tree, and apart from
+    /// them in the other formats, which would no longer be diagnostic notation or JSON.
+    fn report<'a>(&self, output: &'a mut DumpOutput) -> &'a mut String {
+        match self.format {
+            Format::Tree => &mut output.output,
+            _ => &mut output.report,
+        }
+    }
+
+    /// Writes the item encoded as `bytes` at `offset` in the input, followed by what the
+    /// profile tells about it as a whole.
+    fn write_item(
+        &self,
+        bytes: &[u8],
+        offset: usize,
+        object: cbor_object::CborObject,
+        output: &mut DumpOutput,
+    ) {
+        let names = self
+            .cddl
+            .as_ref()
+            .and_then(|cddl| cddl.names(bytes, &object));
+        self.write_named_item(bytes, offset, object, names, output);
+    }
+
+    /// Writes an item like [`Self::write_item`], with the names that a CDDL rule gives to
+    /// its members and elements.
+    fn write_named_item(
+        &self,
+        bytes: &[u8],
+        offset: usize,
+        object: cbor_object::CborObject,
+        cddl_names: Option<Rc<Names>>,
+        output: &mut DumpOutput,
+    ) {
+        let value = Value::from_object(&object);
+        let issues = match self.profile {
+            Some(Profile::DagCbor) => dag_cbor::strictness_issues(bytes),
+            _ => vec![],
+        };
+        match &self.format {
+            Format::Tree => {
+                let issues = issues
+                    .iter()
+                    .map(|(offset, issue)| (*offset, format!("not DAG-CBOR: {}", issue)))
+                    .collect();
+                self.item_into_tree(object, cddl_names)
+                    .with_annotations_at(issues)
+                    .write(&mut output.output);
+            }
+            Format::Diagnostic(diagnostic_options) => {
+                output
+                    .output
+                    .push_str(&diagnostic::pretty_diagnostic(&object, diagnostic_options));
+                output.output.push('\n');
+            }
+            Format::Json(json_options) => {
+                output
+                    .output
+                    .push_str(&json::to_json(&object, json_options));
+                output.output.push('\n');
+            }
+        }
+        let report = self.report(output);
+        if self.format != Format::Tree {
+            // Without the tree, the offsets tell which item an issue concerns.
+            for (issue_offset, issue) in issues {
+                writeln!(
+                    report,
+                    "not DAG-CBOR: {} at offset {}",
+                    issue,
+                    offset + issue_offset
+                )
+                .unwrap();
+            }
+        }
+        if self.profile == Some(Profile::Senml) {
+            report.push_str("-- resolved SenML records --\n");
+            match value {
+                Ok(pack) => report.push_str(&senml::resolved_table(&pack, self.now)),
+                Err(err) => writeln!(report, "invalid senml pack: {}", err).unwrap(),
+            }
+        }
+    }
+
+    fn item_into_tree(
+        &self,
+        object: cbor_object::CborObject,
+        cddl_names: Option<Rc<Names>>,
+    ) -> tree::Node {
+        let mut context = Context {
+            now: self.now,
+            dictionary: self.dictionary.clone(),
+            cddl_names,
+            ..Default::default()
+        };
+        match self.profile {
+            Some(profile) => schema::into_tree_with_schema(object, profile.schema(), &mut context),
+            None => object.into_tree_with_context(&mut context),
+        }
+    }
+}
+
+/// Reads a time given as seconds since the epoch or as an RFC 3339 date, e.g. for
+/// [`DumpOptions::now`].
+pub fn parse_time(text: &str) -> Result<i64> {
+    time::parse_time(text).map_err(|err| anyhow!(err))
+}
+
+/// Reads a dictionary of tag and map key names from its TOML text.
+pub fn parse_dictionary(text: &str) -> Result<Dictionary> {
+    Dictionary::parse(text).map_err(|err| anyhow!("Error reading dictionary: {}", err))
+}
+
+/// Reads the rules of a CDDL file.
+pub fn parse_cddl(text: &str) -> Result<Cddl> {
+    Cddl::parse(text).map_err(|err| anyhow!("Error reading CDDL: {}", err))
+}
+
+/// Reads the COSE message of a health certificate from its `HC1:` text form.
+pub fn decode_hc1(text: &str) -> Result<Vec<u8>> {
+    hcert::decode_hc1(text).map_err(|err| anyhow!("Error decoding HC1 data: {}", err))
+}
+
+#[derive(Debug, Default)]
+pub struct CoseOptions {
+    /// Type of the message when it is not tagged.
+    pub profile: Option<Profile>,
+    /// Externally supplied data that is signed, MACed or authenticated along with the
+    /// message.
+    pub external_aad: Vec<u8>,
+    /// Time in seconds since the epoch at which the validity of CWTs is checked.
+    pub now: Option<i64>,
+}
+
+pub struct CoseOutput {
+    pub output: String,
+    pub success: bool,
+}
+
+/// Verifies a COSE_Sign1 or COSE_Mac0 message with the key in `key_content`, writing the
+/// message, the bytes that are signed or MACed and the result.
+pub fn verify_cose(
+    bytes_content: &[u8],
+    key_content: &[u8],
+    options: &CoseOptions,
+) -> Result<CoseOutput> {
+    let key = cose_key::Key::from_file_content(key_content)
+        .map_err(|err| anyhow!("Error reading key: {}", err))?;
+    let Ok((_, object)) = parse_cbor(bytes_content) else {
+        bail!("Error parsing CBOR data");
+    };
+    let untagged = options.profile.and_then(|profile| profile.cose_tag());
+    let message = Value::from_object(&object)
+        .and_then(|value| cose_verify::Message::from_value(&value, untagged))
+        .map_err(|err| anyhow!("Error reading COSE message: {}", err))?;
+    let to_be_signed = message.to_be_signed(&options.external_aad);
+    let result = cose_verify::verify(&message, &to_be_signed, &key);
+
+    let dump_options = DumpOptions {
+        profile: options.profile,
+        now: options.now,
+        ..Default::default()
+    };
+    let mut output = String::new();
+    dump_options.item_into_tree(object, None).write(&mut output);
+    writeln!(
+        &mut output,
+        "-- {} --",
+        message.message_type.structure_name()
+    )?;
+    // unwrap safety: the encoding of a value is always a well-formed item
+    let (_, to_be_signed) = parse_cbor(&to_be_signed).unwrap();
+    to_be_signed
+        .into_tree_with_context(&mut Context {
+            schema: cose::TO_BE_SIGNED,
+            ..Default::default()
+        })
+        .write(&mut output);
+    let algorithm = message
+        .headers
+        .algorithm()
+        .map(cose::algorithm_name)
+        .unwrap_or_default();
+    let proof_name = message.message_type.proof_name();
+    match &result {
+        Ok(()) => writeln!(&mut output, "valid {} {}", algorithm, proof_name)?,
+        Err(err) => writeln!(&mut output, "invalid {} {}: {}", algorithm, proof_name, err)?,
+    }
+    Ok(CoseOutput {
+        output,
+        success: result.is_ok(),
+    })
+}
+
+/// Decrypts a COSE_Encrypt0 or COSE_Encrypt message with the key in `key_content`, writing
+/// the message, the additional authenticated data and the plaintext.
+pub fn decrypt_cose(
+    bytes_content: &[u8],
+    key_content: &[u8],
+    options: &CoseOptions,
+) -> Result<CoseOutput> {
+    let key = cose_key::Key::from_file_content(key_content)
+        .map_err(|err| anyhow!("Error reading key: {}", err))?;
+    let Ok((_, object)) = parse_cbor(bytes_content) else {
+        bail!("Error parsing CBOR data");
+    };
+    let untagged = options.profile.and_then(|profile| profile.cose_tag());
+    let message = Value::from_object(&object)
+        .and_then(|value| cose_decrypt::EncryptedMessage::from_value(&value, untagged))
+        .map_err(|err| anyhow!("Error reading COSE message: {}", err))?;
+    let enc_structure = message.enc_structure(&options.external_aad);
+    let result = cose_decrypt::decrypt(&message, &enc_structure, &key);
+
+    let dump_options = DumpOptions {
+        profile: options.profile,
+        now: options.now,
+        ..Default::default()
+    };
+    let mut output = String::new();
+    dump_options.item_into_tree(object, None).write(&mut output);
+    writeln!(&mut output, "-- Enc_structure --")?;
+    // unwrap safety: the encoding of a value is always a well-formed item
+    let (_, enc_structure) = parse_cbor(&enc_structure).unwrap();
+    enc_structure
+        .into_tree_with_context(&mut Context {
+            schema: cose::ENC_STRUCTURE,
+            ..Default::default()
+        })
+        .write(&mut output);
+    let algorithm = message
+        .headers
+        .algorithm()
+        .map(cose::algorithm_name)
+        .unwrap_or_default();
+    match &result {
+        Ok(plaintext) => {
+            writeln!(&mut output, "valid {} ciphertext", algorithm)?;
+            writeln!(&mut output, "-- plaintext --")?;
+            // The plaintext is shown as a byte string, so that it is embedded if it is CBOR.
+            let plaintext = Value::Bytes(plaintext.clone()).encode();
+            // unwrap safety: the encoding of a value is always a well-formed item
+            let (_, plaintext) = parse_cbor(&plaintext).unwrap();
+            plaintext.into_tree().write(&mut output);
+        }
+        Err(err) => writeln!(&mut output, "invalid {} ciphertext: {}", algorithm, err)?,
+    }
+    Ok(CoseOutput {
+        output,
+        success: result.is_ok(),
+    })
+}
+
+/// Format of an exported COSE_Key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyFormat {
+    Jwk,
+    Pem,
+    /// The SHA-256 COSE Key Thumbprint (RFC 9679) in hex.
+    Thumbprint,
+}
+
+/// Converts the COSE_Key in `bytes_content` to `format`.
+pub fn export_cose_key(bytes_content: &[u8], format: KeyFormat) -> Result<String> {
+    let Ok((_, object)) = parse_cbor(bytes_content) else {
         bail!("Error parsing CBOR data");
     };
+    let key = Value::from_object(&object)
+        .and_then(|value| cose_key::Key::from_cose_key(&value))
+        .map_err(|err| anyhow!("Error reading COSE_Key: {}", err))?;
+    match format {
+        KeyFormat::Jwk => Ok(key.to_jwk() + "\n"),
+        KeyFormat::Pem => key
+            .to_pem()
+            .map_err(|err| anyhow!("Error exporting key: {}", err)),
+        KeyFormat::Thumbprint => Ok(format_hex(&key.thumbprint()) + "\n"),
+    }
+}
 
-    let cbor_tree = object.into_tree();
+/// A CTAP2 command, for the responses to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ctap2Command {
+    MakeCredential,
+    GetAssertion,
+    GetInfo,
+    ClientPin,
+    BioEnrollment,
+    CredentialManagement,
+    LargeBlobs,
+}
+
+impl Ctap2Command {
+    fn code(&self) -> u8 {
+        match self {
+            Ctap2Command::MakeCredential => ctap2::MAKE_CREDENTIAL_COMMAND,
+            Ctap2Command::GetAssertion => ctap2::GET_ASSERTION_COMMAND,
+            Ctap2Command::GetInfo => ctap2::GET_INFO_COMMAND,
+            Ctap2Command::ClientPin => ctap2::CLIENT_PIN_COMMAND,
+            Ctap2Command::BioEnrollment => ctap2::BIO_ENROLLMENT_COMMAND,
+            Ctap2Command::CredentialManagement => ctap2::CREDENTIAL_MANAGEMENT_COMMAND,
+            Ctap2Command::LargeBlobs => ctap2::LARGE_BLOBS_COMMAND,
+        }
+    }
+}
+
+/// Direction of a CTAP2 frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ctap2Frame {
+    /// A command byte followed by the parameters of the command.
+    Command,
+    /// A status byte followed by the response to the command, when it is known.
+    Response(Option<Ctap2Command>),
+}
+
+/// Writes a CTAP2 frame: its leading command or status byte, then the CBOR map that
+/// follows it with the keys named after the command and a note on any encoding that is not
+/// CTAP2 canonical.
+pub fn dump_ctap2_tree(
+    bytes_content: &[u8],
+    frame: Ctap2Frame,
+    options: &DumpOptions,
+) -> Result<String> {
+    let Some((&leading_byte, rest)) = bytes_content.split_first() else {
+        bail!("Error parsing CTAP2 frame: empty input");
+    };
+    let (leading_node, schema) = match frame {
+        Ctap2Frame::Command => {
+            let node = tree::Node::new(vec![leading_byte])
+                .with_comment(format!("command = {:#04x}", leading_byte));
+            match ctap2::command(leading_byte) {
+                Some(command) => (node.with_annotation(command.name), command.parameters),
+                None => (node.with_annotation("unknown command"), Schema::Any),
+            }
+        }
+        Ctap2Frame::Response(command) => {
+            let node = tree::Node::new(vec![leading_byte])
+                .with_comment(format!("status = {:#04x}", leading_byte));
+            let node =
+                node.with_annotation(ctap2::status_name(leading_byte).unwrap_or("unknown status"));
+            let schema = command
+                .and_then(|command| ctap2::command(command.code()))
+                .map_or(Schema::Any, |command| command.response);
+            (node, schema)
+        }
+    };
 
     let mut output = String::new();
+    leading_node.write(&mut output);
+    if rest.is_empty() {
+        return Ok(output);
+    }
+    let Ok((trailing, object)) = parse_cbor(rest) else {
+        bail!("Error parsing CBOR data");
+    };
+    let mut context = Context {
+        now: options.now,
+        dictionary: options.dictionary.clone(),
+        ..Default::default()
+    };
+    let mut cbor_tree = schema::into_tree_with_schema(object, schema, &mut context);
+    let issues = ctap2::canonical_issues(&rest[..rest.len() - trailing.len()], 1);
+    if !issues.is_empty() {
+        cbor_tree =
+            cbor_tree.with_annotation(format!("not CTAP2 canonical: {}", issues.join(", ")));
+    }
     cbor_tree.write(&mut output);
+    write_trailing_bytes(&mut output, trailing)?;
+    Ok(output)
+}
+
+/// Writes bytes as lowercase hex digits.
+pub fn format_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut acc, b| {
+        write!(acc, "{:02x}", b).unwrap();
+        acc
+    })
+}
+
+fn write_trailing_bytes(output: &mut String, rest: &[u8]) -> Result<()> {
     if !rest.is_empty() {
-        writeln!(
+        writeln!(output, "trailing bytes {}", format_hex(rest))?;
+    }
+    Ok(())
+}
+
+/// What [`dump_cbor_tree`] writes.
+#[derive(Debug, Default)]
+pub struct DumpOutput {
+    /// The items in the chosen format.
+    pub output: String,
+    /// What is reported about the items in `--format diag` and `json`, such as trailing
+    /// bytes, which is kept out of `output`. In the tree format it follows the items.
+    pub report: String,
+}
+
+pub fn dump_cbor_tree(bytes_content: &[u8], options: &DumpOptions) -> Result<DumpOutput> {
+    let Ok((mut rest, object)) = parse_cbor(bytes_content) else {
+        bail!("Error parsing CBOR data");
+    };
+    let sequence = options.sequence || tag_magic::is_sequence_label(&object);
+
+    let mut output = DumpOutput::default();
+    options.write_item(
+        &bytes_content[..bytes_content.len() - rest.len()],
+        0,
+        object,
+        &mut output,
+    );
+    while sequence && !rest.is_empty() {
+        let Ok((rest_new, object)) = parse_cbor(rest) else {
+            break;
+        };
+        let offset = bytes_content.len() - rest.len();
+        options.write_item(
+            &rest[..rest.len() - rest_new.len()],
+            offset,
+            object,
             &mut output,
-            "trailing bytes {}",
-            rest.iter().fold(String::new(), |mut acc, b| {
-                write!(acc, "{:02x}", b).unwrap();
-                acc
-            })
-        )?;
+        );
+        rest = rest_new;
     }
+    write_trailing_bytes(options.report(&mut output), rest)?;
 
     Ok(output)
 }
+
+pub struct CddlOutput {
+    pub output: String,
+    /// What is reported about the items, like [`DumpOutput::report`].
+    pub report: String,
+    pub success: bool,
+}
+
+/// Writes each item of the input with the names that the CDDL rule `cddl` gives to its
+/// members and elements, followed by its mismatches against the rule with the path and
+/// offset of each.
+pub fn validate_cddl(
+    bytes_content: &[u8],
+    cddl: &CddlRule,
+    options: &DumpOptions,
+) -> Result<CddlOutput> {
+    let rule = &cddl.rule;
+    let validator = cddl_validate::Validator::new(&cddl.cddl, bytes_content);
+    let mut rest = bytes_content;
+    let mut output = DumpOutput::default();
+    let mut success = true;
+    while !rest.is_empty() {
+        let offset = bytes_content.len() - rest.len();
+        let Ok((rest_new, object)) = parse_cbor(rest) else {
+            if offset == 0 {
+                bail!("Error parsing CBOR data");
+            }
+            break;
+        };
+        let sequence = options.sequence || tag_magic::is_sequence_label(&object);
+        let report = validator
+            .validate(rule, &object, offset)
+            .map_err(|err| anyhow!("Error reading CDDL: {}", err))?;
+        let mismatches = report.mismatches;
+        options.write_named_item(
+            &rest[..rest.len() - rest_new.len()],
+            offset,
+            object,
+            Some(Rc::new(report.names)),
+            &mut output,
+        );
+        let report = options.report(&mut output);
+        writeln!(report, "-- CDDL rule {} --", rule)?;
+        if mismatches.is_empty() {
+            writeln!(report, "valid")?;
+        }
+        for mismatch in &mismatches {
+            writeln!(report, "{}", mismatch)?;
+        }
+        success &= mismatches.is_empty();
+        rest = rest_new;
+        if !sequence {
+            break;
+        }
+    }
+    write_trailing_bytes(options.report(&mut output), rest)?;
+    Ok(CddlOutput {
+        output: output.output,
+        report: output.report,
+        success,
+    })
+}
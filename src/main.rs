@@ -1,9 +1,14 @@
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
+use std::rc::Rc;
 
 use anyhow::Result;
-use clap::Parser;
+use cbor_inspector::{
+    CddlRule, CoseOptions, Ctap2Command, Ctap2Frame, DiagnosticOptions, DumpOptions, DumpOutput,
+    FloatWidth, Format, JsonEncodeOptions, JsonOptions, KeyFormat, Profile,
+};
+use clap::{Parser, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -11,14 +16,361 @@ struct Args {
     #[arg(short = 'H', long = "hex", default_value_t = false)]
     hex: bool,
 
+    /// Read the input as an `HC1:` string, the Base45 text of a zlib-compressed health
+    /// certificate
+    #[arg(long = "hc1", default_value_t = false, conflicts_with = "hex")]
+    hc1: bool,
+
+    /// Decode the input as a CBOR sequence
+    #[arg(long = "seq", default_value_t = false)]
+    seq: bool,
+
+    /// Structure of the input when it is not tagged
+    #[arg(long = "profile", value_enum)]
+    profile: Option<ProfileArg>,
+
+    /// Verify the COSE_Sign1 or COSE_Mac0 input with the key in a COSE_Key, JWK or PEM file
+    #[arg(long = "verify", value_name = "KEYFILE")]
+    verify: Option<PathBuf>,
+
+    /// Decrypt the COSE_Encrypt0 or COSE_Encrypt input with the symmetric key in a COSE_Key or
+    /// JWK file
+    #[arg(long = "decrypt", value_name = "KEYFILE", conflicts_with = "verify")]
+    decrypt: Option<PathBuf>,
+
+    /// Hex encoded external additional authenticated data for --verify and --decrypt
+    #[arg(long = "external-aad", value_name = "HEX", default_value = "")]
+    external_aad: String,
+
+    /// Convert the COSE_Key input to a JWK, a PEM public key or its thumbprint
+    #[arg(long = "export-key", value_enum, value_name = "FORMAT")]
+    export_key: Option<KeyFormatArg>,
+
+    /// Check the validity of CWTs at TIME, given in seconds since the epoch or as an RFC 3339
+    /// date
+    #[arg(long = "now", value_name = "TIME")]
+    now: Option<String>,
+
+    /// Decode the input as a CTAP2 frame: a command or status byte followed by a CBOR map
+    #[arg(
+        long = "ctap2",
+        value_enum,
+        value_name = "FRAME",
+        conflicts_with = "profile"
+    )]
+    ctap2: Option<Ctap2FrameArg>,
+
+    /// Name private tags and map keys with the TOML dictionary in FILE
+    #[arg(long = "dictionary", value_name = "FILE")]
+    dictionary: Option<PathBuf>,
+
+    /// Validate each item against the CDDL rule given by --rule in FILE
+    #[arg(
+        long = "cddl",
+        value_name = "FILE",
+        requires = "rule",
+        conflicts_with = "ctap2"
+    )]
+    cddl: Option<PathBuf>,
+
+    /// Name of the CDDL rule that the items must match
+    #[arg(long = "rule", value_name = "NAME", requires = "cddl")]
+    rule: Option<String>,
+
+    /// Only name the members and elements of the items after the CDDL rule, without
+    /// validating them
+    #[arg(long = "annotate-only", default_value_t = false, requires = "cddl")]
+    annotate_only: bool,
+
+    /// How to write the items
+    #[arg(
+        long = "format",
+        value_enum,
+        default_value = "tree",
+        conflicts_with = "ctap2"
+    )]
+    format: FormatArg,
+
+    /// Mark longer than needed heads with encoding indicators in --format diag
+    #[arg(long = "encoding-indicators", default_value_t = false)]
+    encoding_indicators: bool,
+
+    /// Write byte strings as b64'' instead of h'' in --format diag
+    #[arg(long = "base64-bytes", default_value_t = false)]
+    base64_bytes: bool,
+
+    /// Encode the input written in diagnostic notation, and write the CBOR as binary, as hex
+    /// or as the tree
+    #[arg(
+        long = "encode",
+        value_enum,
+        value_name = "OUTPUT",
+        num_args = 0..=1,
+        default_missing_value = "binary",
+        conflicts_with_all = ["hex", "hc1"]
+    )]
+    encode: Option<EncodeArg>,
+
+    /// Encode the input JSON or JSON Lines, and write the CBOR as binary, as hex or as the
+    /// tree
+    #[arg(
+        long = "from-json",
+        value_enum,
+        value_name = "OUTPUT",
+        num_args = 0..=1,
+        default_missing_value = "binary",
+        conflicts_with_all = ["hex", "hc1", "encode"]
+    )]
+    from_json: Option<EncodeArg>,
+
+    /// Encode every integer with an argument of 1, 2, 4 or 8 bytes in --from-json
+    #[arg(long = "int-width", value_name = "BYTES")]
+    int_width: Option<usize>,
+
+    /// Encode every float with the given width in --from-json
+    #[arg(long = "float-width", value_enum)]
+    float_width: Option<FloatWidthArg>,
+
+    /// Sort the map keys as in RFC 8949 deterministic encoding in --from-json
+    #[arg(long = "deterministic", default_value_t = false)]
+    deterministic: bool,
+
+    /// JSON pointer to base64 strings to encode as byte strings in --from-json, where * matches
+    /// any key or index
+    #[arg(long = "bytes", value_name = "POINTER")]
+    bytes: Vec<String>,
+
+    /// Keep tags as {"tag": n, "value": ...} objects in --format json
+    #[arg(long = "keep-tags", default_value_t = false)]
+    keep_tags: bool,
+
+    /// Write bignums as strings instead of numbers in --format json
+    #[arg(long = "bignum-strings", default_value_t = false)]
+    bignum_strings: bool,
+
+    /// Write each item as an envelope that keeps its type and encoding in --format json
+    #[arg(long = "envelope", default_value_t = false)]
+    envelope: bool,
+
+    /// Write the packed CBOR input as plain CBOR instead of the tree
+    #[arg(long = "unpack", default_value_t = false)]
+    unpack: bool,
+
     filepath: Option<PathBuf>,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ProfileArg {
+    CoseSign1,
+    CoseSign,
+    CoseMac0,
+    CoseMac,
+    CoseEncrypt0,
+    CoseEncrypt,
+    CoseKey,
+    CoseKeySet,
+    Cwt,
+    WebauthnAttestation,
+    Mdoc,
+    Hcert,
+    Senml,
+    Suit,
+    Corim,
+    Coswid,
+    DagCbor,
+}
+
+impl From<ProfileArg> for Profile {
+    fn from(profile: ProfileArg) -> Self {
+        match profile {
+            ProfileArg::CoseSign1 => Profile::CoseSign1,
+            ProfileArg::CoseSign => Profile::CoseSign,
+            ProfileArg::CoseMac0 => Profile::CoseMac0,
+            ProfileArg::CoseMac => Profile::CoseMac,
+            ProfileArg::CoseEncrypt0 => Profile::CoseEncrypt0,
+            ProfileArg::CoseEncrypt => Profile::CoseEncrypt,
+            ProfileArg::CoseKey => Profile::CoseKey,
+            ProfileArg::CoseKeySet => Profile::CoseKeySet,
+            ProfileArg::Cwt => Profile::Cwt,
+            ProfileArg::WebauthnAttestation => Profile::WebauthnAttestation,
+            ProfileArg::Mdoc => Profile::Mdoc,
+            ProfileArg::Hcert => Profile::Hcert,
+            ProfileArg::Senml => Profile::Senml,
+            ProfileArg::Suit => Profile::Suit,
+            ProfileArg::Corim => Profile::Corim,
+            ProfileArg::Coswid => Profile::Coswid,
+            ProfileArg::DagCbor => Profile::DagCbor,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FormatArg {
+    Tree,
+    Diag,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum EncodeArg {
+    Binary,
+    Hex,
+    Tree,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FloatWidthArg {
+    Half,
+    Single,
+    Double,
+}
+
+impl From<FloatWidthArg> for FloatWidth {
+    fn from(width: FloatWidthArg) -> Self {
+        match width {
+            FloatWidthArg::Half => FloatWidth::Half,
+            FloatWidthArg::Single => FloatWidth::Single,
+            FloatWidthArg::Double => FloatWidth::Double,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum KeyFormatArg {
+    Jwk,
+    Pem,
+    Thumbprint,
+}
+
+impl From<KeyFormatArg> for KeyFormat {
+    fn from(format: KeyFormatArg) -> Self {
+        match format {
+            KeyFormatArg::Jwk => KeyFormat::Jwk,
+            KeyFormatArg::Pem => KeyFormat::Pem,
+            KeyFormatArg::Thumbprint => KeyFormat::Thumbprint,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Ctap2FrameArg {
+    Command,
+    Response,
+    MakeCredentialResponse,
+    GetAssertionResponse,
+    GetInfoResponse,
+    ClientPinResponse,
+    BioEnrollmentResponse,
+    CredentialManagementResponse,
+    LargeBlobsResponse,
+}
+
+impl From<Ctap2FrameArg> for Ctap2Frame {
+    fn from(frame: Ctap2FrameArg) -> Self {
+        match frame {
+            Ctap2FrameArg::Command => Ctap2Frame::Command,
+            Ctap2FrameArg::Response => Ctap2Frame::Response(None),
+            Ctap2FrameArg::MakeCredentialResponse => {
+                Ctap2Frame::Response(Some(Ctap2Command::MakeCredential))
+            }
+            Ctap2FrameArg::GetAssertionResponse => {
+                Ctap2Frame::Response(Some(Ctap2Command::GetAssertion))
+            }
+            Ctap2FrameArg::GetInfoResponse => Ctap2Frame::Response(Some(Ctap2Command::GetInfo)),
+            Ctap2FrameArg::ClientPinResponse => Ctap2Frame::Response(Some(Ctap2Command::ClientPin)),
+            Ctap2FrameArg::BioEnrollmentResponse => {
+                Ctap2Frame::Response(Some(Ctap2Command::BioEnrollment))
+            }
+            Ctap2FrameArg::CredentialManagementResponse => {
+                Ctap2Frame::Response(Some(Ctap2Command::CredentialManagement))
+            }
+            Ctap2FrameArg::LargeBlobsResponse => {
+                Ctap2Frame::Response(Some(Ctap2Command::LargeBlobs))
+            }
+        }
+    }
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
+    // An HC1 string holds a health certificate unless told otherwise.
+    let profile = args
+        .profile
+        .map(Profile::from)
+        .or(args.hc1.then_some(Profile::Hcert));
 
     #[allow(clippy::collapsible_else_if)]
-    let bytes_content = if args.hex {
+    let bytes_content = if args.hc1 {
+        let hc1_content = if let Some(filepath) = &args.filepath {
+            fs::read_to_string(filepath)?
+        } else {
+            io::read_to_string(io::stdin())?
+        };
+
+        match cbor_inspector::decode_hc1(&hc1_content) {
+            Ok(bytes_content) => bytes_content,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    } else if let Some(encode) = args.encode {
+        let edn_content = if let Some(filepath) = &args.filepath {
+            fs::read_to_string(filepath)?
+        } else {
+            io::read_to_string(io::stdin())?
+        };
+
+        let bytes_content = match cbor_inspector::encode_diagnostic(&edn_content) {
+            Ok(bytes_content) => bytes_content,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        };
+        match encode {
+            EncodeArg::Binary => {
+                io::stdout().write_all(&bytes_content)?;
+                return Ok(());
+            }
+            EncodeArg::Hex => {
+                println!("{}", cbor_inspector::format_hex(&bytes_content));
+                return Ok(());
+            }
+            EncodeArg::Tree => bytes_content,
+        }
+    } else if let Some(from_json) = args.from_json {
+        let json_content = if let Some(filepath) = &args.filepath {
+            fs::read_to_string(filepath)?
+        } else {
+            io::read_to_string(io::stdin())?
+        };
+
+        let options = JsonEncodeOptions {
+            integer_length: args.int_width,
+            float_width: args.float_width.map(FloatWidth::from),
+            deterministic: args.deterministic,
+            byte_strings: args.bytes.clone(),
+        };
+        let bytes_content = match cbor_inspector::encode_json(&json_content, &options) {
+            Ok(bytes_content) => bytes_content,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        };
+        match from_json {
+            EncodeArg::Binary => {
+                io::stdout().write_all(&bytes_content)?;
+                return Ok(());
+            }
+            EncodeArg::Hex => {
+                println!("{}", cbor_inspector::format_hex(&bytes_content));
+                return Ok(());
+            }
+            EncodeArg::Tree => bytes_content,
+        }
+    } else if args.hex {
         let hex_content = if let Some(filepath) = args.filepath {
             fs::read_to_string(filepath)?
         } else {
@@ -41,13 +393,158 @@ fn main() -> Result<()> {
             buffer
         }
     };
-    let output = match cbor_inspector::dump_cbor_tree(&bytes_content) {
+    if args.unpack {
+        match cbor_inspector::unpack_cbor(&bytes_content) {
+            Ok(output) => io::stdout().write_all(&output)?,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let now = match args
+        .now
+        .as_deref()
+        .map(cbor_inspector::parse_time)
+        .transpose()
+    {
+        Ok(now) => now,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(format) = args.export_key {
+        match cbor_inspector::export_cose_key(&bytes_content, format.into()) {
+            Ok(output) => print!("{}", output),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(key_filepath) = args.verify.as_ref().or(args.decrypt.as_ref()) {
+        let key_content = fs::read(key_filepath)?;
+        let external_aad = match cbor_inspector::parse_hex(&args.external_aad) {
+            Ok(external_aad) => external_aad,
+            Err(err) => {
+                eprintln!("{} in --external-aad", err);
+                std::process::exit(1);
+            }
+        };
+        let options = CoseOptions {
+            profile,
+            external_aad,
+            now,
+        };
+        let result = if args.verify.is_some() {
+            cbor_inspector::verify_cose(&bytes_content, &key_content, &options)
+        } else {
+            cbor_inspector::decrypt_cose(&bytes_content, &key_content, &options)
+        };
+        match result {
+            Ok(cose_output) => {
+                print!("{}", cose_output.output);
+                if !cose_output.success {
+                    std::process::exit(1);
+                }
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let dictionary = match args.dictionary {
+        Some(filepath) => match cbor_inspector::parse_dictionary(&fs::read_to_string(filepath)?) {
+            Ok(dictionary) => Some(Rc::new(dictionary)),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let cddl = match (&args.cddl, &args.rule) {
+        (Some(filepath), Some(rule)) => {
+            match cbor_inspector::parse_cddl(&fs::read_to_string(filepath)?)
+                .and_then(|cddl| CddlRule::new(cddl, rule))
+            {
+                Ok(cddl) => Some(cddl),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => None,
+    };
+    let mut options = DumpOptions {
+        // Several items written in diagnostic notation make a sequence.
+        sequence: args.seq
+            || args.encode == Some(EncodeArg::Tree)
+            || args.from_json == Some(EncodeArg::Tree),
+        profile,
+        now,
+        dictionary,
+        cddl: None,
+        format: match args.format {
+            FormatArg::Tree => Format::Tree,
+            FormatArg::Diag => Format::Diagnostic(DiagnosticOptions {
+                encoding_indicators: args.encoding_indicators,
+                base64: args.base64_bytes,
+            }),
+            FormatArg::Json => Format::Json(JsonOptions {
+                keep_tags: args.keep_tags,
+                bignum_strings: args.bignum_strings,
+                envelope: args.envelope,
+            }),
+        },
+    };
+    if let Some(cddl) = cddl {
+        if args.annotate_only {
+            options.cddl = Some(cddl);
+        } else {
+            match cbor_inspector::validate_cddl(&bytes_content, &cddl, &options) {
+                Ok(cddl_output) => {
+                    print!("{}", cddl_output.output);
+                    eprint!("{}", cddl_output.report);
+                    if !cddl_output.success {
+                        std::process::exit(1);
+                    }
+                }
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+            return Ok(());
+        }
+    }
+    let result =
+        match args.ctap2 {
+            Some(frame) => cbor_inspector::dump_ctap2_tree(&bytes_content, frame.into(), &options)
+                .map(|output| DumpOutput {
+                    output,
+                    ..Default::default()
+                }),
+            None => cbor_inspector::dump_cbor_tree(&bytes_content, &options),
+        };
+    let output = match result {
         Ok(output) => output,
         Err(err) => {
             eprintln!("{}", err);
             std::process::exit(1);
         }
     };
-    print!("{}", output);
+    print!("{}", output.output);
+    eprint!("{}", output.report);
     Ok(())
 }
@@ -0,0 +1,321 @@
+//! Mobile documents such as the mobile driving licence (ISO/IEC 18013-5 Section 8.3): the
+//! device response, the data elements signed by the issuer and the mobile security object
+//! holding their digests.
+
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::cbor_object::CborObject;
+use crate::cbor_parser::parse_cbor;
+use crate::context::Context;
+use crate::cose;
+use crate::cose_key;
+use crate::schema::{Entry, Schema};
+use crate::tag_magic::TAG_ENCODED_CBOR;
+use crate::value::Value;
+
+const STATUS_CODES: &[(i64, &str)] = &[
+    (0, "OK"),
+    (10, "general error"),
+    (11, "CBOR decoding error"),
+    (12, "CBOR validation error"),
+];
+
+const ISSUER_SIGNED_ITEM: Schema = Schema::Map(&[
+    Entry::text("digestID", "digest id", Schema::Any),
+    Entry::text("random", "random", Schema::Opaque),
+    Entry::text("elementIdentifier", "data element identifier", Schema::Any),
+    Entry::text("elementValue", "data element value", Schema::Any),
+]);
+
+const VALIDITY_INFO: Schema = Schema::Map(&[
+    Entry::text("signed", "signed", Schema::Any),
+    Entry::text("validFrom", "valid from", Schema::Any),
+    Entry::text("validUntil", "valid until", Schema::Any),
+    Entry::text("expectedUpdate", "expected update", Schema::Any),
+]);
+
+const DEVICE_KEY_INFO: Schema = Schema::Map(&[
+    Entry::text("deviceKey", "device key", cose_key::COSE_KEY),
+    Entry::text("keyAuthorizations", "key authorizations", Schema::Any),
+    Entry::text("keyInfo", "key info", Schema::Any),
+]);
+
+const MOBILE_SECURITY_OBJECT: Schema = Schema::Map(&[
+    Entry::text("version", "version", Schema::Any),
+    Entry::text("digestAlgorithm", "digest algorithm", Schema::Any),
+    Entry::text(
+        "valueDigests",
+        "value digests",
+        Schema::MapOf(&Schema::MapOf(&Schema::Opaque)),
+    ),
+    Entry::text("deviceKeyInfo", "device key info", DEVICE_KEY_INFO),
+    Entry::text("docType", "document type", Schema::Any),
+    Entry::text("validityInfo", "validity info", VALIDITY_INFO),
+]);
+
+/// The payload of the issuer signature, a tag 24 around the mobile security object.
+static MOBILE_SECURITY_OBJECT_BYTES: Schema = Schema::Embedded(&MOBILE_SECURITY_OBJECT);
+static ISSUER_AUTH: Schema =
+    Schema::Array(&cose::sign1_with_payload(&MOBILE_SECURITY_OBJECT_BYTES));
+
+const ISSUER_SIGNED_MAP: Schema = Schema::Map(&[
+    Entry::text(
+        "nameSpaces",
+        "issuer name spaces",
+        Schema::MapOf(&Schema::ArrayOf(&Schema::Embedded(&ISSUER_SIGNED_ITEM))),
+    ),
+    Entry::text("issuerAuth", "issuer signature", ISSUER_AUTH),
+]);
+
+static DEVICE_AUTH: Schema = Schema::Map(&[
+    Entry::text("deviceSignature", "device signature", cose::COSE_SIGN1),
+    Entry::text("deviceMac", "device MAC", cose::COSE_MAC0),
+]);
+
+const DEVICE_SIGNED: Schema = Schema::Map(&[
+    Entry::text("nameSpaces", "device name spaces", Schema::Any),
+    Entry::text("deviceAuth", "device authentication", DEVICE_AUTH),
+]);
+
+const DOCUMENT: Schema = Schema::Map(&[
+    Entry::text("docType", "document type", Schema::Any),
+    Entry::text("issuerSigned", "issuer-signed data", ISSUER_SIGNED),
+    Entry::text("deviceSigned", "device-signed data", DEVICE_SIGNED),
+    Entry::text("errors", "errors", Schema::Any),
+]);
+
+const DEVICE_RESPONSE: Schema = Schema::Map(&[
+    Entry::text("version", "version", Schema::Any),
+    Entry::text("documents", "documents", Schema::ArrayOf(&DOCUMENT)),
+    Entry::text("documentErrors", "document errors", Schema::Any),
+    Entry::text("status", "status", Schema::Values(STATUS_CODES)),
+]);
+
+fn decode(bytes: &[u8]) -> Result<Value, String> {
+    let Ok((_, object)) = parse_cbor(bytes) else {
+        return Err("invalid embedded item".to_string());
+    };
+    Value::from_object(&object)
+}
+
+/// Returns the bytes embedded with tag 24.
+fn encoded_cbor(value: &Value) -> Option<&[u8]> {
+    match value {
+        Value::Tag(TAG_ENCODED_CBOR, content) => content.as_bytes(),
+        _ => None,
+    }
+}
+
+/// Reads the mobile security object from the payload of the issuer signature.
+fn mobile_security_object(issuer_auth: &Value) -> Result<Value, String> {
+    let message = match issuer_auth {
+        Value::Tag(cose::TAG_COSE_SIGN1, message) => &**message,
+        message => message,
+    };
+    let payload = match message {
+        Value::Array(elements) if elements.len() == 4 => elements[2].as_bytes(),
+        _ => None,
+    };
+    let payload = decode(payload.ok_or("issuerAuth is not a COSE_Sign1 message with payload")?)?;
+    decode(encoded_cbor(&payload).ok_or("expected MobileSecurityObjectBytes")?)
+}
+
+fn digest(algorithm: &str, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    match algorithm {
+        "SHA-256" => Ok(Sha256::digest(bytes).to_vec()),
+        "SHA-384" => Ok(Sha384::digest(bytes).to_vec()),
+        "SHA-512" => Ok(Sha512::digest(bytes).to_vec()),
+        _ => Err(format!("unsupported digest algorithm {:?}", algorithm)),
+    }
+}
+
+/// Returns the value of the member of a map with the text key `label`.
+fn member<'a>(map: &'a CborObject, label: &str) -> Option<&'a CborObject> {
+    map.as_map()?
+        .into_iter()
+        .find(|(key, _)| key.as_text().as_deref() == Some(label))
+        .map(|(_, value)| value)
+}
+
+/// Checks the digest of each IssuerSignedItemBytes against the value digests of the mobile
+/// security object (ISO/IEC 18013-5 Section 9.1.2.4).
+fn check_digests(issuer_signed: &CborObject) -> Result<String, String> {
+    let issuer_auth = member(issuer_signed, "issuerAuth").ok_or("missing issuerAuth")?;
+    let mso = mobile_security_object(&Value::from_object(issuer_auth)?)?;
+    let algorithm = mso
+        .get_text("digestAlgorithm")
+        .and_then(Value::as_text)
+        .ok_or("missing digestAlgorithm")?;
+    let value_digests = mso.get_text("valueDigests").ok_or("missing valueDigests")?;
+    let Some(name_spaces) = member(issuer_signed, "nameSpaces").and_then(CborObject::as_map) else {
+        return Ok("no issuer-signed data elements".to_string());
+    };
+
+    let mut checked = 0;
+    let mut mismatches = vec![];
+    for (name_space, items) in name_spaces {
+        let name_space = name_space.as_text().ok_or("name space is not a tstr")?;
+        let Some(items) = items.as_array() else {
+            return Err(format!("data elements of {} are not an array", name_space));
+        };
+        for item in items {
+            let item_bytes = Value::from_object(item)?;
+            let item_bytes = encoded_cbor(&item_bytes).ok_or("expected IssuerSignedItemBytes")?;
+            let item_value = decode(item_bytes)?;
+            let identifier = item_value
+                .get_text("elementIdentifier")
+                .and_then(Value::as_text)
+                .unwrap_or("?");
+            let digest_id = item_value
+                .get_text("digestID")
+                .and_then(Value::as_integer)
+                .and_then(|id| i64::try_from(id).ok())
+                .ok_or_else(|| format!("missing digestID of {}/{}", name_space, identifier))?;
+            let expected = value_digests
+                .get_text(&name_space)
+                .and_then(|digests| digests.get(digest_id))
+                .and_then(Value::as_bytes);
+            // The digest covers the tag and the byte string around the item, as the issuer
+            // encoded them.
+            let actual = digest(algorithm, &item.encode())?;
+            if expected != Some(actual.as_slice()) {
+                mismatches.push(format!("{}/{}", name_space, identifier));
+            }
+            checked += 1;
+        }
+    }
+    if !mismatches.is_empty() {
+        return Ok(format!("value digest mismatch: {}", mismatches.join(", ")));
+    }
+    Ok(format!("{} {} value digests match", checked, algorithm))
+}
+
+fn select_issuer_signed(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    let verdict = check_digests(object);
+    let description = match verdict {
+        Ok(verdict) => verdict,
+        Err(err) => format!("invalid issuer-signed data: {}", err),
+    };
+    (ISSUER_SIGNED_MAP, Some(description))
+}
+
+/// Issuer-signed data elements, described by whether their digests match the ones the
+/// issuer signed.
+const ISSUER_SIGNED: Schema = Schema::Select(select_issuer_signed);
+
+/// Tells a device response from a single document or its issuer-signed part by its keys.
+fn select_mdoc(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    let value = Value::from_object(object).unwrap_or(Value::Simple(0));
+    let has = |key| value.get_text(key).is_some();
+    if has("issuerAuth") {
+        (ISSUER_SIGNED, None)
+    } else if has("issuerSigned") {
+        (DOCUMENT, None)
+    } else if has("status") || has("documents") {
+        (DEVICE_RESPONSE, None)
+    } else {
+        (
+            Schema::Any,
+            Some("invalid mdoc: expected DeviceResponse, Document or IssuerSigned".to_string()),
+        )
+    }
+}
+
+/// A DeviceResponse, or one Document or IssuerSigned structure of it.
+pub const MDOC: Schema = Schema::Select(select_mdoc);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The encoding of IssuerSigned with one data element in `org.iso.18013.5.1`, tagged
+    /// with the head `item_tag`, and the SHA-256 digest `item_digest` for it.
+    fn issuer_signed(item_tag: &[u8], item_digest: &[u8]) -> Vec<u8> {
+        let item = Value::Map(vec![
+            (Value::Text("digestID".into()), Value::Unsigned(7)),
+            (Value::Text("random".into()), Value::Bytes(vec![0x5a; 16])),
+            (
+                Value::Text("elementIdentifier".into()),
+                Value::Text("family_name".into()),
+            ),
+            (
+                Value::Text("elementValue".into()),
+                Value::Text("Doe".into()),
+            ),
+        ]);
+        let mut item_bytes = item_tag.to_vec();
+        item_bytes.extend(Value::Bytes(item.encode()).encode());
+        let digests = if item_digest.is_empty() {
+            Sha256::digest(&item_bytes).to_vec()
+        } else {
+            item_digest.to_vec()
+        };
+        let mso = Value::Map(vec![
+            (
+                Value::Text("digestAlgorithm".into()),
+                Value::Text("SHA-256".into()),
+            ),
+            (
+                Value::Text("valueDigests".into()),
+                Value::Map(vec![(
+                    Value::Text("org.iso.18013.5.1".into()),
+                    Value::Map(vec![(Value::Unsigned(7), Value::Bytes(digests))]),
+                )]),
+            ),
+        ]);
+        let mso_bytes = Value::Tag(TAG_ENCODED_CBOR, Box::new(Value::Bytes(mso.encode())));
+        let issuer_auth = Value::Array(vec![
+            Value::Bytes(vec![0xa1, 0x01, 0x26]),
+            Value::Map(vec![]),
+            Value::Bytes(mso_bytes.encode()),
+            Value::Bytes(vec![0; 64]),
+        ]);
+        // {"nameSpaces": {"org.iso.18013.5.1": [item_bytes]}, "issuerAuth": issuer_auth}
+        let mut output = vec![0xa2];
+        output.extend(Value::Text("nameSpaces".into()).encode());
+        output.push(0xa1);
+        output.extend(Value::Text("org.iso.18013.5.1".into()).encode());
+        output.push(0x81);
+        output.extend(item_bytes);
+        output.extend(Value::Text("issuerAuth".into()).encode());
+        output.extend(issuer_auth.encode());
+        output
+    }
+
+    fn check(bytes: &[u8]) -> Result<String, String> {
+        let (_, object) = parse_cbor(bytes).unwrap();
+        check_digests(&object)
+    }
+
+    #[test]
+    fn value_digests() {
+        assert_eq!(
+            check(&issuer_signed(&[0xd8, 0x18], &[])),
+            Ok("1 SHA-256 value digests match".to_string())
+        );
+        // The digest is over the tag head as the issuer encoded it.
+        assert_eq!(
+            check(&issuer_signed(&[0xd9, 0x00, 0x18], &[])),
+            Ok("1 SHA-256 value digests match".to_string())
+        );
+        assert_eq!(
+            check(&issuer_signed(&[0xd8, 0x18], &[0; 32])),
+            Ok("value digest mismatch: org.iso.18013.5.1/family_name".to_string())
+        );
+        assert_eq!(check(&[0xa0]), Err("missing issuerAuth".to_string()));
+    }
+
+    #[test]
+    fn mdoc_structures() {
+        let context = Context::default();
+        let select = |bytes: &[u8]| {
+            let (_, object) = parse_cbor(bytes).unwrap();
+            select_mdoc(&object, &context).1
+        };
+        assert_eq!(select(&issuer_signed(&[0xd8, 0x18], &[])), None);
+        assert_eq!(
+            select(&[0xa0]),
+            Some("invalid mdoc: expected DeviceResponse, Document or IssuerSigned".to_string())
+        );
+    }
+}
@@ -0,0 +1,316 @@
+//! Packed CBOR (draft-ietf-cbor-packed).
+//!
+//! Tag 113 sets up a table, `113([[*item], rump])`, whose items are prepended to the table
+//! that is already in effect. Inside the rump, items are referenced by:
+//!
+//! - shared item references: `simple(0)` to `simple(15)` for items 0 to 15, and `6(n)` for
+//!   item `16 + 2n` (unsigned `n`) or `16 + 2n + 1` (negative `-1 - n`);
+//! - straight argument references: tags 224 to 255, 28704 to 32767 and 1879052288 to
+//!   2147483647, which prepend the referenced item to their content;
+//! - inverted argument references: tags 216 to 223, 27648 to 28671 and 1811940352 to
+//!   1879048191, which append the referenced item to their content.
+
+use crate::cbor_object::{CborObject, ToTree};
+use crate::cbor_parser::parse_cbor;
+use crate::context::Context;
+use crate::diagnostic::short_diagnostic;
+use crate::tree::Node;
+use crate::value::Value;
+
+pub const TAG_PACKED: u64 = 113;
+pub const TAG_SHARED_ITEM_REFERENCE: u64 = 6;
+
+/// Number of simple values used as shared item references.
+const SIMPLE_REFERENCES: u8 = 16;
+
+pub fn is_reference_simple(value: u8) -> bool {
+    value < SIMPLE_REFERENCES
+}
+
+/// Limits the nesting of references, which could otherwise loop forever.
+const MAX_DEPTH: usize = 64;
+
+/// Limits the number of items visited while unpacking, as references to items that hold
+/// references themselves can otherwise expand exponentially.
+const MAX_ITEMS: usize = 1 << 20;
+
+enum ArgumentReference {
+    Straight(usize),
+    Inverted(usize),
+}
+
+fn argument_reference(tag: u64) -> Option<ArgumentReference> {
+    let (first_tag, first_index, straight) = match tag {
+        216..=223 => (216, 0, false),
+        224..=255 => (224, 0, true),
+        27648..=28671 => (27648, 8, false),
+        28704..=32767 => (28704, 32, true),
+        1811940352..=1879048191 => (1811940352, 1032, false),
+        1879052288..=2147483647 => (1879052288, 4096, true),
+        _ => return None,
+    };
+    let index = (tag - first_tag) as usize + first_index;
+    match straight {
+        true => Some(ArgumentReference::Straight(index)),
+        false => Some(ArgumentReference::Inverted(index)),
+    }
+}
+
+pub fn is_reference_tag(tag: u64) -> bool {
+    tag == TAG_SHARED_ITEM_REFERENCE || argument_reference(tag).is_some()
+}
+
+pub fn tag_name(tag: u64) -> Option<&'static str> {
+    match tag {
+        TAG_PACKED => Some("packed cbor"),
+        TAG_SHARED_ITEM_REFERENCE => Some("shared item reference"),
+        _ => match argument_reference(tag)? {
+            ArgumentReference::Straight(_) => Some("straight argument reference"),
+            ArgumentReference::Inverted(_) => Some("inverted argument reference"),
+        },
+    }
+}
+
+fn shared_item_index(content: &Value) -> Option<usize> {
+    match content {
+        Value::Unsigned(n) => usize::try_from(*n).ok()?.checked_mul(2)?.checked_add(16),
+        Value::Negative(n) => usize::try_from(*n).ok()?.checked_mul(2)?.checked_add(17),
+        _ => None,
+    }
+}
+
+fn lookup(table: &[Value], index: usize) -> Result<&Value, String> {
+    table.get(index).ok_or_else(|| {
+        format!(
+            "item {} is out of range ({} items in table)",
+            index,
+            table.len()
+        )
+    })
+}
+
+/// Splits the content of tag 113 into the new table items and the rump.
+pub fn split_table_setup(content: &Value) -> Result<(&[Value], &Value), String> {
+    match content {
+        Value::Array(items) => match &items[..] {
+            [Value::Array(table), rump] => Ok((table, rump)),
+            _ => Err("expected [[*item], rump]".to_string()),
+        },
+        _ => Err("expected array".to_string()),
+    }
+}
+
+fn combine(argument: Value, rump: Value) -> Result<Value, String> {
+    match (argument, rump) {
+        (Value::Bytes(mut argument), Value::Bytes(rump)) => {
+            argument.extend(rump);
+            Ok(Value::Bytes(argument))
+        }
+        (Value::Text(mut argument), Value::Text(rump)) => {
+            argument.push_str(&rump);
+            Ok(Value::Text(argument))
+        }
+        (Value::Array(mut argument), Value::Array(rump)) => {
+            argument.extend(rump);
+            Ok(Value::Array(argument))
+        }
+        (Value::Map(mut argument), Value::Map(rump)) => {
+            argument.extend(rump);
+            Ok(Value::Map(argument))
+        }
+        _ => Err("argument and rump cannot be concatenated".to_string()),
+    }
+}
+
+fn unpack_with_depth(
+    value: &Value,
+    table: &[Value],
+    depth: usize,
+    budget: &mut usize,
+) -> Result<Value, String> {
+    if depth > MAX_DEPTH {
+        return Err("references are nested too deeply".to_string());
+    }
+    *budget = budget
+        .checked_sub(1)
+        .ok_or_else(|| "unpacked item too large".to_string())?;
+    let mut unpack = |value: &Value| unpack_with_depth(value, table, depth + 1, budget);
+    match value {
+        Value::Simple(n) if is_reference_simple(*n) => unpack(lookup(table, usize::from(*n))?),
+        Value::Tag(TAG_SHARED_ITEM_REFERENCE, content) => match shared_item_index(content) {
+            Some(index) => unpack(lookup(table, index)?),
+            None => Err("expected integer shared item index".to_string()),
+        },
+        Value::Tag(TAG_PACKED, content) => {
+            let (items, rump) = split_table_setup(content)?;
+            let table = [items, table].concat();
+            unpack_with_depth(rump, &table, depth + 1, budget)
+        }
+        Value::Tag(tag, content) => match argument_reference(*tag) {
+            Some(ArgumentReference::Straight(index)) => {
+                let argument = unpack(lookup(table, index)?)?;
+                combine(argument, unpack(content)?)
+            }
+            Some(ArgumentReference::Inverted(index)) => {
+                let content = unpack(content)?;
+                combine(content, unpack(lookup(table, index)?)?)
+            }
+            None => Ok(Value::Tag(*tag, Box::new(unpack(content)?))),
+        },
+        Value::Array(items) => Ok(Value::Array(
+            items.iter().map(unpack).collect::<Result<_, _>>()?,
+        )),
+        Value::Map(pairs) => Ok(Value::Map(
+            pairs
+                .iter()
+                .map(|(key, value)| Ok((unpack(key)?, unpack(value)?)))
+                .collect::<Result<_, String>>()?,
+        )),
+        _ => Ok(value.clone()),
+    }
+}
+
+/// Replaces every reference in `value` by the item it refers to in `table`.
+pub fn unpack(value: &Value, table: &[Value]) -> Result<Value, String> {
+    let mut budget = MAX_ITEMS;
+    unpack_with_depth(value, table, 0, &mut budget)
+}
+
+/// Describes what a reference inside a packed item stands for.
+pub fn describe_reference(reference: &Value, table: &[Value]) -> Result<String, String> {
+    let unpacked = unpack(reference, table)?;
+    let encoded = unpacked.encode();
+    // unwrap safety: the encoding of a value is always a well-formed item
+    let (_, object) = parse_cbor(&encoded).unwrap();
+    Ok(format!("-> {}", short_diagnostic(&object)))
+}
+
+/// Builds the content of tag 113 with its table items prepended to the table in effect.
+pub fn into_tree(payload: CborObject, context: &mut Context) -> (Result<String, String>, Node) {
+    let items = Value::from_object(&payload)
+        .and_then(|content| Ok(split_table_setup(&content)?.0.to_vec()));
+    match items {
+        Ok(items) => {
+            let description = match items.len() {
+                1 => "1 table item".to_string(),
+                n => format!("{} table items", n),
+            };
+            let outer_table = context.packing_table.take();
+            let table = [items, outer_table.clone().unwrap_or_default()].concat();
+            context.packing_table = Some(table);
+            let child = payload.into_tree_with_context(context);
+            context.packing_table = outer_table;
+            (Ok(description), child)
+        }
+        Err(err) => (Err(err), payload.into_tree_with_context(context)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Value {
+        Value::Text(s.to_string())
+    }
+
+    #[test]
+    fn unpack_shared_and_argument_references() {
+        let table = vec![text("https://example.com/"), text("name")];
+        let packed = Value::Array(vec![
+            Value::Tag(224, Box::new(text("a"))),
+            Value::Tag(216, Box::new(text("http://"))),
+            Value::Simple(1),
+            Value::Tag(6, Box::new(Value::Unsigned(0))),
+        ]);
+        assert_eq!(
+            unpack(&packed, &table),
+            Err("item 16 is out of range (2 items in table)".to_string())
+        );
+
+        let packed = Value::Array(vec![
+            Value::Tag(224, Box::new(text("a"))),
+            Value::Tag(216, Box::new(text("http://"))),
+            Value::Simple(1),
+        ]);
+        assert_eq!(
+            unpack(&packed, &table),
+            Ok(Value::Array(vec![
+                text("https://example.com/a"),
+                text("http://https://example.com/"),
+                text("name"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn unpack_nested_table_setup() {
+        let packed = Value::Tag(
+            TAG_PACKED,
+            Box::new(Value::Array(vec![
+                Value::Array(vec![text("inner")]),
+                Value::Array(vec![Value::Simple(0), Value::Simple(1)]),
+            ])),
+        );
+        assert_eq!(
+            unpack(&packed, &[text("outer")]),
+            Ok(Value::Array(vec![text("inner"), text("outer")]))
+        );
+    }
+
+    #[test]
+    fn argument_reference_ranges() {
+        let index = |tag| match argument_reference(tag) {
+            Some(ArgumentReference::Straight(index)) => Some((index, true)),
+            Some(ArgumentReference::Inverted(index)) => Some((index, false)),
+            None => None,
+        };
+        assert_eq!(index(215), None);
+        assert_eq!(index(216), Some((0, false)));
+        assert_eq!(index(223), Some((7, false)));
+        assert_eq!(index(224), Some((0, true)));
+        assert_eq!(index(255), Some((31, true)));
+        assert_eq!(index(27647), None);
+        assert_eq!(index(27648), Some((8, false)));
+        assert_eq!(index(28671), Some((1031, false)));
+        assert_eq!(index(28703), None);
+        assert_eq!(index(28704), Some((32, true)));
+        assert_eq!(index(32767), Some((4095, true)));
+        assert_eq!(index(1811940351), None);
+        assert_eq!(index(1811940352), Some((1032, false)));
+        assert_eq!(index(1879048191), Some((67108871, false)));
+        assert_eq!(index(1879052287), None);
+        assert_eq!(index(1879052288), Some((4096, true)));
+        assert_eq!(index(2147483647), Some((268435455, true)));
+        assert_eq!(index(2147483648), None);
+    }
+
+    #[test]
+    fn unpack_too_large() {
+        let reference = |index: u64| match index {
+            0..=15 => Value::Simple(index as u8),
+            _ if index.is_multiple_of(2) => {
+                Value::Tag(6, Box::new(Value::Unsigned((index - 16) / 2)))
+            }
+            _ => Value::Tag(6, Box::new(Value::Negative((index - 17) / 2))),
+        };
+        let mut table: Vec<_> = (1..24)
+            .map(|index| Value::Array(vec![reference(index), reference(index)]))
+            .collect();
+        table.push(text("leaf"));
+        assert_eq!(
+            unpack(&reference(22), &table),
+            Ok(Value::Array(vec![text("leaf"), text("leaf")]))
+        );
+        assert_eq!(
+            unpack(&reference(0), &table),
+            Err("unpacked item too large".to_string())
+        );
+    }
+
+    #[test]
+    fn unpack_loop() {
+        let table = vec![Value::Array(vec![Value::Simple(0)])];
+        assert!(unpack(&Value::Simple(0), &table).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Names for the elements of arrays and the entries of maps in well-known structures.
+//!
+//! A parent item hands the schema of each child to it through the context. Arrays and maps
+//! look up the names and schemas of their children in it, byte strings use it to tell
+//! embedded items from binary data and to split binary data into fields, and tags without a
+//! structure of their own pass it on to their payload.
+
+use crate::cbor_object::{CborObject, ToTree};
+use crate::context::Context;
+use crate::time;
+use crate::tree::Node;
+use crate::value::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    Int(i64),
+    Text(&'static str),
+}
+
+impl Label {
+    fn matches(&self, key: &CborObject) -> bool {
+        match (self, key) {
+            (Label::Int(label), _) => key.as_integer() == Some(i128::from(*label)),
+            (Label::Text(label), CborObject::TextString(text)) => text.value() == label.as_bytes(),
+            (Label::Text(_), _) => false,
+        }
+    }
+}
+
+/// An element of an array, named by its position.
+#[derive(Debug)]
+pub struct Field {
+    pub name: &'static str,
+    pub schema: Schema,
+}
+
+/// An entry of a map, named by its key.
+#[derive(Debug)]
+pub struct Entry {
+    pub label: Label,
+    pub name: &'static str,
+    pub schema: Schema,
+}
+
+impl Entry {
+    /// An entry with an integer key.
+    pub const fn int(label: i64, name: &'static str, schema: Schema) -> Entry {
+        Entry {
+            label: Label::Int(label),
+            name,
+            schema,
+        }
+    }
+
+    /// An entry with a text key.
+    pub const fn text(label: &'static str, name: &'static str, schema: Schema) -> Entry {
+        Entry {
+            label: Label::Text(label),
+            name,
+            schema,
+        }
+    }
+}
+
+/// Splits binary data with a layout of its own into annotated fields, or explains why it
+/// does not fit the layout.
+pub type Layout = fn(&[u8], &mut Context) -> Result<Vec<Node>, String>;
+
+/// Chooses the schema of an item from its content and describes the item.
+pub type Select = fn(&CborObject, &Context) -> (Schema, Option<String>);
+
+#[derive(Debug, Default, Clone, Copy)]
+pub enum Schema {
+    #[default]
+    Any,
+    /// Binary data, never shown as an embedded item.
+    Opaque,
+    /// A byte string holding an embedded item.
+    Embedded(&'static Schema),
+    /// A byte string holding binary data with the given layout.
+    Layout(Layout),
+    /// An integer with named values.
+    Values(&'static [(i64, &'static str)]),
+    /// A NumericDate (RFC 8392 Section 2): seconds since 1970-01-01T00:00:00Z.
+    Time,
+    Array(&'static [Field]),
+    ArrayOf(&'static Schema),
+    Map(&'static [Entry]),
+    /// A map whose keys are data rather than names, with values of one structure.
+    MapOf(&'static Schema),
+    /// An array of labels each followed by its value, like the entries of a map.
+    Pairs(&'static [Entry]),
+    /// A structure that depends on the content of the item.
+    Select(Select),
+}
+
+impl Schema {
+    /// Returns the name and schema of the element at `index` of an array.
+    pub fn element(&self, index: usize) -> (Option<&'static str>, Schema) {
+        match self {
+            Schema::Array(fields) => match fields.get(index) {
+                Some(field) => (Some(field.name), field.schema),
+                None => (None, Schema::Any),
+            },
+            Schema::ArrayOf(schema) => (None, **schema),
+            _ => (None, Schema::Any),
+        }
+    }
+
+    /// Returns the name and the value schema of the entry with the key `key` of a map, or of
+    /// the label `key` of pairs.
+    pub fn entry(&self, key: &CborObject) -> (Option<&'static str>, Schema) {
+        match self {
+            Schema::Map(entries) | Schema::Pairs(entries) => {
+                match entries.iter().find(|entry| entry.label.matches(key)) {
+                    Some(entry) => (Some(entry.name), entry.schema),
+                    None => (None, Schema::Any),
+                }
+            }
+            Schema::MapOf(schema) => (None, **schema),
+            _ => (None, Schema::Any),
+        }
+    }
+
+    fn value_annotation(&self, object: &CborObject) -> Option<String> {
+        match self {
+            Schema::Values(values) => {
+                let value = object.as_integer()?;
+                values
+                    .iter()
+                    .find(|(id, _)| i128::from(*id) == value)
+                    .map(|(_, name)| name.to_string())
+            }
+            Schema::Time => {
+                let value = Value::from_object(object).ok()?;
+                Some(match time::numeric_date(&value) {
+                    Some(seconds) => time::format_numeric_date(seconds),
+                    None => "invalid NumericDate".to_string(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Builds an item with the schema `schema`, annotating it with its description if the
+/// schema depends on its content.
+pub fn into_tree_with_schema(object: CborObject, schema: Schema, context: &mut Context) -> Node {
+    let Schema::Select(select) = schema else {
+        return context.with_schema(schema, |context| object.into_tree_with_context(context));
+    };
+    let (schema, description) = select(&object, context);
+    // The chosen schema may depend on the content in turn.
+    let node = into_tree_with_schema(object, schema, context);
+    match description {
+        Some(description) => node.with_annotation(description),
+        None => node,
+    }
+}
+
+/// Builds a child item with the schema its parent expects for it, naming the child and, for
+/// integers with named values and dates, its value.
+pub fn child_into_tree(
+    child: CborObject,
+    name: Option<&str>,
+    schema: Schema,
+    context: &mut Context,
+) -> Node {
+    let value_annotation = schema.value_annotation(&child);
+    let mut node = into_tree_with_schema(child, schema, context);
+    if let Some(name) = name {
+        node = node.with_annotation(name);
+    }
+    if let Some(value_annotation) = value_annotation {
+        node = node.with_annotation(value_annotation);
+    }
+    node
+}
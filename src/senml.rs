@@ -0,0 +1,350 @@
+//! Sensor Measurement Lists (SenML, RFC 8428): the labels of SenML CBOR records and the
+//! resolution of their base values into measurements.
+
+use std::fmt::Write as _;
+
+use crate::diagnostic::bytes_diagnostic;
+use crate::schema::{Entry, Schema};
+use crate::time;
+use crate::value::Value;
+
+pub const CONTENT_FORMAT_SENML: u64 = 112;
+pub const CONTENT_FORMAT_SENSML: u64 = 113;
+
+const LABEL_BASE_VERSION: i64 = -1;
+const LABEL_BASE_NAME: i64 = -2;
+const LABEL_BASE_TIME: i64 = -3;
+const LABEL_BASE_UNIT: i64 = -4;
+const LABEL_BASE_VALUE: i64 = -5;
+const LABEL_BASE_SUM: i64 = -6;
+const LABEL_NAME: i64 = 0;
+const LABEL_UNIT: i64 = 1;
+const LABEL_VALUE: i64 = 2;
+const LABEL_STRING_VALUE: i64 = 3;
+const LABEL_BOOLEAN_VALUE: i64 = 4;
+const LABEL_SUM: i64 = 5;
+const LABEL_TIME: i64 = 6;
+const LABEL_UPDATE_TIME: i64 = 7;
+const LABEL_DATA_VALUE: i64 = 8;
+
+/// Version of SenML RFC 8428 describes.
+const VERSION: u64 = 10;
+
+/// Times below 2**28 are relative to the current time (RFC 8428 Section 4.5.3).
+const RELATIVE_TIME_LIMIT: f64 = 268435456.0;
+
+/// SenML labels of RFC 8428 Table 6.
+const RECORD: Schema = Schema::Map(&[
+    Entry::int(LABEL_BASE_VERSION, "bver", Schema::Any),
+    Entry::int(LABEL_BASE_NAME, "bn", Schema::Any),
+    Entry::int(LABEL_BASE_TIME, "bt", Schema::Any),
+    Entry::int(LABEL_BASE_UNIT, "bu", Schema::Any),
+    Entry::int(LABEL_BASE_VALUE, "bv", Schema::Any),
+    Entry::int(LABEL_BASE_SUM, "bs", Schema::Any),
+    Entry::int(LABEL_NAME, "n", Schema::Any),
+    Entry::int(LABEL_UNIT, "u", Schema::Any),
+    Entry::int(LABEL_VALUE, "v", Schema::Any),
+    Entry::int(LABEL_STRING_VALUE, "vs", Schema::Any),
+    Entry::int(LABEL_BOOLEAN_VALUE, "vb", Schema::Any),
+    Entry::int(LABEL_SUM, "s", Schema::Any),
+    Entry::int(LABEL_TIME, "t", Schema::Any),
+    Entry::int(LABEL_UPDATE_TIME, "ut", Schema::Any),
+    Entry::int(LABEL_DATA_VALUE, "vd", Schema::Opaque),
+]);
+
+/// A SenML pack: an array of records.
+pub const PACK: Schema = Schema::ArrayOf(&RECORD);
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Float(x) => Some(*x),
+        _ => value.as_integer().map(|x| x as f64),
+    }
+}
+
+fn format_number(x: f64) -> String {
+    if x.fract() == 0.0 && x.abs() < 1e15 {
+        format!("{}", x as i64)
+    } else {
+        format!("{}", x)
+    }
+}
+
+/// A measurement with the base values of its pack applied (RFC 8428 Section 4.6).
+#[derive(Debug, PartialEq)]
+struct Measurement {
+    name: String,
+    time: String,
+    unit: String,
+    value: String,
+}
+
+/// Base values in effect, carried from record to record.
+#[derive(Default)]
+struct BaseValues {
+    name: String,
+    time: f64,
+    unit: Option<String>,
+    value: f64,
+    sum: f64,
+}
+
+fn text_field<'a>(record: &'a Value, label: i64, name: &str) -> Result<Option<&'a str>, String> {
+    match record.get(label) {
+        Some(value) => value
+            .as_text()
+            .map(Some)
+            .ok_or_else(|| format!("{} is not a text string", name)),
+        None => Ok(None),
+    }
+}
+
+fn number_field(record: &Value, label: i64, name: &str) -> Result<Option<f64>, String> {
+    match record.get(label) {
+        Some(value) => number(value)
+            .map(Some)
+            .ok_or_else(|| format!("{} is not a number", name)),
+        None => Ok(None),
+    }
+}
+
+/// Tells whether a name uses only the characters of RFC 8428 Section 4.5.1.
+fn is_valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-:./_".contains(c))
+}
+
+fn format_time(time: f64, now: Option<i64>) -> String {
+    if time >= RELATIVE_TIME_LIMIT {
+        return time::format_numeric_date(time);
+    }
+    match now {
+        Some(now) => time::format_numeric_date(now as f64 + time),
+        None => format!("now{:+} s", time),
+    }
+}
+
+fn resolve(pack: &Value, now: Option<i64>) -> Result<Vec<Measurement>, String> {
+    let Value::Array(records) = pack else {
+        return Err("expected an array of records".to_string());
+    };
+    let mut base = BaseValues::default();
+    let mut measurements = vec![];
+    for (index, record) in records.iter().enumerate() {
+        let record_error = |err: String| format!("record {}: {}", index, err);
+        if !matches!(record, Value::Map(_)) {
+            return Err(record_error("expected a map".to_string()));
+        }
+        if let Some(version) = record.get(LABEL_BASE_VERSION) {
+            match version {
+                Value::Unsigned(version) if *version <= VERSION => {}
+                _ => return Err(record_error("unsupported version".to_string())),
+            }
+        }
+        if let Some(name) = text_field(record, LABEL_BASE_NAME, "bn").map_err(record_error)? {
+            base.name = name.to_string();
+        }
+        if let Some(time) = number_field(record, LABEL_BASE_TIME, "bt").map_err(record_error)? {
+            base.time = time;
+        }
+        if let Some(unit) = text_field(record, LABEL_BASE_UNIT, "bu").map_err(record_error)? {
+            base.unit = Some(unit.to_string());
+        }
+        if let Some(value) = number_field(record, LABEL_BASE_VALUE, "bv").map_err(record_error)? {
+            base.value = value;
+        }
+        if let Some(sum) = number_field(record, LABEL_BASE_SUM, "bs").map_err(record_error)? {
+            base.sum = sum;
+        }
+
+        let value =
+            if let Some(value) = number_field(record, LABEL_VALUE, "v").map_err(record_error)? {
+                Some(format_number(base.value + value))
+            } else if let Some(value) =
+                text_field(record, LABEL_STRING_VALUE, "vs").map_err(record_error)?
+            {
+                Some(format!("{:?}", value))
+            } else if let Some(value) = record.get(LABEL_BOOLEAN_VALUE) {
+                match value {
+                    Value::Simple(20) => Some("false".to_string()),
+                    Value::Simple(21) => Some("true".to_string()),
+                    _ => return Err(record_error("vb is not a boolean".to_string())),
+                }
+            } else if let Some(value) = record.get(LABEL_DATA_VALUE) {
+                let data = value
+                    .as_bytes()
+                    .ok_or_else(|| record_error("vd is not a byte string".to_string()))?;
+                Some(bytes_diagnostic(data))
+            } else {
+                None
+            };
+        let sum = number_field(record, LABEL_SUM, "s")
+            .map_err(record_error)?
+            .map(|sum| format!("sum {}", format_number(base.sum + sum)));
+        // Records with neither a value nor a sum only set base values.
+        let value = match (value, sum) {
+            (Some(value), Some(sum)) => format!("{}, {}", value, sum),
+            (Some(value), None) | (None, Some(value)) => value,
+            (None, None) => continue,
+        };
+
+        let name = format!(
+            "{}{}",
+            base.name,
+            text_field(record, LABEL_NAME, "n")
+                .map_err(record_error)?
+                .unwrap_or_default()
+        );
+        if !is_valid_name(&name) {
+            return Err(record_error(format!("invalid name {:?}", name)));
+        }
+        let time = number_field(record, LABEL_TIME, "t").map_err(record_error)?;
+        let unit = text_field(record, LABEL_UNIT, "u").map_err(record_error)?;
+        measurements.push(Measurement {
+            name,
+            time: format_time(base.time + time.unwrap_or_default(), now),
+            unit: unit
+                .map(str::to_string)
+                .or(base.unit.clone())
+                .unwrap_or_default(),
+            value,
+        });
+    }
+    Ok(measurements)
+}
+
+/// Writes the measurements of a pack as a table with a line per measurement, giving relative
+/// times from `now` if it is known.
+pub fn resolved_table(pack: &Value, now: Option<i64>) -> String {
+    let measurements = match resolve(pack, now) {
+        Ok(measurements) => measurements,
+        Err(err) => return format!("invalid senml pack: {}\n", err),
+    };
+    let widths = [
+        measurements.iter().map(|m| m.name.len()).max().unwrap_or(0),
+        measurements.iter().map(|m| m.time.len()).max().unwrap_or(0),
+        measurements.iter().map(|m| m.unit.len()).max().unwrap_or(0),
+    ];
+    measurements.iter().fold(String::new(), |mut output, m| {
+        // Columns that are empty in every line are left out.
+        for (column, width) in [&m.name, &m.time, &m.unit].into_iter().zip(widths) {
+            if width > 0 {
+                write!(output, "{:width$}  ", column).unwrap();
+            }
+        }
+        writeln!(output, "{}", m.value).unwrap();
+        output
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(entries: Vec<(i64, Value)>) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(label, value)| {
+                    let label = match u64::try_from(label) {
+                        Ok(label) => Value::Unsigned(label),
+                        Err(_) => Value::Negative((-1 - label) as u64),
+                    };
+                    (label, value)
+                })
+                .collect(),
+        )
+    }
+
+    fn text(text: &str) -> Value {
+        Value::Text(text.to_string())
+    }
+
+    #[test]
+    fn resolve_base_values() {
+        // RFC 8428 Section 5.1.2, with the base value of Section 5.1.4
+        let pack = Value::Array(vec![
+            record(vec![
+                (LABEL_BASE_NAME, text("urn:dev:ow:10e2073a01080063:")),
+                (LABEL_BASE_TIME, Value::Float(1.276020076001e9)),
+                (LABEL_BASE_UNIT, text("A")),
+                (LABEL_BASE_VERSION, Value::Unsigned(5)),
+                (LABEL_NAME, text("voltage")),
+                (LABEL_UNIT, text("V")),
+                (LABEL_VALUE, Value::Float(120.1)),
+            ]),
+            record(vec![
+                (LABEL_NAME, text("current")),
+                (LABEL_TIME, Value::Negative(4)),
+                (LABEL_VALUE, Value::Float(1.2)),
+            ]),
+            record(vec![(LABEL_BASE_VALUE, Value::Unsigned(10))]),
+            record(vec![
+                (LABEL_NAME, text("current")),
+                (LABEL_VALUE, Value::Unsigned(3)),
+                (LABEL_SUM, Value::Unsigned(7)),
+            ]),
+        ]);
+        let measurements = resolve(&pack, None).unwrap();
+        assert_eq!(
+            measurements,
+            vec![
+                Measurement {
+                    name: "urn:dev:ow:10e2073a01080063:voltage".to_string(),
+                    time: "2010-06-08T18:01:16.001Z".to_string(),
+                    unit: "V".to_string(),
+                    value: "120.1".to_string(),
+                },
+                Measurement {
+                    name: "urn:dev:ow:10e2073a01080063:current".to_string(),
+                    time: "2010-06-08T18:01:11.001Z".to_string(),
+                    unit: "A".to_string(),
+                    value: "1.2".to_string(),
+                },
+                Measurement {
+                    name: "urn:dev:ow:10e2073a01080063:current".to_string(),
+                    time: "2010-06-08T18:01:16.001Z".to_string(),
+                    unit: "A".to_string(),
+                    value: "13, sum 7".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn relative_times() {
+        let pack = Value::Array(vec![record(vec![
+            (LABEL_NAME, text("temperature")),
+            (LABEL_TIME, Value::Negative(59)),
+            (LABEL_BOOLEAN_VALUE, Value::Simple(21)),
+        ])]);
+        assert_eq!(resolved_table(&pack, None), "temperature  now-60 s  true\n");
+        assert_eq!(
+            resolved_table(&pack, Some(60)),
+            "temperature  1970-01-01T00:00:00Z  true\n"
+        );
+    }
+
+    #[test]
+    fn invalid_packs() {
+        assert_eq!(
+            resolved_table(&Value::Map(vec![]), None),
+            "invalid senml pack: expected an array of records\n"
+        );
+        let pack = Value::Array(vec![record(vec![(LABEL_VALUE, Value::Unsigned(1))])]);
+        assert_eq!(
+            resolved_table(&pack, None),
+            "invalid senml pack: record 0: invalid name \"\"\n"
+        );
+        let pack = Value::Array(vec![record(vec![(
+            LABEL_BASE_VERSION,
+            Value::Unsigned(11),
+        )])]);
+        assert_eq!(
+            resolved_table(&pack, None),
+            "invalid senml pack: record 0: unsupported version\n"
+        );
+    }
+}
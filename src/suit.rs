@@ -0,0 +1,214 @@
+//! Software Updates for Internet of Things manifests (draft-ietf-suit-manifest, following the
+//! information model of RFC 9124): the envelope, its authentication wrapper, the manifest and
+//! its command sequences.
+
+use crate::cbor_object::CborObject;
+use crate::context::Context;
+use crate::cose::ALGORITHMS;
+use crate::schema::{Entry, Field, Schema};
+use crate::tag_identifier;
+use crate::value::Value;
+
+pub const TAG_ENVELOPE: u64 = 107;
+pub const TAG_MANIFEST: u64 = 1070;
+
+/// Shows the bytes of a digest in hex.
+fn select_digest_bytes(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    let description = object.as_bytes().map(|bytes| crate::format_hex(&bytes));
+    (Schema::Opaque, description)
+}
+
+/// SUIT_Digest: a COSE hash algorithm and a digest.
+const DIGEST: Schema = Schema::Array(&[
+    Field {
+        name: "algorithm",
+        schema: Schema::Values(ALGORITHMS),
+    },
+    Field {
+        name: "digest",
+        schema: Schema::Select(select_digest_bytes),
+    },
+]);
+
+/// Describes a byte string holding a UUID, as vendor, class and device identifiers do.
+fn select_uuid(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    let uuid = object
+        .as_bytes()
+        .and_then(|bytes| <[u8; 16]>::try_from(bytes.as_slice()).ok());
+    let description = match uuid {
+        Some(uuid) => tag_identifier::format_uuid(&uuid),
+        None => "invalid uuid: expected 16 bytes".to_string(),
+    };
+    (Schema::Opaque, Some(description))
+}
+
+const UUID: Schema = Schema::Select(select_uuid);
+
+/// Shows a component identifier as a path, with its printable segments as text.
+fn select_component_identifier(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    let CborObject::Array(array) = object else {
+        return (
+            Schema::Any,
+            Some("invalid component identifier: expected an array".to_string()),
+        );
+    };
+    let mut path = String::new();
+    for segment in array.value() {
+        let Some(bytes) = segment.as_bytes() else {
+            return (
+                Schema::Any,
+                Some("invalid component identifier: expected bstr segments".to_string()),
+            );
+        };
+        path.push('/');
+        match std::str::from_utf8(&bytes) {
+            Ok(text) if !text.is_empty() && text.chars().all(|c| c.is_ascii_graphic()) => {
+                path.push_str(text)
+            }
+            _ => path.push_str(&crate::format_hex(&bytes)),
+        }
+    }
+    (
+        Schema::ArrayOf(&Schema::Opaque),
+        Some(format!("component {}", path)),
+    )
+}
+
+const COMPONENT_IDENTIFIER: Schema = Schema::Select(select_component_identifier);
+
+/// SUIT_Parameters.
+const PARAMETERS: Schema = Schema::Map(&[
+    Entry::int(1, "vendor-identifier", UUID),
+    Entry::int(2, "class-identifier", UUID),
+    Entry::int(3, "image-digest", Schema::Embedded(&DIGEST)),
+    Entry::int(5, "component-slot", Schema::Any),
+    Entry::int(12, "strict-order", Schema::Any),
+    Entry::int(13, "soft-failure", Schema::Any),
+    Entry::int(14, "image-size", Schema::Any),
+    Entry::int(18, "content", Schema::Opaque),
+    Entry::int(21, "uri", Schema::Any),
+    Entry::int(22, "source-component", Schema::Any),
+    Entry::int(23, "invoke-args", Schema::Opaque),
+    Entry::int(24, "device-identifier", UUID),
+]);
+
+/// Conditions and directives, with the reporting policy or the argument that follows them.
+static COMMAND_SEQUENCE: Schema = Schema::Pairs(&[
+    Entry::int(1, "condition-vendor-identifier", Schema::Any),
+    Entry::int(2, "condition-class-identifier", Schema::Any),
+    Entry::int(3, "condition-image-match", Schema::Any),
+    Entry::int(5, "condition-component-slot", Schema::Any),
+    Entry::int(6, "condition-check-content", Schema::Any),
+    Entry::int(12, "directive-set-component-index", Schema::Any),
+    Entry::int(14, "condition-abort", Schema::Any),
+    Entry::int(
+        15,
+        "directive-try-each",
+        Schema::ArrayOf(&Schema::Embedded(&COMMAND_SEQUENCE)),
+    ),
+    Entry::int(18, "directive-write", Schema::Any),
+    Entry::int(19, "directive-set-parameters", PARAMETERS),
+    Entry::int(20, "directive-override-parameters", PARAMETERS),
+    Entry::int(21, "directive-fetch", Schema::Any),
+    Entry::int(22, "directive-copy", Schema::Any),
+    Entry::int(23, "directive-invoke", Schema::Any),
+    Entry::int(24, "condition-device-identifier", Schema::Any),
+    Entry::int(31, "directive-swap", Schema::Any),
+    Entry::int(
+        32,
+        "directive-run-sequence",
+        Schema::Embedded(&COMMAND_SEQUENCE),
+    ),
+]);
+
+static COMMON: Schema = Schema::Map(&[
+    Entry::int(1, "dependencies", Schema::Any),
+    Entry::int(2, "components", Schema::ArrayOf(&COMPONENT_IDENTIFIER)),
+    Entry::int(4, "shared-sequence", Schema::Embedded(&COMMAND_SEQUENCE)),
+]);
+
+/// A severable command sequence, or the SUIT_Digest of the sequence severed from the manifest.
+fn select_severable_sequence(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    match object {
+        CborObject::Array(_) => (DIGEST, Some("severed".to_string())),
+        _ => (Schema::Embedded(&COMMAND_SEQUENCE), None),
+    }
+}
+
+/// A severable text map, or the SUIT_Digest of the map severed from the manifest.
+fn select_severable_text(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    match object {
+        CborObject::Array(_) => (DIGEST, Some("severed".to_string())),
+        _ => (Schema::Any, None),
+    }
+}
+
+const SEVERABLE_SEQUENCE: Schema = Schema::Select(select_severable_sequence);
+const SEVERABLE_TEXT: Schema = Schema::Select(select_severable_text);
+
+/// SUIT_Manifest. A severed member is a SUIT_Digest instead of a byte string.
+static MANIFEST: Schema = Schema::Map(&[
+    Entry::int(1, "manifest-version", Schema::Any),
+    Entry::int(2, "manifest-sequence-number", Schema::Any),
+    Entry::int(3, "common", Schema::Embedded(&COMMON)),
+    Entry::int(4, "reference-uri", Schema::Any),
+    Entry::int(5, "manifest-component-id", COMPONENT_IDENTIFIER),
+    Entry::int(7, "validate", Schema::Embedded(&COMMAND_SEQUENCE)),
+    Entry::int(8, "load", Schema::Embedded(&COMMAND_SEQUENCE)),
+    Entry::int(9, "invoke", Schema::Embedded(&COMMAND_SEQUENCE)),
+    Entry::int(16, "payload-fetch", SEVERABLE_SEQUENCE),
+    Entry::int(17, "install", SEVERABLE_SEQUENCE),
+    Entry::int(23, "text", SEVERABLE_TEXT),
+]);
+
+/// SUIT_Authentication: the digest of the manifest, then the COSE_Sign1 or COSE_Mac0
+/// messages that authenticate it.
+const AUTHENTICATION: Schema = Schema::Array(&[
+    Field {
+        name: "manifest digest",
+        schema: Schema::Embedded(&DIGEST),
+    },
+    Field {
+        name: "authentication block",
+        schema: Schema::Any,
+    },
+]);
+
+/// SUIT_Envelope, with the members severed from the manifest.
+pub static ENVELOPE: Schema = Schema::Map(&[
+    Entry::int(1, "delegation", Schema::Any),
+    Entry::int(
+        2,
+        "authentication-wrapper",
+        Schema::Embedded(&AUTHENTICATION),
+    ),
+    Entry::int(3, "manifest", Schema::Embedded(&MANIFEST)),
+    Entry::int(16, "payload-fetch", Schema::Embedded(&COMMAND_SEQUENCE)),
+    Entry::int(17, "install", Schema::Embedded(&COMMAND_SEQUENCE)),
+    Entry::int(23, "text", Schema::Any),
+]);
+
+/// Tells a manifest, which has its version under key 1, from an envelope, which may have
+/// delegation chains there.
+fn select_suit(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    match Value::from_object(object) {
+        Ok(value @ Value::Map(_)) if matches!(value.get(1), Some(Value::Unsigned(_))) => {
+            (MANIFEST, None)
+        }
+        Ok(Value::Map(_)) => (ENVELOPE, None),
+        // A tagged envelope or manifest has its structure from the tag.
+        _ => (Schema::Any, None),
+    }
+}
+
+/// A SUIT envelope or manifest, tagged or not.
+pub const SUIT: Schema = Schema::Select(select_suit);
+
+/// Returns the name of a SUIT tag and the structure of its content.
+pub fn tag_schema(tag: u64) -> Option<(&'static str, Schema)> {
+    match tag {
+        TAG_ENVELOPE => Some(("suit envelope", ENVELOPE)),
+        TAG_MANIFEST => Some(("suit manifest", MANIFEST)),
+        _ => None,
+    }
+}
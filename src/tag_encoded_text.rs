@@ -0,0 +1,282 @@
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine as _;
+
+use crate::cbor_object::CborObject;
+use crate::format_hex;
+
+pub const TAG_URI: u64 = 32;
+pub const TAG_BASE64URL: u64 = 33;
+pub const TAG_BASE64: u64 = 34;
+pub const TAG_REGEXP: u64 = 35;
+pub const TAG_MIME_MESSAGE: u64 = 36;
+
+pub fn tag_name(tag: u64) -> Option<&'static str> {
+    match tag {
+        TAG_URI => Some("uri"),
+        TAG_BASE64URL => Some("base64url text"),
+        TAG_BASE64 => Some("base64 text"),
+        TAG_REGEXP => Some("regexp"),
+        TAG_MIME_MESSAGE => Some("mime message"),
+        _ => None,
+    }
+}
+
+/// Validates the text string content of tags 32 to 36 (RFC 8949 Section 3.4.5.3) and
+/// returns a short description of what it contains.
+pub fn describe(tag: u64, payload: &CborObject) -> Result<String, String> {
+    match payload.as_text() {
+        Some(text) => describe_text(tag, &text),
+        None => Err("expected tstr".to_string()),
+    }
+}
+
+fn describe_text(tag: u64, text: &str) -> Result<String, String> {
+    match tag {
+        TAG_URI => describe_uri(text),
+        TAG_BASE64URL => describe_base64(URL_SAFE_NO_PAD.decode(text)),
+        TAG_BASE64 => describe_base64(STANDARD.decode(text)),
+        TAG_REGEXP => describe_regexp(text),
+        TAG_MIME_MESSAGE => describe_mime_message(text),
+        _ => Err(format!("unknown tag {}", tag)),
+    }
+}
+
+fn describe_base64(decoded: Result<Vec<u8>, base64::DecodeError>) -> Result<String, String> {
+    match decoded {
+        Ok(bytes) => Ok(format!("decoded h'{}'", format_hex(&bytes))),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn describe_regexp(text: &str) -> Result<String, String> {
+    match regex_syntax::ast::parse::Parser::new().parse(text) {
+        Ok(_) => Ok("valid regular expression".to_string()),
+        Err(err) => Err(err.kind().to_string()),
+    }
+}
+
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~".contains(c)
+}
+
+fn is_sub_delim(c: char) -> bool {
+    "!$&'()*+,;=".contains(c)
+}
+
+/// Checks the characters of a URI component, allowing percent-encoded octets and `extra`.
+fn check_uri_chars(component: &str, name: &str, extra: &str) -> Result<(), String> {
+    let mut chars = component.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            let encoded: String = chars.by_ref().take(2).collect();
+            if encoded.len() != 2 || !encoded.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("invalid percent-encoding in {}", name));
+            }
+        } else if !(is_unreserved(c) || is_sub_delim(c) || extra.contains(c)) {
+            return Err(format!("invalid character {:?} in {}", c, name));
+        }
+    }
+    Ok(())
+}
+
+/// Validates a URI-reference (RFC 3986 Section 4.1) and lists its components.
+fn describe_uri(text: &str) -> Result<String, String> {
+    let mut components = Vec::new();
+
+    let (rest, fragment) = match text.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (text, None),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+
+    let mut rest = rest;
+    if let Some(colon) = rest.find(':') {
+        if !rest[..colon].contains('/') {
+            let scheme = &rest[..colon];
+            let mut chars = scheme.chars();
+            if !chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                || !chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+            {
+                return Err(format!("invalid scheme {:?}", scheme));
+            }
+            components.push(format!("scheme {:?}", scheme));
+            rest = &rest[colon + 1..];
+        }
+    }
+
+    if let Some(after_slashes) = rest.strip_prefix("//") {
+        let end = after_slashes.find('/').unwrap_or(after_slashes.len());
+        let authority = &after_slashes[..end];
+        rest = &after_slashes[end..];
+
+        let host_port = match authority.rsplit_once('@') {
+            Some((userinfo, host_port)) => {
+                check_uri_chars(userinfo, "userinfo", ":")?;
+                host_port
+            }
+            None => authority,
+        };
+        let (host, port) = match host_port.strip_prefix('[') {
+            Some(ip_literal) => {
+                let Some((ip_literal, port)) = ip_literal.split_once(']') else {
+                    return Err("unterminated IP literal".to_string());
+                };
+                if !ip_literal
+                    .chars()
+                    .all(|c| is_unreserved(c) || is_sub_delim(c) || c == ':')
+                {
+                    return Err(format!("invalid IP literal {:?}", ip_literal));
+                }
+                let port = match port {
+                    "" => None,
+                    _ => match port.strip_prefix(':') {
+                        Some(port) => Some(port),
+                        None => return Err("invalid characters after IP literal".to_string()),
+                    },
+                };
+                (&host_port[..ip_literal.len() + 2], port)
+            }
+            None => {
+                let (host, port) = match host_port.split_once(':') {
+                    Some((host, port)) => (host, Some(port)),
+                    None => (host_port, None),
+                };
+                check_uri_chars(host, "host", "")?;
+                (host, port)
+            }
+        };
+        components.push(format!("host {:?}", host));
+        if let Some(port) = port {
+            if !port.chars().all(|c| c.is_ascii_digit()) {
+                return Err(format!("invalid port {:?}", port));
+            }
+            if !port.is_empty() {
+                components.push(format!("port {}", port));
+            }
+        }
+    }
+
+    check_uri_chars(rest, "path", ":@/")?;
+    if !rest.is_empty() {
+        components.push(format!("path {:?}", rest));
+    }
+    if let Some(query) = query {
+        check_uri_chars(query, "query", ":@/?")?;
+        components.push(format!("query {:?}", query));
+    }
+    if let Some(fragment) = fragment {
+        check_uri_chars(fragment, "fragment", ":@/?")?;
+        components.push(format!("fragment {:?}", fragment));
+    }
+
+    if components.is_empty() {
+        Ok("empty reference".to_string())
+    } else {
+        Ok(components.join(", "))
+    }
+}
+
+/// Validates the header section of a MIME message (RFC 2045) and summarizes it.
+fn describe_mime_message(text: &str) -> Result<String, String> {
+    let mut header_fields = 0usize;
+    let mut content_type = None;
+    let mut body = None;
+
+    let mut offset = 0usize;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        offset += line.len();
+        let line = line.trim_end_matches('\n').trim_end_matches('\r');
+        if line.is_empty() {
+            body = Some(&text[offset..]);
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if header_fields == 0 {
+                return Err(format!(
+                    "line {} continues a missing header field",
+                    index + 1
+                ));
+            }
+            continue;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(format!("line {} is not a header field", index + 1));
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_graphic()) {
+            return Err(format!("invalid header field name {:?}", name));
+        }
+        header_fields += 1;
+        if name.eq_ignore_ascii_case("content-type") {
+            content_type = Some(value.trim());
+        }
+    }
+
+    let mut description = format!("{} header fields", header_fields);
+    if let Some(content_type) = content_type {
+        description.push_str(&format!(", content-type {:?}", content_type));
+    }
+    match body {
+        Some(body) => description.push_str(&format!(", body {} bytes", body.len())),
+        None => description.push_str(", no body"),
+    }
+    Ok(description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uri() {
+        assert_eq!(
+            describe_text(TAG_URI, "http://www.example.com:8080/a?b#c"),
+            Ok(
+                "scheme \"http\", host \"www.example.com\", port 8080, path \"/a\", query \"b\", fragment \"c\""
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            describe_text(TAG_URI, "../a%20b"),
+            Ok("path \"../a%20b\"".to_string())
+        );
+        assert!(describe_text(TAG_URI, "http://exa mple.com/").is_err());
+        assert!(describe_text(TAG_URI, "1http://example.com/").is_err());
+        assert!(describe_text(TAG_URI, "/a%2").is_err());
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(
+            describe_text(TAG_BASE64URL, "AQL_"),
+            Ok("decoded h'0102ff'".to_string())
+        );
+        assert!(describe_text(TAG_BASE64URL, "AQI=").is_err());
+        assert_eq!(
+            describe_text(TAG_BASE64, "AQI="),
+            Ok("decoded h'0102'".to_string())
+        );
+        assert!(describe_text(TAG_BASE64, "AQI").is_err());
+        assert!(describe_text(TAG_BASE64, "AQL_").is_err());
+    }
+
+    #[test]
+    fn regexp() {
+        assert!(describe_text(TAG_REGEXP, "^a+[0-9]$").is_ok());
+        assert!(describe_text(TAG_REGEXP, "a(b").is_err());
+    }
+
+    #[test]
+    fn mime_message() {
+        assert_eq!(
+            describe_text(
+                TAG_MIME_MESSAGE,
+                "MIME-Version: 1.0\r\nContent-Type: text/plain\r\n\r\nhello"
+            ),
+            Ok("2 header fields, content-type \"text/plain\", body 5 bytes".to_string())
+        );
+        assert!(describe_text(TAG_MIME_MESSAGE, "not a header\r\n\r\n").is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::cbor_object::CborObject;
+
+pub const TAG_UUID: u64 = 37;
+pub const TAG_IPV4: u64 = 52;
+pub const TAG_IPV6: u64 = 54;
+pub const TAG_RELATIVE_OID: u64 = 110;
+pub const TAG_OID: u64 = 111;
+pub const TAG_PEN_RELATIVE_OID: u64 = 112;
+
+/// The arc that tag 112 object identifiers are relative to (IANA Private Enterprise Numbers).
+const PEN_ARC: &str = "1.3.6.1.4.1";
+
+const WELL_KNOWN_OIDS: &[(&str, &str)] = &[
+    ("1.2.840.10045.2.1", "ecPublicKey"),
+    ("1.2.840.10045.3.1.7", "prime256v1"),
+    ("1.2.840.10045.4.3.2", "ecdsa-with-SHA256"),
+    ("1.2.840.10045.4.3.3", "ecdsa-with-SHA384"),
+    ("1.2.840.10045.4.3.4", "ecdsa-with-SHA512"),
+    ("1.2.840.113549.1.1.1", "rsaEncryption"),
+    ("1.2.840.113549.1.1.11", "sha256WithRSAEncryption"),
+    ("1.2.840.113549.1.1.12", "sha384WithRSAEncryption"),
+    ("1.2.840.113549.1.1.13", "sha512WithRSAEncryption"),
+    ("1.2.840.113549.1.9.1", "emailAddress"),
+    ("1.3.101.110", "X25519"),
+    ("1.3.101.111", "X448"),
+    ("1.3.101.112", "Ed25519"),
+    ("1.3.101.113", "Ed448"),
+    ("1.3.132.0.34", "secp384r1"),
+    ("1.3.132.0.35", "secp521r1"),
+    ("1.3.6.1.5.5.7.1.1", "authorityInfoAccess"),
+    ("1.3.6.1.5.5.7.3.1", "serverAuth"),
+    ("1.3.6.1.5.5.7.3.2", "clientAuth"),
+    ("1.3.6.1.5.5.7.3.3", "codeSigning"),
+    ("2.16.840.1.101.3.4.2.1", "sha256"),
+    ("2.16.840.1.101.3.4.2.2", "sha384"),
+    ("2.16.840.1.101.3.4.2.3", "sha512"),
+    ("2.5.4.3", "commonName"),
+    ("2.5.4.6", "countryName"),
+    ("2.5.4.7", "localityName"),
+    ("2.5.4.8", "stateOrProvinceName"),
+    ("2.5.4.10", "organizationName"),
+    ("2.5.4.11", "organizationalUnitName"),
+    ("2.5.29.14", "subjectKeyIdentifier"),
+    ("2.5.29.15", "keyUsage"),
+    ("2.5.29.17", "subjectAltName"),
+    ("2.5.29.19", "basicConstraints"),
+    ("2.5.29.35", "authorityKeyIdentifier"),
+    ("2.5.29.37", "extKeyUsage"),
+];
+
+pub fn tag_name(tag: u64) -> Option<&'static str> {
+    match tag {
+        TAG_UUID => Some("uuid"),
+        TAG_IPV4 => Some("ipv4"),
+        TAG_IPV6 => Some("ipv6"),
+        TAG_RELATIVE_OID => Some("relative oid"),
+        TAG_OID => Some("oid"),
+        TAG_PEN_RELATIVE_OID => Some("pen relative oid"),
+        _ => None,
+    }
+}
+
+/// Validates the payload of an identifier tag and returns its canonical text form.
+pub fn describe(tag: u64, payload: &CborObject) -> Result<String, String> {
+    match tag {
+        TAG_UUID => describe_uuid(&expect_bytes(payload)?),
+        TAG_IPV4 => describe_ip::<4>(payload),
+        TAG_IPV6 => describe_ip::<16>(payload),
+        TAG_RELATIVE_OID => Ok(format!(
+            ".{}",
+            decode_oid_arcs(&expect_bytes(payload)?)?.join(".")
+        )),
+        TAG_OID => describe_oid(&expect_bytes(payload)?),
+        TAG_PEN_RELATIVE_OID => {
+            let arcs = decode_oid_arcs(&expect_bytes(payload)?)?;
+            Ok(format!("{}.{}", PEN_ARC, arcs.join(".")))
+        }
+        _ => Err(format!("unknown tag {}", tag)),
+    }
+}
+
+fn expect_bytes(payload: &CborObject) -> Result<Vec<u8>, String> {
+    payload
+        .as_bytes()
+        .ok_or_else(|| "expected bstr".to_string())
+}
+
+fn describe_uuid(bytes: &[u8]) -> Result<String, String> {
+    let Ok(bytes) = <[u8; 16]>::try_from(bytes) else {
+        return Err(format!("expected 16 bytes, got {}", bytes.len()));
+    };
+    Ok(format!(
+        "{} (version {})",
+        format_uuid(&bytes),
+        bytes[6] >> 4
+    ))
+}
+
+/// Formats a UUID in the hyphenated form of RFC 9562 Section 4.
+pub fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex = crate::format_hex(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn format_ip(bytes: &[u8]) -> String {
+    // unwrap safety: callers pass either 4 or 16 bytes
+    match bytes.len() {
+        4 => Ipv4Addr::from(<[u8; 4]>::try_from(bytes).unwrap()).to_string(),
+        _ => Ipv6Addr::from(<[u8; 16]>::try_from(bytes).unwrap()).to_string(),
+    }
+}
+
+fn expect_prefix_length<const N: usize>(object: &CborObject) -> Result<u64, String> {
+    let length = object
+        .as_unsigned()
+        .ok_or_else(|| "expected unsigned prefix length".to_string())?;
+    if length > N as u64 * 8 {
+        return Err(format!("prefix length {} is too long", length));
+    }
+    Ok(length)
+}
+
+/// Describes the address, prefix and interface forms of RFC 9164.
+fn describe_ip<const N: usize>(payload: &CborObject) -> Result<String, String> {
+    if let Some(bytes) = payload.as_bytes() {
+        let Ok(address) = <[u8; N]>::try_from(&bytes[..]) else {
+            return Err(format!("expected {} bytes, got {}", N, bytes.len()));
+        };
+        return Ok(format_ip(&address));
+    }
+
+    let Some(items) = payload.as_array() else {
+        return Err("expected bstr or array".to_string());
+    };
+    match items[..] {
+        // Prefix: [prefix-length, address with trailing zero bytes removed]
+        [length, prefix] if length.as_unsigned().is_some() => {
+            let length = expect_prefix_length::<N>(length)?;
+            let prefix = expect_bytes(prefix)?;
+            if prefix.len() as u64 > length.div_ceil(8) {
+                return Err(format!("prefix has more than {} bits", length));
+            }
+            if prefix.last() == Some(&0) {
+                return Err("prefix has trailing zero bytes".to_string());
+            }
+            let mut address = [0u8; N];
+            address[..prefix.len()].copy_from_slice(&prefix);
+            let value = address
+                .iter()
+                .fold(0u128, |acc, b| (acc << 8) | u128::from(*b));
+            let host_mask = u128::MAX
+                .checked_shr((128 - (N as u64 * 8 - length)) as u32)
+                .unwrap_or(0);
+            if value & host_mask != 0 {
+                return Err("prefix has bits set after the prefix length".to_string());
+            }
+            Ok(format!("{}/{}", format_ip(&address), length))
+        }
+        // Interface: [address, prefix-length / null, ?zone]
+        [address, length, ref zone @ ..] if zone.len() <= 1 => {
+            let address = expect_bytes(address)?;
+            let Ok(address) = <[u8; N]>::try_from(&address[..]) else {
+                return Err(format!("expected {} bytes, got {}", N, address.len()));
+            };
+            let mut text = format_ip(&address);
+            if !length.is_null() {
+                text.push_str(&format!("/{}", expect_prefix_length::<N>(length)?));
+            }
+            if let [zone] = zone {
+                let zone = match (zone.as_unsigned(), zone.as_text()) {
+                    (Some(index), _) => index.to_string(),
+                    (_, Some(name)) => name,
+                    _ => return Err("expected unsigned or tstr zone".to_string()),
+                };
+                text.push_str(&format!("%{}", zone));
+            }
+            Ok(text)
+        }
+        _ => Err("unexpected array layout".to_string()),
+    }
+}
+
+/// Decodes the BER subidentifiers of an object identifier (RFC 9090 Section 2.1).
+fn decode_oid_arcs(bytes: &[u8]) -> Result<Vec<String>, String> {
+    if bytes.is_empty() {
+        return Err("empty object identifier".to_string());
+    }
+    let mut arcs = Vec::new();
+    let mut arc = 0u128;
+    let mut first_byte = true;
+    for &b in bytes {
+        if first_byte && b == 0x80 {
+            return Err("subidentifier has a leading 0x80 byte".to_string());
+        }
+        arc = arc
+            .checked_mul(128)
+            .ok_or_else(|| "subidentifier is too large".to_string())?
+            | u128::from(b & 0x7f);
+        first_byte = b & 0x80 == 0;
+        if first_byte {
+            arcs.push(arc.to_string());
+            arc = 0;
+        }
+    }
+    if !first_byte {
+        return Err("last subidentifier is truncated".to_string());
+    }
+    Ok(arcs)
+}
+
+fn describe_oid(bytes: &[u8]) -> Result<String, String> {
+    let arcs = decode_oid_arcs(bytes)?;
+    // unwrap safety: decode_oid_arcs returns at least one arc
+    let first: u128 = arcs[0].parse().unwrap();
+    let (root, second) = match first {
+        0..40 => (0, first),
+        40..80 => (1, first - 40),
+        _ => (2, first - 80),
+    };
+    let mut oid = format!("{}.{}", root, second);
+    for arc in &arcs[1..] {
+        oid.push('.');
+        oid.push_str(arc);
+    }
+    match WELL_KNOWN_OIDS.iter().find(|(known, _)| *known == oid) {
+        Some((_, name)) => Ok(format!("{} ({})", oid, name)),
+        None => Ok(oid),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uuid() {
+        assert_eq!(
+            describe_uuid(b"\x55\x0e\x84\x00\xe2\x9b\x41\xd4\xa7\x16\x44\x66\x55\x44\x00\x00"),
+            Ok("550e8400-e29b-41d4-a716-446655440000 (version 4)".to_string())
+        );
+        assert!(describe_uuid(b"\x55\x0e").is_err());
+    }
+
+    #[test]
+    fn oid() {
+        assert_eq!(
+            describe_oid(b"\x2a\x86\x48\xce\x3d\x03\x01\x07"),
+            Ok("1.2.840.10045.3.1.7 (prime256v1)".to_string())
+        );
+        assert_eq!(describe_oid(b"\x88\x37\x03"), Ok("2.999.3".to_string()));
+        assert!(describe_oid(b"\x2a\x86").is_err());
+        assert!(describe_oid(b"\x2a\x80\x01").is_err());
+        assert!(describe_oid(b"").is_err());
+    }
+}
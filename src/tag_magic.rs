@@ -0,0 +1,134 @@
+use crate::cbor_object::CborObject;
+
+/// Encoded CBOR data item (RFC 8949 Section 3.4.5.1).
+pub const TAG_ENCODED_CBOR: u64 = 24;
+pub const TAG_SELF_DESCRIBED_CBOR: u64 = 55799;
+pub const TAG_SELF_DESCRIBED_CBOR_SEQUENCE: u64 = 55800;
+
+/// Tags 1668546560 to 1668612095 carry a CoAP Content-Format number in their lower 16 bits
+/// (RFC 9277 Section 2.2).
+const TAG_CONTENT_FORMAT_FIRST: u64 = 0x63740000;
+const TAG_CONTENT_FORMAT_LAST: u64 = 0x6374ffff;
+
+/// The byte string wrapped by the protocol tag at the start of a labeled CBOR sequence.
+const SEQUENCE_MARKER: &[u8] = b"BOR";
+
+const CONTENT_FORMATS: &[(u64, &str)] = &[
+    (0, "text/plain; charset=utf-8"),
+    (16, "application/cose; cose-type=\"cose-encrypt0\""),
+    (17, "application/cose; cose-type=\"cose-mac0\""),
+    (18, "application/cose; cose-type=\"cose-sign1\""),
+    (40, "application/link-format"),
+    (41, "application/xml"),
+    (42, "application/octet-stream"),
+    (47, "application/exi"),
+    (50, "application/json"),
+    (51, "application/json-patch+json"),
+    (52, "application/merge-patch+json"),
+    (60, "application/cbor"),
+    (61, "application/cwt"),
+    (62, "application/multipart-core"),
+    (63, "application/cbor-seq"),
+    (96, "application/cose; cose-type=\"cose-encrypt\""),
+    (97, "application/cose; cose-type=\"cose-mac\""),
+    (98, "application/cose; cose-type=\"cose-sign\""),
+    (101, "application/cose-key"),
+    (102, "application/cose-key-set"),
+    (110, "application/senml+json"),
+    (111, "application/sensml+json"),
+    (112, "application/senml+cbor"),
+    (113, "application/sensml+cbor"),
+    (256, "application/coap-group+json"),
+    (320, "application/senml+xml"),
+    (10000, "application/vnd.ocf+cbor"),
+    (11542, "application/vnd.oma.lwm2m+tlv"),
+    (11543, "application/vnd.oma.lwm2m+json"),
+    (11544, "application/vnd.oma.lwm2m+cbor"),
+];
+
+pub fn content_format(tag: u64) -> Option<u64> {
+    (TAG_CONTENT_FORMAT_FIRST..=TAG_CONTENT_FORMAT_LAST)
+        .contains(&tag)
+        .then(|| tag - TAG_CONTENT_FORMAT_FIRST)
+}
+
+fn describe_content_format(content_format: u64) -> String {
+    match CONTENT_FORMATS.iter().find(|(id, _)| *id == content_format) {
+        Some((_, name)) => format!("content-format {} ({})", content_format, name),
+        None => format!("content-format {}", content_format),
+    }
+}
+
+pub fn tag_name(tag: u64) -> Option<&'static str> {
+    match tag {
+        TAG_SELF_DESCRIBED_CBOR => Some("self-described cbor"),
+        TAG_SELF_DESCRIBED_CBOR_SEQUENCE => Some("self-described cbor sequence"),
+        _ => content_format(tag).map(|_| "content-format label"),
+    }
+}
+
+/// Recognizes the file magic of RFC 8949 Section 3.4.6 and the labels of RFC 9277.
+pub fn describe(tag: u64, payload: &CborObject) -> Result<String, String> {
+    match tag {
+        TAG_SELF_DESCRIBED_CBOR => {
+            match payload.as_tag().and_then(|(tag, _)| content_format(tag)) {
+                Some(content_format) => Ok(format!(
+                    "labeled cbor file, {}",
+                    describe_content_format(content_format)
+                )),
+                None => Ok("cbor file".to_string()),
+            }
+        }
+        TAG_SELF_DESCRIBED_CBOR_SEQUENCE => {
+            let Some((tag, marker)) = payload.as_tag() else {
+                return Err("expected content-format tag".to_string());
+            };
+            let Some(content_format) = content_format(tag) else {
+                return Err(format!("tag {} is not a content-format tag", tag));
+            };
+            if marker.as_bytes().as_deref() != Some(SEQUENCE_MARKER) {
+                return Err("expected h'424f52' in content-format tag".to_string());
+            }
+            Ok(format!(
+                "labeled cbor sequence, {}",
+                describe_content_format(content_format)
+            ))
+        }
+        _ => match content_format(tag) {
+            Some(content_format) => Ok(describe_content_format(content_format)),
+            None => Err(format!("unknown tag {}", tag)),
+        },
+    }
+}
+
+/// Tells whether an item is the label that starts an RFC 9277 CBOR sequence, after which
+/// the rest of the input is a sequence of items.
+pub fn is_sequence_label(object: &CborObject) -> bool {
+    matches!(object.as_tag(), Some((TAG_SELF_DESCRIBED_CBOR_SEQUENCE, payload))
+        if describe(TAG_SELF_DESCRIBED_CBOR_SEQUENCE, payload).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cbor_parser::parse_cbor;
+
+    #[test]
+    fn labeled_file() {
+        let (_, object) = parse_cbor(b"\xd9\xd9\xf7\xda\x63\x74\x00\x3c\x01").unwrap();
+        let (tag, payload) = object.as_tag().unwrap();
+        assert_eq!(
+            describe(tag, payload),
+            Ok("labeled cbor file, content-format 60 (application/cbor)".to_string())
+        );
+        assert!(!is_sequence_label(&object));
+    }
+
+    #[test]
+    fn labeled_sequence() {
+        let (_, object) = parse_cbor(b"\xd9\xd9\xf8\xda\x63\x74\x00\x3f\x43BOR").unwrap();
+        assert!(is_sequence_label(&object));
+        let (_, object) = parse_cbor(b"\xd9\xd9\xf8\xda\x63\x74\x00\x3f\x43BOX").unwrap();
+        assert!(!is_sequence_label(&object));
+    }
+}
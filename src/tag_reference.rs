@@ -0,0 +1,89 @@
+use crate::cbor_object::{CborObject, ToTree};
+use crate::context::Context;
+use crate::diagnostic::short_diagnostic;
+use crate::tree::Node;
+
+pub const TAG_STRINGREF: u64 = 25;
+pub const TAG_SHAREABLE: u64 = 28;
+pub const TAG_SHAREDREF: u64 = 29;
+pub const TAG_STRINGREF_NAMESPACE: u64 = 256;
+
+pub fn tag_name(tag: u64) -> Option<&'static str> {
+    match tag {
+        TAG_STRINGREF => Some("stringref"),
+        TAG_SHAREABLE => Some("shareable"),
+        TAG_SHAREDREF => Some("sharedref"),
+        TAG_STRINGREF_NAMESPACE => Some("stringref namespace"),
+        _ => None,
+    }
+}
+
+fn expect_index(payload: &CborObject) -> Result<usize, String> {
+    payload
+        .as_unsigned()
+        .and_then(|index| usize::try_from(index).ok())
+        .ok_or_else(|| "expected unsigned index".to_string())
+}
+
+fn resolve_stringref(payload: &CborObject, context: &Context) -> Result<String, String> {
+    let index = expect_index(payload)?;
+    let Some(namespace) = context.string_namespaces.last() else {
+        return Err("dangling reference outside of a stringref namespace".to_string());
+    };
+    match namespace.get(index) {
+        Some(string) => Ok(format!("-> {}", string)),
+        None => Err(format!(
+            "string {} is out of range ({} strings in namespace)",
+            index,
+            namespace.len()
+        )),
+    }
+}
+
+fn resolve_sharedref(payload: &CborObject, context: &Context) -> Result<String, String> {
+    let index = expect_index(payload)?;
+    match context.shared_values.get(index) {
+        Some(value) => Ok(format!("-> {}", value)),
+        None => Err(format!(
+            "shared value {} is out of range ({} shared values)",
+            index,
+            context.shared_values.len()
+        )),
+    }
+}
+
+/// Builds the payload of a stringref (tags 25, 256) or value sharing (tags 28, 29) tag,
+/// keeping the tables in the context up to date, and describes what the tag refers to.
+pub fn into_tree(
+    tag: u64,
+    payload: CborObject,
+    context: &mut Context,
+) -> (Option<Result<String, String>>, Node) {
+    match tag {
+        TAG_STRINGREF => (
+            Some(resolve_stringref(&payload, context)),
+            payload.into_tree_with_context(context),
+        ),
+        TAG_SHAREDREF => (
+            Some(resolve_sharedref(&payload, context)),
+            payload.into_tree_with_context(context),
+        ),
+        TAG_SHAREABLE => {
+            // The index is taken before the payload is built, so that the payload can refer to
+            // itself.
+            let index = context.shared_values.len();
+            context.shared_values.push(short_diagnostic(&payload));
+            (
+                Some(Ok(format!("shared value {}", index))),
+                payload.into_tree_with_context(context),
+            )
+        }
+        TAG_STRINGREF_NAMESPACE => {
+            context.string_namespaces.push(vec![]);
+            let child = payload.into_tree_with_context(context);
+            context.string_namespaces.pop();
+            (None, child)
+        }
+        _ => (None, payload.into_tree_with_context(context)),
+    }
+}
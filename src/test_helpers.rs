@@ -0,0 +1,11 @@
+//! Helpers shared by the unit tests.
+
+use crate::cbor_object::CborObject;
+use crate::cbor_parser::parse_cbor;
+use crate::parse_hex;
+
+/// Parses the item encoded by `hex`, which may contain spaces.
+pub fn parse_item(hex: &str) -> CborObject {
+    let bytes = parse_hex(hex).unwrap();
+    parse_cbor(&bytes).unwrap().1
+}
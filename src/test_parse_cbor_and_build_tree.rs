@@ -3,6 +3,7 @@ use pretty_assertions::assert_eq;
 
 use crate::cbor_object::*;
 use crate::cbor_parser::parse_cbor;
+use crate::context::Context;
 use crate::tree::Node;
 
 #[test]
@@ -859,3 +860,947 @@ fn parse_break() -> Result<()> {
     assert_eq!(actual, expected);
     Ok(())
 }
+
+#[test]
+fn parse_tag_expected_conversion_nested() -> Result<()> {
+    let input = b"\xd6\x82\x42\x01\x02\xd7\x41\xff\x00";
+    let expected = Node::new(vec![0xd6])
+        .with_comment("tag(0x16 = 22) = expected conversion to base64".to_string())
+        .with_child(
+            Node::new(vec![0x82])
+                .with_comment("array(0x2 = 2)".to_string())
+                .with_children(vec![
+                    Node::new(vec![0x42])
+                        .with_comment("bstr(0x2 = 2)".to_string())
+                        .with_child(Node::new(vec![0x01, 0x02]).with_comment("b64'AQI='")),
+                    Node::new(vec![0xd7])
+                        .with_comment("tag(0x17 = 23) = expected conversion to base16")
+                        .with_child(
+                            Node::new(vec![0x41])
+                                .with_comment("bstr(0x1 = 1)".to_string())
+                                .with_child(Node::new(vec![0xff]).with_comment("h'ff'")),
+                        ),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_expected_conversion_embedded() -> Result<()> {
+    let input = b"\xd5\x41\x01\x00";
+    let expected = Node::new(vec![0xd5])
+        .with_comment("tag(0x15 = 21) = expected conversion to base64url".to_string())
+        .with_child(
+            Node::new(vec![0x41])
+                .with_comment("bstr(0x1 = 1)".to_string())
+                .with_child(Node::new(vec![0x01]).with_comment("b64'AQ'")),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_base64url_text() -> Result<()> {
+    let input = b"\xd8\x21\x63AQI\x00";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x21])
+        .with_comment("tag(0x21 = 33) = base64url text".to_string())
+        .with_annotation("decoded h'0102'")
+        .with_child(
+            Node::new(vec![0x63])
+                .with_comment("tstr(0x3 = 3)".to_string())
+                .with_child(Node::new(b"AQI".to_vec()).with_comment("\"AQI\"")),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_uri_invalid() -> Result<()> {
+    let input = b"\xd8\x20\x01\x00";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x20])
+        .with_comment("tag(0x20 = 32) = uri".to_string())
+        .with_annotation("invalid uri: expected tstr")
+        .with_child(Node::new(vec![0x01]).with_comment("unsigned(0x1) = 1".to_string()));
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_uuid() -> Result<()> {
+    let input = b"\xd8\x25\x50\x55\x0e\x84\x00\xe2\x9b\x41\xd4\xa7\x16\x44\x66\x55\x44\x00\x00\x00";
+    let uuid = b"\x55\x0e\x84\x00\xe2\x9b\x41\xd4\xa7\x16\x44\x66\x55\x44\x00\x00";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x25])
+        .with_comment("tag(0x25 = 37) = uuid".to_string())
+        .with_annotation("550e8400-e29b-41d4-a716-446655440000 (version 4)")
+        .with_child(
+            Node::new(vec![0x50])
+                .with_comment("bstr(0x10 = 16)".to_string())
+                .with_child(
+                    Node::new(uuid.to_vec())
+                        .with_comment(format!("\"{}\"", bstr::ByteSlice::escape_bytes(&uuid[..]))),
+                ),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_ipv4_prefix() -> Result<()> {
+    let input = b"\xd8\x34\x82\x18\x18\x43\xc0\x00\x02\x00";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x34])
+        .with_comment("tag(0x34 = 52) = ipv4".to_string())
+        .with_annotation("192.0.2.0/24")
+        .with_child(
+            Node::new(vec![0x82])
+                .with_comment("array(0x2 = 2)".to_string())
+                .with_children(vec![
+                    Node::new(vec![0x18])
+                        .with_more_bytes(vec![0x18])
+                        .with_comment("unsigned(0x18) = 24".to_string()),
+                    Node::new(vec![0x43])
+                        .with_comment("bstr(0x3 = 3)".to_string())
+                        .with_child(
+                            Node::new(vec![0xc0, 0x00, 0x02]).with_comment("\"\\xC0\\0\\x02\""),
+                        ),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_ipv6_prefix_with_host_bits() -> Result<()> {
+    let input = b"\xd8\x36\x82\x08\x42\x20\x01\x00";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x36])
+        .with_comment("tag(0x36 = 54) = ipv6".to_string())
+        .with_annotation("invalid ipv6: prefix has more than 8 bits")
+        .with_child(
+            Node::new(vec![0x82])
+                .with_comment("array(0x2 = 2)".to_string())
+                .with_children(vec![
+                    Node::new(vec![0x08]).with_comment("unsigned(0x8) = 8".to_string()),
+                    Node::new(vec![0x42])
+                        .with_comment("bstr(0x2 = 2)".to_string())
+                        .with_child(Node::new(vec![0x20, 0x01]).with_comment("\"\\x20\\x01\"")),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_oid() -> Result<()> {
+    let input = b"\xd8\x6f\x48\x2a\x86\x48\xce\x3d\x03\x01\x07\x00";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x6f])
+        .with_comment("tag(0x6f = 111) = oid".to_string())
+        .with_annotation("1.2.840.10045.3.1.7 (prime256v1)")
+        .with_child(
+            Node::new(vec![0x48])
+                .with_comment("bstr(0x8 = 8)".to_string())
+                .with_child(
+                    Node::new(vec![0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07])
+                        .with_comment("\"*\\x86H\\xCE=\\x03\\x01\\x07\""),
+                ),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_stringref() -> Result<()> {
+    let input = b"\xd9\x01\x00\x83\x63abc\xd8\x19\x00\xd8\x19\x01\x00";
+    let expected = Node::new(vec![0xd9])
+        .with_more_bytes(vec![0x01, 0x00])
+        .with_comment("tag(0x100 = 256) = stringref namespace".to_string())
+        .with_child(
+            Node::new(vec![0x83])
+                .with_comment("array(0x3 = 3)".to_string())
+                .with_children(vec![
+                    Node::new(vec![0x63])
+                        .with_comment("tstr(0x3 = 3)".to_string())
+                        .with_child(Node::new(b"abc".to_vec()).with_comment("\"abc\"")),
+                    Node::new(vec![0xd8])
+                        .with_more_bytes(vec![0x19])
+                        .with_comment("tag(0x19 = 25) = stringref".to_string())
+                        .with_annotation("-> \"abc\"")
+                        .with_child(Node::new(vec![0x00]).with_comment("unsigned(0x0) = 0")),
+                    Node::new(vec![0xd8])
+                        .with_more_bytes(vec![0x19])
+                        .with_comment("tag(0x19 = 25) = stringref".to_string())
+                        .with_annotation(
+                            "invalid stringref: string 1 is out of range (1 strings in namespace)",
+                        )
+                        .with_child(Node::new(vec![0x01]).with_comment("unsigned(0x1) = 1")),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_shared_value() -> Result<()> {
+    let input = b"\xd8\x1c\x82\x01\xd8\x1d\x00\x00";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x1c])
+        .with_comment("tag(0x1c = 28) = shareable".to_string())
+        .with_annotation("shared value 0")
+        .with_child(
+            Node::new(vec![0x82])
+                .with_comment("array(0x2 = 2)".to_string())
+                .with_children(vec![
+                    Node::new(vec![0x01]).with_comment("unsigned(0x1) = 1"),
+                    Node::new(vec![0xd8])
+                        .with_more_bytes(vec![0x1d])
+                        .with_comment("tag(0x1d = 29) = sharedref".to_string())
+                        .with_annotation("-> [1, 29(0)]")
+                        .with_child(Node::new(vec![0x00]).with_comment("unsigned(0x0) = 0")),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_packed_cbor() -> Result<()> {
+    let input = b"\xd8\x71\x82\x81\x63abc\x82\xe0\xe1\x00";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x71])
+        .with_comment("tag(0x71 = 113) = packed cbor".to_string())
+        .with_annotation("1 table item")
+        .with_child(
+            Node::new(vec![0x82])
+                .with_comment("array(0x2 = 2)".to_string())
+                .with_children(vec![
+                    Node::new(vec![0x81])
+                        .with_comment("array(0x1 = 1)".to_string())
+                        .with_child(
+                            Node::new(vec![0x63])
+                                .with_comment("tstr(0x3 = 3)".to_string())
+                                .with_child(Node::new(b"abc".to_vec()).with_comment("\"abc\"")),
+                        ),
+                    Node::new(vec![0x82])
+                        .with_comment("array(0x2 = 2)".to_string())
+                        .with_children(vec![
+                            Node::new(vec![0xe0])
+                                .with_comment("simple(0x0 = 0) = shared item reference")
+                                .with_annotation("-> \"abc\""),
+                            Node::new(vec![0xe1])
+                                .with_comment("simple(0x1 = 1) = shared item reference")
+                                .with_annotation(
+                                    "invalid shared item reference: item 1 is out of range (1 items in table)",
+                                ),
+                        ]),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_labeled_cbor_sequence() -> Result<()> {
+    let input = b"\xd9\xd9\xf8\xda\x63\x74\x00\x3f\x43BOR\x01";
+    let expected = Node::new(vec![0xd9])
+        .with_more_bytes(vec![0xd9, 0xf8])
+        .with_comment("tag(0xd9f8 = 55800) = self-described cbor sequence".to_string())
+        .with_annotation("labeled cbor sequence, content-format 63 (application/cbor-seq)")
+        .with_child(
+            Node::new(vec![0xda])
+                .with_more_bytes(vec![0x63, 0x74, 0x00, 0x3f])
+                .with_comment("tag(0x6374003f = 1668546623) = content-format label".to_string())
+                .with_annotation("content-format 63 (application/cbor-seq)")
+                .with_child(
+                    Node::new(vec![0x43])
+                        .with_comment("bstr(0x3 = 3)".to_string())
+                        .with_child(Node::new(b"BOR".to_vec()).with_comment("\"BOR\"")),
+                ),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x01");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_cose_sign1() -> Result<()> {
+    let input = b"\xd2\x84\x43\xa1\x01\x26\xa0\xf6\x41\x00\x00";
+    let expected = Node::new(vec![0xd2])
+        .with_comment("tag(0x12 = 18) = cose sign1")
+        .with_child(
+            Node::new(vec![0x84])
+                .with_comment("array(0x4 = 4)")
+                .with_children(vec![
+                    Node::new(vec![0x43])
+                        .with_comment("bstr(0x3 = 3)")
+                        .with_annotation("protected header")
+                        .with_child(
+                            Node::new(vec![0xa1])
+                                .with_comment("map(0x1 = 1)")
+                                .with_children(vec![
+                                    Node::new(vec![0x01])
+                                        .with_comment("unsigned(0x1) = 1")
+                                        .with_annotation("alg"),
+                                    Node::new(vec![0x26])
+                                        .with_comment("negative(0x6) = -7")
+                                        .with_annotation("ES256"),
+                                ])
+                                .mark_embedded(),
+                        ),
+                    Node::new(vec![0xa0])
+                        .with_comment("map(0x0 = 0)")
+                        .with_annotation("unprotected header"),
+                    Node::new(vec![0xf6])
+                        .with_comment("simple(0x16 = 22) = null")
+                        .with_annotation("payload"),
+                    Node::new(vec![0x41])
+                        .with_comment("bstr(0x1 = 1)")
+                        .with_annotation("signature")
+                        .with_child(Node::new(vec![0x00]).with_comment("\"\\0\"")),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"\x00");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_labeled_cose_key() -> Result<()> {
+    let input = b"\xda\x63\x74\x00\x65\xa2\x01\x04\x20\x42\x00\x01";
+    let expected = Node::new(vec![0xda])
+        .with_more_bytes(vec![0x63, 0x74, 0x00, 0x65])
+        .with_comment("tag(0x63740065 = 1668546661) = content-format label")
+        .with_annotation("content-format 101 (application/cose-key)")
+        .with_child(
+            Node::new(vec![0xa2])
+                .with_comment("map(0x2 = 2)")
+                .with_annotation("symmetric key, 16 bits")
+                .with_children(vec![
+                    Node::new(vec![0x01])
+                        .with_comment("unsigned(0x1) = 1")
+                        .with_annotation("kty"),
+                    Node::new(vec![0x04])
+                        .with_comment("unsigned(0x4) = 4")
+                        .with_annotation("Symmetric"),
+                    Node::new(vec![0x20])
+                        .with_comment("negative(0x0) = -1")
+                        .with_annotation("k"),
+                    Node::new(vec![0x42])
+                        .with_comment("bstr(0x2 = 2)")
+                        .with_child(Node::new(vec![0x00, 0x01]).with_comment("\"\\0\\x01\"")),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_cwt() -> Result<()> {
+    let input = b"\xd8\x3d\xd1\x84\x41\xa0\xa0\x44\xa1\x04\x18\x64\x41\x00";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x3d])
+        .with_comment("tag(0x3d = 61) = cwt")
+        .with_child(
+            Node::new(vec![0xd1])
+                .with_comment("tag(0x11 = 17) = cose mac0")
+                .with_child(
+                    Node::new(vec![0x84])
+                        .with_comment("array(0x4 = 4)")
+                        .with_children(vec![
+                            Node::new(vec![0x41])
+                                .with_comment("bstr(0x1 = 1)")
+                                .with_annotation("protected header")
+                                .with_child(
+                                    Node::new(vec![0xa0])
+                                        .with_comment("map(0x0 = 0)")
+                                        .mark_embedded(),
+                                ),
+                            Node::new(vec![0xa0])
+                                .with_comment("map(0x0 = 0)")
+                                .with_annotation("unprotected header"),
+                            Node::new(vec![0x44])
+                                .with_comment("bstr(0x4 = 4)")
+                                .with_annotation("payload")
+                                .with_child(
+                                    Node::new(vec![0xa1])
+                                        .with_comment("map(0x1 = 1)")
+                                        .with_annotation("expired at 1970-01-01T00:01:40Z, checked at 1970-01-01T00:03:20Z")
+                                        .with_children(vec![
+                                            Node::new(vec![0x04])
+                                                .with_comment("unsigned(0x4) = 4")
+                                                .with_annotation("exp"),
+                                            Node::new(vec![0x18])
+                                                .with_more_bytes(vec![0x64])
+                                                .with_comment("unsigned(0x64) = 100")
+                                                .with_annotation("1970-01-01T00:01:40Z"),
+                                        ])
+                                        .mark_embedded(),
+                                ),
+                            Node::new(vec![0x41])
+                                .with_comment("bstr(0x1 = 1)")
+                                .with_annotation("tag")
+                                .with_child(Node::new(vec![0x00]).with_comment("\"\\0\"")),
+                        ]),
+                ),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual = object.into_tree_with_context(&mut Context {
+        now: Some(200),
+        ..Default::default()
+    });
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_ctap2_client_pin() -> Result<()> {
+    // authenticatorClientPIN(pinUvAuthProtocol: 1, subCommand: getPINRetries)
+    let input = b"\xa2\x01\x01\x02\x01";
+    let expected = Node::new(vec![0xa2])
+        .with_comment("map(0x2 = 2)".to_string())
+        .with_children(vec![
+            Node::new(vec![0x01])
+                .with_comment("unsigned(0x1) = 1".to_string())
+                .with_annotation("pinUvAuthProtocol"),
+            Node::new(vec![0x01]).with_comment("unsigned(0x1) = 1".to_string()),
+            Node::new(vec![0x02])
+                .with_comment("unsigned(0x2) = 2".to_string())
+                .with_annotation("subCommand"),
+            Node::new(vec![0x01])
+                .with_comment("unsigned(0x1) = 1".to_string())
+                .with_annotation("getPINRetries"),
+        ]);
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let schema = crate::ctap2::command(crate::ctap2::CLIENT_PIN_COMMAND)
+        .unwrap()
+        .parameters;
+    let actual = crate::schema::into_tree_with_schema(object, schema, &mut Context::default());
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_encoded_cbor() -> Result<()> {
+    let input = b"\xd8\x18\x41\x01";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x18])
+        .with_comment("tag(0x18 = 24) = encoded cbor")
+        .with_child(
+            Node::new(vec![0x41])
+                .with_comment("bstr(0x1 = 1)")
+                .with_child(
+                    Node::new(vec![0x01])
+                        .with_comment("unsigned(0x1) = 1")
+                        .mark_embedded(),
+                ),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    // The byte string is embedded even where byte strings are binary data.
+    let actual = object.into_tree_with_context(&mut Context {
+        opaque_byte_strings: true,
+        ..Default::default()
+    });
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_full_date() -> Result<()> {
+    let input = b"\xd9\x03\xec\x6a1940-10-09";
+    let expected = Node::new(vec![0xd9])
+        .with_more_bytes(vec![0x03, 0xec])
+        .with_comment("tag(0x3ec = 1004) = full-date")
+        .with_annotation("1940-10-09")
+        .with_child(
+            Node::new(vec![0x6a])
+                .with_comment("tstr(0xa = 10)")
+                .with_child(Node::new(b"1940-10-09".to_vec()).with_comment("\"1940-10-09\"")),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_suit_manifest() -> Result<()> {
+    // 1070({7: <<[12, 0, 1, 15]>>})
+    let input = b"\xd9\x04\x2e\xa1\x07\x45\x84\x0c\x00\x01\x0f";
+    let expected = Node::new(vec![0xd9])
+        .with_more_bytes(vec![0x04, 0x2e])
+        .with_comment("tag(0x42e = 1070) = suit manifest")
+        .with_child(
+            Node::new(vec![0xa1])
+                .with_comment("map(0x1 = 1)")
+                .with_children(vec![
+                    Node::new(vec![0x07])
+                        .with_comment("unsigned(0x7) = 7")
+                        .with_annotation("validate"),
+                    Node::new(vec![0x45])
+                        .with_comment("bstr(0x5 = 5)")
+                        .with_child(
+                            Node::new(vec![0x84])
+                                .with_comment("array(0x4 = 4)")
+                                .with_children(vec![
+                                    Node::new(vec![0x0c])
+                                        .with_comment("unsigned(0xc) = 12")
+                                        .with_annotation("directive-set-component-index"),
+                                    Node::new(vec![0x00]).with_comment("unsigned(0x0) = 0"),
+                                    Node::new(vec![0x01])
+                                        .with_comment("unsigned(0x1) = 1")
+                                        .with_annotation("condition-vendor-identifier"),
+                                    Node::new(vec![0x0f]).with_comment("unsigned(0xf) = 15"),
+                                ])
+                                .mark_embedded(),
+                        ),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_uccs() -> Result<()> {
+    // 601({263: 1, 265: "x"}), an unprotected claims set with EAT claims
+    let input = b"\xd9\x02\x59\xa2\x19\x01\x07\x01\x19\x01\x09\x61x";
+    let expected = Node::new(vec![0xd9])
+        .with_more_bytes(vec![0x02, 0x59])
+        .with_comment("tag(0x259 = 601) = uccs")
+        .with_child(
+            Node::new(vec![0xa2])
+                .with_comment("map(0x2 = 2)")
+                .with_children(vec![
+                    Node::new(vec![0x19])
+                        .with_more_bytes(vec![0x01, 0x07])
+                        .with_comment("unsigned(0x107) = 263")
+                        .with_annotation("dbgstat"),
+                    Node::new(vec![0x01])
+                        .with_comment("unsigned(0x1) = 1")
+                        .with_annotation("disabled"),
+                    Node::new(vec![0x19])
+                        .with_more_bytes(vec![0x01, 0x09])
+                        .with_comment("unsigned(0x109) = 265")
+                        .with_annotation("eat_profile"),
+                    Node::new(vec![0x61])
+                        .with_comment("tstr(0x1 = 1)")
+                        .with_child(Node::new(b"x".to_vec()).with_comment("\"x\"")),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_suit_envelope_with_severed_member() -> Result<()> {
+    // {1: <<[]>>, 3: <<{1: 1, 2: 0, 17: [-16, h'aa']}>>}, an envelope with delegation chains
+    // and a manifest whose install sequence is severed
+    let input = b"\xa2\x01\x41\x80\x03\x4a\xa3\x01\x01\x02\x00\x11\x82\x2f\x41\xaa";
+    let manifest = Node::new(vec![0xa3])
+        .with_comment("map(0x3 = 3)")
+        .with_children(vec![
+            Node::new(vec![0x01])
+                .with_comment("unsigned(0x1) = 1")
+                .with_annotation("manifest-version"),
+            Node::new(vec![0x01]).with_comment("unsigned(0x1) = 1"),
+            Node::new(vec![0x02])
+                .with_comment("unsigned(0x2) = 2")
+                .with_annotation("manifest-sequence-number"),
+            Node::new(vec![0x00]).with_comment("unsigned(0x0) = 0"),
+            Node::new(vec![0x11])
+                .with_comment("unsigned(0x11) = 17")
+                .with_annotation("install"),
+            Node::new(vec![0x82])
+                .with_comment("array(0x2 = 2)")
+                .with_annotation("severed")
+                .with_children(vec![
+                    Node::new(vec![0x2f])
+                        .with_comment("negative(0xf) = -16")
+                        .with_annotation("algorithm")
+                        .with_annotation("SHA-256"),
+                    Node::new(vec![0x41])
+                        .with_comment("bstr(0x1 = 1)")
+                        .with_annotation("aa")
+                        .with_annotation("digest")
+                        .with_child(Node::new(vec![0xaa]).with_comment("\"\\xAA\"")),
+                ]),
+        ])
+        .mark_embedded();
+    let expected = Node::new(vec![0xa2])
+        .with_comment("map(0x2 = 2)")
+        .with_children(vec![
+            Node::new(vec![0x01])
+                .with_comment("unsigned(0x1) = 1")
+                .with_annotation("delegation"),
+            Node::new(vec![0x41])
+                .with_comment("bstr(0x1 = 1)")
+                .with_child(
+                    Node::new(vec![0x80])
+                        .with_comment("array(0x0 = 0)")
+                        .mark_embedded(),
+                ),
+            Node::new(vec![0x03])
+                .with_comment("unsigned(0x3) = 3")
+                .with_annotation("manifest"),
+            Node::new(vec![0x4a])
+                .with_comment("bstr(0xa = 10)")
+                .with_child(manifest),
+        ]);
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual =
+        crate::schema::into_tree_with_schema(object, crate::suit::SUIT, &mut Context::default());
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_suit_components_and_uuids() -> Result<()> {
+    // 1070({3: <<{2: [[h'00'], ['flash', h'0001'], [1]],
+    //             4: <<[19, {1: h'fa6b...', 2: h'00'}]>>}>>})
+    let input = b"\xd9\x04\x2e\xa1\x03\x58\x2d\xa2\x02\x83\x81\x41\x00\x82\x45flash\x42\x00\x01\
+                  \x81\x01\x04\x58\x18\x82\x13\xa2\x01\x50\xfa\x6b\x4a\x53\xd5\xad\x5f\xdf\xbe\x9d\
+                  \xe6\x63\xe4\xd4\x1f\xfe\x02\x41\x00";
+    let uuid = b"\xfa\x6b\x4a\x53\xd5\xad\x5f\xdf\xbe\x9d\xe6\x63\xe4\xd4\x1f\xfe";
+    let components = Node::new(vec![0x83])
+        .with_comment("array(0x3 = 3)")
+        .with_children(vec![
+            Node::new(vec![0x81])
+                .with_comment("array(0x1 = 1)")
+                .with_annotation("component /00")
+                .with_child(
+                    Node::new(vec![0x41])
+                        .with_comment("bstr(0x1 = 1)")
+                        .with_child(Node::new(vec![0x00]).with_comment("\"\\0\"")),
+                ),
+            Node::new(vec![0x82])
+                .with_comment("array(0x2 = 2)")
+                .with_annotation("component /flash/0001")
+                .with_children(vec![
+                    Node::new(vec![0x45])
+                        .with_comment("bstr(0x5 = 5)")
+                        .with_child(Node::new(b"flash".to_vec()).with_comment("\"flash\"")),
+                    Node::new(vec![0x42])
+                        .with_comment("bstr(0x2 = 2)")
+                        .with_child(Node::new(vec![0x00, 0x01]).with_comment("\"\\0\\x01\"")),
+                ]),
+            Node::new(vec![0x81])
+                .with_comment("array(0x1 = 1)")
+                .with_annotation("invalid component identifier: expected bstr segments")
+                .with_child(Node::new(vec![0x01]).with_comment("unsigned(0x1) = 1")),
+        ]);
+    let shared_sequence = Node::new(vec![0x82])
+        .with_comment("array(0x2 = 2)")
+        .with_children(vec![
+            Node::new(vec![0x13])
+                .with_comment("unsigned(0x13) = 19")
+                .with_annotation("directive-set-parameters"),
+            Node::new(vec![0xa2])
+                .with_comment("map(0x2 = 2)")
+                .with_children(vec![
+                    Node::new(vec![0x01])
+                        .with_comment("unsigned(0x1) = 1")
+                        .with_annotation("vendor-identifier"),
+                    Node::new(vec![0x50])
+                        .with_comment("bstr(0x10 = 16)")
+                        .with_annotation("fa6b4a53-d5ad-5fdf-be9d-e663e4d41ffe")
+                        .with_child(Node::new(uuid.to_vec()).with_comment(format!(
+                            "\"{}\"",
+                            bstr::ByteSlice::escape_bytes(&uuid[..])
+                        ))),
+                    Node::new(vec![0x02])
+                        .with_comment("unsigned(0x2) = 2")
+                        .with_annotation("class-identifier"),
+                    Node::new(vec![0x41])
+                        .with_comment("bstr(0x1 = 1)")
+                        .with_annotation("invalid uuid: expected 16 bytes")
+                        .with_child(Node::new(vec![0x00]).with_comment("\"\\0\"")),
+                ]),
+        ])
+        .mark_embedded();
+    let expected = Node::new(vec![0xd9])
+        .with_more_bytes(vec![0x04, 0x2e])
+        .with_comment("tag(0x42e = 1070) = suit manifest")
+        .with_child(
+            Node::new(vec![0xa1])
+                .with_comment("map(0x1 = 1)")
+                .with_children(vec![
+                    Node::new(vec![0x03])
+                        .with_comment("unsigned(0x3) = 3")
+                        .with_annotation("common"),
+                    Node::new(vec![0x58])
+                        .with_more_bytes(vec![0x2d])
+                        .with_comment("bstr(0x2d = 45)")
+                        .with_child(
+                            Node::new(vec![0xa2])
+                                .with_comment("map(0x2 = 2)")
+                                .with_children(vec![
+                                    Node::new(vec![0x02])
+                                        .with_comment("unsigned(0x2) = 2")
+                                        .with_annotation("components"),
+                                    components,
+                                    Node::new(vec![0x04])
+                                        .with_comment("unsigned(0x4) = 4")
+                                        .with_annotation("shared-sequence"),
+                                    Node::new(vec![0x58])
+                                        .with_more_bytes(vec![0x18])
+                                        .with_comment("bstr(0x18 = 24)")
+                                        .with_child(shared_sequence),
+                                ])
+                                .mark_embedded(),
+                        ),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_eat_ueids() -> Result<()> {
+    // [h'01616263', h'02616263', h'09', h'', 1]
+    let input = b"\x85\x44\x01abc\x44\x02abc\x41\x09\x40\x01";
+    let ueid = |head: u8, bytes: &[u8], comment: &str, annotation: &str| {
+        Node::new(vec![head])
+            .with_comment(format!("bstr(0x{:x} = {})", bytes.len(), bytes.len()))
+            .with_annotation(annotation)
+            .with_child(Node::new(bytes.to_vec()).with_comment(comment))
+    };
+    let expected = Node::new(vec![0x85])
+        .with_comment("array(0x5 = 5)")
+        .with_children(vec![
+            ueid(0x44, b"\x01abc", "\"\\x01abc\"", "RAND, 3 bytes"),
+            ueid(0x44, b"\x02abc", "\"\\x02abc\"", "IEEE EUI, 3 bytes"),
+            ueid(0x41, b"\x09", "\"\\t\"", "invalid ueid: unknown type 0x09"),
+            ueid(0x40, b"", "\"\"", "invalid ueid: empty"),
+            Node::new(vec![0x01])
+                .with_comment("unsigned(0x1) = 1")
+                .with_annotation("invalid ueid: expected a bstr"),
+        ]);
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual = crate::schema::into_tree_with_schema(
+        object,
+        crate::schema::Schema::ArrayOf(&crate::eat::UEID),
+        &mut Context::default(),
+    );
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_coswid_roles() -> Result<()> {
+    // {2: [{33: 6}, {33: [1, 7]}]}, entities holding one role or an array of them
+    let input = b"\xa1\x02\x82\xa1\x18\x21\x06\xa1\x18\x21\x82\x01\x07";
+    let role = || {
+        Node::new(vec![0x18])
+            .with_more_bytes(vec![0x21])
+            .with_comment("unsigned(0x21) = 33")
+            .with_annotation("role")
+    };
+    let expected = Node::new(vec![0xa1])
+        .with_comment("map(0x1 = 1)")
+        .with_children(vec![
+            Node::new(vec![0x02])
+                .with_comment("unsigned(0x2) = 2")
+                .with_annotation("entity"),
+            Node::new(vec![0x82])
+                .with_comment("array(0x2 = 2)")
+                .with_children(vec![
+                    Node::new(vec![0xa1])
+                        .with_comment("map(0x1 = 1)")
+                        .with_children(vec![
+                            role(),
+                            Node::new(vec![0x06])
+                                .with_comment("unsigned(0x6) = 6")
+                                .with_annotation("maintainer"),
+                        ]),
+                    Node::new(vec![0xa1])
+                        .with_comment("map(0x1 = 1)")
+                        .with_children(vec![
+                            role(),
+                            Node::new(vec![0x82])
+                                .with_comment("array(0x2 = 2)")
+                                .with_children(vec![
+                                    Node::new(vec![0x01])
+                                        .with_comment("unsigned(0x1) = 1")
+                                        .with_annotation("tag-creator"),
+                                    Node::new(vec![0x07]).with_comment("unsigned(0x7) = 7"),
+                                ]),
+                        ]),
+                ]),
+        ]);
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual = crate::schema::into_tree_with_schema(
+        object,
+        crate::coswid::COSWID,
+        &mut Context::default(),
+    );
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_tag_cid() -> Result<()> {
+    // 42(h'00015500'), a CIDv1 of raw data with an empty identity multihash
+    let input = b"\xd8\x2a\x45\x00\x01\x55\x00\x00";
+    let expected = Node::new(vec![0xd8])
+        .with_more_bytes(vec![0x2a])
+        .with_comment("tag(0x2a = 42) = cid")
+        .with_annotation("bafkqaaa (v1, raw, identity)")
+        .with_child(
+            Node::new(vec![0x45])
+                .with_comment("bstr(0x5 = 5)")
+                .with_child(
+                    Node::new(vec![0x00, 0x01, 0x55, 0x00, 0x00])
+                        .with_comment("\"\\0\\x01U\\0\\0\""),
+                ),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual = object.into_tree();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_with_dictionary() -> Result<()> {
+    // 60000({1: [{1: 2}]})
+    let input = b"\xd9\xea\x60\xa1\x01\x81\xa1\x01\x02";
+    let dictionary = crate::dictionary::Dictionary::parse(
+        "[tags]\n60000 = \"reading\"\n\
+         [keys]\n1 = \"samples\"\n\
+         [keys.\"/1/*\"]\n1 = \"unit\"",
+    )
+    .unwrap();
+    let expected = Node::new(vec![0xd9])
+        .with_more_bytes(vec![0xea, 0x60])
+        .with_comment("tag(0xea60 = 60000) = reading")
+        .with_child(
+            Node::new(vec![0xa1])
+                .with_comment("map(0x1 = 1)")
+                .with_children(vec![
+                    Node::new(vec![0x01])
+                        .with_comment("unsigned(0x1) = 1")
+                        .with_annotation("samples"),
+                    Node::new(vec![0x81])
+                        .with_comment("array(0x1 = 1)")
+                        .with_child(
+                            Node::new(vec![0xa1])
+                                .with_comment("map(0x1 = 1)")
+                                .with_children(vec![
+                                    Node::new(vec![0x01])
+                                        .with_comment("unsigned(0x1) = 1")
+                                        .with_annotation("unit"),
+                                    Node::new(vec![0x02]).with_comment("unsigned(0x2) = 2"),
+                                ]),
+                        ),
+                ]),
+        );
+    let (input, object) = parse_cbor(input)?;
+    assert_eq!(input, b"");
+    let actual = object.into_tree_with_context(&mut Context {
+        dictionary: Some(std::rc::Rc::new(dictionary)),
+        ..Default::default()
+    });
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_with_cddl_names() -> Result<()> {
+    // {4: 1, 1: [2, 3]}
+    let bytes = b"\xa2\x04\x01\x01\x82\x02\x03";
+    let cddl = crate::cddl::Cddl::parse(
+        "claims = { exp => int, 1 => [x: int, y: int] }\n\
+         exp = 4",
+    )
+    .unwrap();
+    let expected = Node::new(vec![0xa2])
+        .with_comment("map(0x2 = 2)")
+        .with_children(vec![
+            Node::new(vec![0x04])
+                .with_comment("unsigned(0x4) = 4")
+                .with_annotation("exp"),
+            Node::new(vec![0x01]).with_comment("unsigned(0x1) = 1"),
+            Node::new(vec![0x01]).with_comment("unsigned(0x1) = 1"),
+            Node::new(vec![0x82])
+                .with_comment("array(0x2 = 2)")
+                .with_children(vec![
+                    Node::new(vec![0x02])
+                        .with_comment("unsigned(0x2) = 2")
+                        .with_annotation("x"),
+                    Node::new(vec![0x03])
+                        .with_comment("unsigned(0x3) = 3")
+                        .with_annotation("y"),
+                ]),
+        ]);
+    let (input, object) = parse_cbor(bytes)?;
+    assert_eq!(input, b"");
+    let names = crate::cddl_validate::Validator::new(&cddl, bytes)
+        .validate("claims", &object, 0)
+        .unwrap()
+        .names;
+    let actual = object.into_tree_with_context(&mut Context {
+        cddl_names: Some(std::rc::Rc::new(names)),
+        ..Default::default()
+    });
+    assert_eq!(actual, expected);
+    Ok(())
+}
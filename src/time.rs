@@ -0,0 +1,158 @@
+//! Dates given as seconds since 1970-01-01T00:00:00Z, and the date tags of RFC 8949 Section
+//! 3.4.1 and RFC 8943.
+
+use chrono::{DateTime, Days, NaiveDate, SecondsFormat};
+
+use crate::cbor_object::CborObject;
+use crate::value::Value;
+
+pub const TAG_DATE_TIME: u64 = 0;
+pub const TAG_DAYS: u64 = 100;
+pub const TAG_FULL_DATE: u64 = 1004;
+
+pub fn tag_name(tag: u64) -> Option<&'static str> {
+    match tag {
+        TAG_DATE_TIME => Some("date/time"),
+        TAG_DAYS => Some("days since 1970-01-01"),
+        TAG_FULL_DATE => Some("full-date"),
+        _ => None,
+    }
+}
+
+/// Validates the payload of a date tag and returns the date in UTC, as an RFC 3339 date-time
+/// or full-date.
+pub fn describe(tag: u64, payload: &CborObject) -> Result<String, String> {
+    match tag {
+        TAG_DATE_TIME => {
+            let text = payload.as_text().ok_or("expected tstr")?;
+            DateTime::parse_from_rfc3339(&text)
+                .map(|date| date.to_utc().to_rfc3339_opts(SecondsFormat::AutoSi, true))
+                .map_err(|err| err.to_string())
+        }
+        TAG_DAYS => {
+            let days = payload.as_integer().ok_or("expected integer")?;
+            let epoch = DateTime::UNIX_EPOCH.date_naive();
+            let date = match u64::try_from(days) {
+                Ok(days) => epoch.checked_add_days(Days::new(days)),
+                Err(_) => u64::try_from(-days)
+                    .ok()
+                    .and_then(|days| epoch.checked_sub_days(Days::new(days))),
+            };
+            date.map(|date| date.format("%Y-%m-%d").to_string())
+                .ok_or_else(|| "date out of range".to_string())
+        }
+        TAG_FULL_DATE => {
+            let text = payload.as_text().ok_or("expected tstr")?;
+            // A full-date has exactly four digits of year, two of month and two of day.
+            let is_full_date = text.len() == 10
+                && text.char_indices().all(|(i, c)| match i {
+                    4 | 7 => c == '-',
+                    _ => c.is_ascii_digit(),
+                });
+            if !is_full_date {
+                return Err(format!("expected YYYY-MM-DD, got {:?}", text));
+            }
+            NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .map_err(|err| err.to_string())
+        }
+        _ => Err(format!("unknown tag {}", tag)),
+    }
+}
+
+/// Reads a NumericDate (RFC 8392 Section 2), which is an integer or a floating-point number
+/// of seconds.
+pub fn numeric_date(value: &Value) -> Option<f64> {
+    match value {
+        Value::Float(seconds) if seconds.is_finite() => Some(*seconds),
+        _ => value.as_integer().map(|seconds| seconds as f64),
+    }
+}
+
+/// Formats a number of seconds since the epoch as an RFC 3339 date in UTC.
+pub fn format_numeric_date(seconds: f64) -> String {
+    // A double has no more than microsecond precision for the dates of this century.
+    let microseconds = (seconds * 1e6).round();
+    let whole_seconds = (microseconds / 1e6).floor();
+    let nanoseconds = (microseconds - whole_seconds * 1e6) as u32 * 1000;
+    match DateTime::from_timestamp(whole_seconds as i64, nanoseconds) {
+        Some(date) if (i64::MIN as f64..i64::MAX as f64).contains(&whole_seconds) => {
+            date.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+        }
+        _ => "date out of range".to_string(),
+    }
+}
+
+/// Reads a time given as seconds since the epoch or as an RFC 3339 date.
+pub fn parse_time(text: &str) -> Result<i64, String> {
+    if let Ok(seconds) = text.parse() {
+        return Ok(seconds);
+    }
+    DateTime::parse_from_rfc3339(text)
+        .map(|date| date.timestamp())
+        .map_err(|err| format!("invalid time {:?}: {}", text, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_dates() {
+        assert_eq!(
+            numeric_date(&Value::Unsigned(1444064944)).map(format_numeric_date),
+            Some("2015-10-05T17:09:04Z".to_string())
+        );
+        assert_eq!(
+            numeric_date(&Value::Float(1363896240.5)).map(format_numeric_date),
+            Some("2013-03-21T20:04:00.500Z".to_string())
+        );
+        assert_eq!(
+            numeric_date(&Value::Negative(0)).map(format_numeric_date),
+            Some("1969-12-31T23:59:59Z".to_string())
+        );
+        assert_eq!(format_numeric_date(1e300), "date out of range");
+        assert_eq!(numeric_date(&Value::Text("1".to_string())), None);
+    }
+
+    #[test]
+    fn date_tags() {
+        let describe_hex = |tag, hex: &str| describe(tag, &crate::test_helpers::parse_item(hex));
+        // "2013-03-21T20:04:00+01:00"
+        assert_eq!(
+            describe_hex(
+                TAG_DATE_TIME,
+                "7819323031332d30332d32315432303a30343a30302b30313a3030"
+            ),
+            Ok("2013-03-21T19:04:00Z".to_string())
+        );
+        // RFC 8943 Section 3: 1940-10-09 is day -10676 and 1980-12-08 is day 3994.
+        assert_eq!(
+            describe_hex(TAG_DAYS, "3929b3"),
+            Ok("1940-10-09".to_string())
+        );
+        assert_eq!(
+            describe_hex(TAG_DAYS, "190f9a"),
+            Ok("1980-12-08".to_string())
+        );
+        assert_eq!(
+            describe_hex(TAG_FULL_DATE, "6a313934302d31302d3039"),
+            Ok("1940-10-09".to_string())
+        );
+        // "1940-02-30"
+        assert!(describe_hex(TAG_FULL_DATE, "6a313934302d30322d3330").is_err());
+        // "1940-2-3"
+        assert!(describe_hex(TAG_FULL_DATE, "68313934302d322d33").is_err());
+        assert_eq!(
+            describe_hex(TAG_DATE_TIME, "01"),
+            Err("expected tstr".to_string())
+        );
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(parse_time("1444064944"), Ok(1444064944));
+        assert_eq!(parse_time("2015-10-05T19:09:04+02:00"), Ok(1444064944));
+        assert!(parse_time("yesterday").is_err());
+    }
+}
@@ -7,6 +7,7 @@ pub struct Node {
     bytes: Vec<u8>,
     more_bytes: Vec<u8>,
     comment: Option<String>,
+    annotations: Vec<String>,
     children: Vec<Node>,
     embedded: bool,
 }
@@ -26,6 +27,7 @@ impl Node {
             bytes,
             more_bytes: vec![],
             comment: None,
+            annotations: vec![],
             children: vec![],
             embedded: false,
         }
@@ -41,6 +43,11 @@ impl Node {
         self
     }
 
+    pub fn with_annotation<'a, T: Into<Cow<'a, str>>>(mut self, annotation: T) -> Self {
+        self.annotations.push(annotation.into().into_owned());
+        self
+    }
+
     pub fn with_child(mut self, child: Node) -> Self {
         self.children.push(child);
         self
@@ -51,6 +58,34 @@ impl Node {
         self
     }
 
+    /// Adds each annotation to the node whose bytes start at its offset, counted from the
+    /// start of this node, or to this node when no node starts there.
+    pub fn with_annotations_at(mut self, annotations: Vec<(usize, String)>) -> Self {
+        let mut pending = annotations;
+        self.annotate_at(&mut 0, &mut pending);
+        self.annotations
+            .extend(pending.into_iter().map(|(_, annotation)| annotation));
+        self
+    }
+
+    fn annotate_at(&mut self, offset: &mut usize, pending: &mut Vec<(usize, String)>) {
+        // Nodes without bytes, like the content of an empty string, start where the next
+        // item does.
+        if !self.bytes.is_empty() {
+            let start = *offset;
+            pending.retain(|(at, annotation)| {
+                if *at == start {
+                    self.annotations.push(annotation.clone());
+                }
+                *at != start
+            });
+        }
+        *offset += self.bytes.len() + self.more_bytes.len();
+        for child in &mut self.children {
+            child.annotate_at(offset, pending);
+        }
+    }
+
     pub fn mark_embedded(mut self) -> Self {
         self.embedded = true;
         self
@@ -95,7 +130,7 @@ impl Node {
             });
         }
 
-        if let Some(comment) = &self.comment {
+        if self.comment.is_some() || !self.annotations.is_empty() {
             if position < comment_position - 2 {
                 for _ in 0..(comment_position - position) {
                     output.push(' ');
@@ -104,7 +139,13 @@ impl Node {
                 output.push_str("  ");
             }
             output.push_str("-- ");
-            output.push_str(comment);
+            if let Some(comment) = &self.comment {
+                output.push_str(comment);
+            }
+            if !self.annotations.is_empty() {
+                output.push_str(if self.comment.is_some() { "  ; " } else { "; " });
+                output.push_str(&self.annotations.join("; "));
+            }
         }
         output.push('\n');
         for child in &self.children {
@@ -128,6 +169,7 @@ impl Node {
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use pretty_assertions::assert_eq;
 
@@ -197,4 +239,49 @@ mod tests {
         tree.write(&mut actual);
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn write_annotations() {
+        let tree = Node::new(vec![0x01])
+            .with_comment("comment 1")
+            .with_annotation("note 1")
+            .with_annotation("note 2")
+            .with_child(Node::new(vec![0x02]).with_annotation("note 1-1"));
+
+        let expected = [
+            "01     -- comment 1  ; note 1; note 2",
+            "   02  -- ; note 1-1",
+            "",
+        ]
+        .join("\n");
+
+        let mut actual = String::new();
+        tree.write(&mut actual);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn annotations_at_offsets() {
+        let tree = Node::new(vec![0x82])
+            .with_children(vec![
+                Node::new(vec![0x40]).with_child(Node::new(vec![])),
+                Node::new(vec![0x18]).with_more_bytes(vec![0x01]),
+            ])
+            .with_annotations_at(vec![
+                (2, "note 2".to_string()),
+                (0, "note 0".to_string()),
+                (5, "note 5".to_string()),
+            ]);
+
+        let expected = Node::new(vec![0x82])
+            .with_annotation("note 0")
+            .with_annotation("note 5")
+            .with_children(vec![
+                Node::new(vec![0x40]).with_child(Node::new(vec![])),
+                Node::new(vec![0x18])
+                    .with_more_bytes(vec![0x01])
+                    .with_annotation("note 2"),
+            ]);
+        assert_eq!(tree, expected);
+    }
 }
@@ -1,4 +1,6 @@
 use crate::cbor_object::{CborObject, ToTree};
+use crate::context::Context;
+use crate::schema::{self, Schema};
 use crate::tree::Node;
 use crate::type_common::{AdditionalInfoValue, ParsedBytesWithValue};
 
@@ -19,10 +21,25 @@ impl Array {
             value,
         }
     }
+
+    pub fn value(&self) -> &[CborObject] {
+        &self.value
+    }
+
+    pub fn encoding_indicator(&self) -> Option<&'static str> {
+        self.parsed_bytes.encoding_indicator()
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+        for item in &self.value {
+            item.encode_into(output);
+        }
+    }
 }
 
 impl ToTree for Array {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, context: &mut Context) -> Node {
         let Array {
             parsed_bytes,
             value,
@@ -32,7 +49,37 @@ impl ToTree for Array {
             AdditionalInfoValue::Reserved => "array(?)".to_string(),
             AdditionalInfoValue::Indefinite => "array(*)".to_string(),
         };
-        let children = value.into_iter().map(|child| child.into_tree()).collect();
+        let schema = std::mem::take(&mut context.schema);
+        let children = match schema {
+            Schema::Pairs(_) => {
+                let mut children = Vec::with_capacity(value.len());
+                let mut value = value.into_iter();
+                while let Some(label) = value.next() {
+                    let (name, value_schema) = schema.entry(&label);
+                    children.push(schema::child_into_tree(label, name, Schema::Any, context));
+                    if let Some(value) = value.next() {
+                        children.push(schema::child_into_tree(value, None, value_schema, context));
+                    }
+                }
+                children
+            }
+            _ => value
+                .into_iter()
+                .enumerate()
+                .map(|(index, child)| {
+                    let (name, child_schema) = schema.element(index);
+                    let name = name
+                        .map(str::to_string)
+                        .or_else(|| context.cddl_name(index.to_string()));
+                    context.with_path_segment(
+                        || index.to_string(),
+                        |context| {
+                            schema::child_into_tree(child, name.as_deref(), child_schema, context)
+                        },
+                    )
+                })
+                .collect(),
+        };
         parsed_bytes
             .into_node()
             .with_comment(comment)
@@ -1,9 +1,66 @@
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine as _;
 use bstr::ByteSlice as _;
 
 use crate::cbor_object::{CborObject, ToTree};
+use crate::context::Context;
+use crate::diagnostic::bytes_diagnostic;
+use crate::format_hex;
+use crate::schema::{self, Schema};
 use crate::tree::Node;
 use crate::type_common::{AdditionalInfoValue, ParsedBytesWithValue};
 
+/// Encoding suggested by tags 21 (base64url), 22 (base64) and 23 (base16)
+/// for the byte strings nested in the tagged item (RFC 8949 Section 3.4.5.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedConversion {
+    Base64Url,
+    Base64,
+    Base16,
+}
+
+impl ExpectedConversion {
+    pub fn from_tag(tag: u64) -> Option<Self> {
+        match tag {
+            21 => Some(ExpectedConversion::Base64Url),
+            22 => Some(ExpectedConversion::Base64),
+            23 => Some(ExpectedConversion::Base16),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExpectedConversion::Base64Url => "expected conversion to base64url",
+            ExpectedConversion::Base64 => "expected conversion to base64",
+            ExpectedConversion::Base16 => "expected conversion to base16",
+        }
+    }
+
+    /// Converts bytes to text as the tag asks.
+    pub fn encode(&self, value: &[u8]) -> String {
+        match self {
+            ExpectedConversion::Base64Url => URL_SAFE_NO_PAD.encode(value),
+            ExpectedConversion::Base64 => STANDARD.encode(value),
+            ExpectedConversion::Base16 => format_hex(value),
+        }
+    }
+
+    fn format(&self, value: &[u8]) -> String {
+        match self {
+            ExpectedConversion::Base16 => format!("h'{}'", self.encode(value)),
+            _ => format!("b64'{}'", self.encode(value)),
+        }
+    }
+}
+
+fn payload_comment(value: &[u8], context: &Context) -> String {
+    match context.expected_conversion {
+        Some(conversion) => conversion.format(value),
+        None => format!("\"{}\"", value.escape_bytes()),
+    }
+}
+
 pub struct ByteString {
     parsed_bytes: ParsedBytesWithValue,
     value: Vec<u8>,
@@ -21,17 +78,46 @@ impl ByteString {
             value,
         }
     }
+
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    pub fn encoding_indicator(&self) -> Option<&'static str> {
+        self.parsed_bytes.encoding_indicator()
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+        output.extend(&self.value);
+    }
+}
+
+/// Builds the content of a byte string: the fields of its layout if the schema gives one,
+/// or else the raw bytes.
+fn payload_into_tree(node: Node, value: Vec<u8>, schema: Schema, context: &mut Context) -> Node {
+    if let Schema::Layout(layout) = schema {
+        match layout(&value, context) {
+            Ok(fields) => return node.with_children(fields),
+            Err(err) => {
+                let payload_comment = payload_comment(&value, context);
+                return node
+                    .with_annotation(err)
+                    .with_child(Node::new(value).with_comment(payload_comment));
+            }
+        }
+    }
+    let payload_comment = payload_comment(&value, context);
+    node.with_child(Node::new(value).with_comment(payload_comment))
 }
 
 impl ToTree for ByteString {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, context: &mut Context) -> Node {
         let comment = format!("bstr({:#x} = {})", self.value.len(), self.value.len());
-        let payload_comment = format!("\"{}\"", self.value.escape_bytes());
-        let payload_node = Node::new(self.value.to_owned()).with_comment(payload_comment);
-        self.parsed_bytes
-            .into_node()
-            .with_comment(comment)
-            .with_child(payload_node)
+        context.register_string(self.value.len(), || bytes_diagnostic(&self.value));
+        let schema = std::mem::take(&mut context.schema);
+        let node = self.parsed_bytes.into_node().with_comment(comment);
+        payload_into_tree(node, self.value, schema, context)
     }
 }
 
@@ -47,16 +133,32 @@ impl IndefiniteByteString {
             value,
         }
     }
+
+    pub fn chunks(&self) -> &[CborObject] {
+        &self.value
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+        for item in &self.value {
+            item.encode_into(output);
+        }
+    }
 }
 
 impl ToTree for IndefiniteByteString {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, context: &mut Context) -> Node {
         let IndefiniteByteString {
             parsed_bytes,
             value,
         } = self;
         let comment = "bstr(*)";
-        let children = value.into_iter().map(|child| child.into_tree()).collect();
+        let children = context.without_string_namespaces(|context| {
+            value
+                .into_iter()
+                .map(|child| child.into_tree_with_context(context))
+                .collect()
+        });
         parsed_bytes
             .into_node()
             .with_comment(comment)
@@ -84,19 +186,56 @@ impl ByteStringWithEmbedded {
             value: Box::new(value),
         }
     }
+
+    pub fn raw_value(&self) -> &[u8] {
+        &self.raw_value
+    }
+
+    pub fn value(&self) -> &CborObject {
+        &self.value
+    }
+
+    pub fn encoding_indicator(&self) -> Option<&'static str> {
+        self.parsed_bytes.encoding_indicator()
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+        output.extend(&self.raw_value);
+    }
 }
 
 impl ToTree for ByteStringWithEmbedded {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, context: &mut Context) -> Node {
         let ByteStringWithEmbedded {
             parsed_bytes,
             raw_value,
             value,
         } = self;
         let comment = format!("bstr({:#x} = {})", raw_value.len(), raw_value.len());
-        parsed_bytes
-            .into_node()
-            .with_comment(comment)
-            .with_child(value.into_tree().mark_embedded())
+        context.register_string(raw_value.len(), || bytes_diagnostic(&raw_value));
+        let schema = std::mem::take(&mut context.schema);
+        let opaque = match schema {
+            Schema::Embedded(_) => false,
+            Schema::Opaque | Schema::Layout(_) => true,
+            // A conversion hint means the bytes are opaque data, even if they happen to be
+            // valid CBOR.
+            _ => context.expected_conversion.is_some() || context.opaque_byte_strings,
+        };
+        if opaque {
+            let node = parsed_bytes.into_node().with_comment(comment);
+            return payload_into_tree(node, raw_value, schema, context);
+        }
+        parsed_bytes.into_node().with_comment(comment).with_child(
+            context
+                .without_string_namespaces(|context| {
+                    let inner_schema = match schema {
+                        Schema::Embedded(inner_schema) => *inner_schema,
+                        _ => Schema::Any,
+                    };
+                    schema::into_tree_with_schema(*value, inner_schema, context)
+                })
+                .mark_embedded(),
+        )
     }
 }
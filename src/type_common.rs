@@ -28,6 +28,36 @@ impl ParsedBytesWithValue {
     pub fn into_node(self) -> Node {
         Node::new(self.bytes).with_more_bytes(self.more_bytes)
     }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        output.extend(&self.bytes);
+        output.extend(&self.more_bytes);
+    }
+
+    /// The encoding indicator of diagnostic notation (RFC 8610 Appendix G.2) for an argument
+    /// that takes more bytes than its preferred encoding.
+    pub fn encoding_indicator(&self) -> Option<&'static str> {
+        let AdditionalInfoValue::Value(value) = self.additional_info_value else {
+            return None;
+        };
+        let preferred_length = match value {
+            0..24 => 0,
+            24..256 => 1,
+            256..65536 => 2,
+            65536..4294967296 => 4,
+            _ => 8,
+        };
+        if self.more_bytes.len() <= preferred_length {
+            return None;
+        }
+        match self.more_bytes.len() {
+            1 => Some("_0"),
+            2 => Some("_1"),
+            4 => Some("_2"),
+            8 => Some("_3"),
+            _ => None,
+        }
+    }
 }
 
 pub struct ParsedBytesWithoutValue {
@@ -43,4 +73,9 @@ impl ParsedBytesWithoutValue {
     pub fn into_node(self) -> Node {
         Node::new(self.bytes).with_more_bytes(self.more_bytes)
     }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        output.extend(&self.bytes);
+        output.extend(&self.more_bytes);
+    }
 }
@@ -1,4 +1,6 @@
 use crate::cbor_object::{CborObject, ToTree};
+use crate::context::Context;
+use crate::schema::{self, Schema};
 use crate::tree::Node;
 use crate::type_common::{AdditionalInfoValue, ParsedBytesWithValue};
 
@@ -19,10 +21,25 @@ impl Map {
             value,
         }
     }
+
+    pub fn value(&self) -> &[CborObject] {
+        &self.value
+    }
+
+    pub fn encoding_indicator(&self) -> Option<&'static str> {
+        self.parsed_bytes.encoding_indicator()
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+        for item in &self.value {
+            item.encode_into(output);
+        }
+    }
 }
 
 impl ToTree for Map {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, context: &mut Context) -> Node {
         let Map {
             parsed_bytes,
             value,
@@ -32,7 +49,29 @@ impl ToTree for Map {
             AdditionalInfoValue::Reserved => "map(?)".to_string(),
             AdditionalInfoValue::Indefinite => "map(*)".to_string(),
         };
-        let children = value.into_iter().map(|child| child.into_tree()).collect();
+        let schema = std::mem::take(&mut context.schema);
+        let mut children = Vec::with_capacity(value.len());
+        let mut value = value.into_iter();
+        while let Some(key) = value.next() {
+            let (name, value_schema) = schema.entry(&key);
+            let name = name
+                .map(str::to_string)
+                .or_else(|| context.cddl_key_name(&key))
+                .or_else(|| context.dictionary_key_name(&key));
+            let segment = context.key_segment(&key);
+            children.push(schema::child_into_tree(
+                key,
+                name.as_deref(),
+                Schema::Any,
+                context,
+            ));
+            if let Some(value) = value.next() {
+                children.push(context.with_path_segment(
+                    || segment.unwrap_or_default(),
+                    |context| schema::child_into_tree(value, None, value_schema, context),
+                ));
+            }
+        }
         parsed_bytes
             .into_node()
             .with_comment(comment)
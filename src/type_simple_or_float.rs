@@ -1,13 +1,16 @@
 use half::f16;
 
 use crate::cbor_object::ToTree;
+use crate::context::Context;
+use crate::packed;
 use crate::tree::Node;
 use crate::type_common::ParsedBytesWithoutValue;
+use crate::value::Value;
 
-const SIMPLE_VALUE_FALSE: u8 = 20;
-const SIMPLE_VALUE_TRUE: u8 = 21;
-const SIMPLE_VALUE_NULL: u8 = 22;
-const SIMPLE_VALUE_UNDEFINED: u8 = 23;
+pub const SIMPLE_VALUE_FALSE: u8 = 20;
+pub const SIMPLE_VALUE_TRUE: u8 = 21;
+pub const SIMPLE_VALUE_NULL: u8 = 22;
+pub const SIMPLE_VALUE_UNDEFINED: u8 = 23;
 
 pub struct SimpleValue {
     parsed_bytes: ParsedBytesWithoutValue,
@@ -21,14 +24,37 @@ impl SimpleValue {
             value,
         }
     }
+
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+    }
 }
 
 impl ToTree for SimpleValue {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, context: &mut Context) -> Node {
         let SimpleValue {
             parsed_bytes,
             value,
         } = self;
+        if let Some(table) = &context.packing_table {
+            if packed::is_reference_simple(value) {
+                let annotation = match packed::describe_reference(&Value::Simple(value), table) {
+                    Ok(description) => description,
+                    Err(err) => format!("invalid shared item reference: {}", err),
+                };
+                return parsed_bytes
+                    .into_node()
+                    .with_comment(format!(
+                        "simple({:#x} = {}) = shared item reference",
+                        value, value
+                    ))
+                    .with_annotation(annotation);
+            }
+        }
         let comment = format!(
             "simple({:#x} = {}) = {}",
             value,
@@ -57,10 +83,18 @@ impl HalfPrecisionFloat {
             value,
         }
     }
+
+    pub fn value(&self) -> f16 {
+        self.value
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+    }
 }
 
 impl ToTree for HalfPrecisionFloat {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, _context: &mut Context) -> Node {
         let HalfPrecisionFloat {
             parsed_bytes,
             value,
@@ -82,10 +116,18 @@ impl SinglePrecisionFloat {
             value,
         }
     }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+    }
 }
 
 impl ToTree for SinglePrecisionFloat {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, _context: &mut Context) -> Node {
         let SinglePrecisionFloat {
             parsed_bytes,
             value,
@@ -107,10 +149,18 @@ impl DoublePrecisionFloat {
             value,
         }
     }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+    }
 }
 
 impl ToTree for DoublePrecisionFloat {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, _context: &mut Context) -> Node {
         let DoublePrecisionFloat {
             parsed_bytes,
             value,
@@ -132,10 +182,18 @@ impl ReservedSimpleOrFloat {
             additional_info_argument,
         }
     }
+
+    pub fn additional_info_argument(&self) -> u8 {
+        self.additional_info_argument
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+    }
 }
 
 impl ToTree for ReservedSimpleOrFloat {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, _context: &mut Context) -> Node {
         let ReservedSimpleOrFloat {
             parsed_bytes,
             additional_info_argument,
@@ -158,10 +216,14 @@ impl Break {
             parsed_bytes: ParsedBytesWithoutValue::new(bytes, vec![]),
         }
     }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+    }
 }
 
 impl ToTree for Break {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, _context: &mut Context) -> Node {
         self.parsed_bytes.into_node().with_comment("break")
     }
 }
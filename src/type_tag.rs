@@ -1,6 +1,23 @@
 use crate::cbor_object::{CborObject, ToTree};
+use crate::context::Context;
+use crate::corim;
+use crate::cose;
+use crate::coswid;
+use crate::cwt;
+use crate::dag_cbor;
+use crate::eat;
+use crate::packed;
+use crate::schema::{self, Schema};
+use crate::suit;
+use crate::tag_encoded_text;
+use crate::tag_identifier;
+use crate::tag_magic;
+use crate::tag_reference;
+use crate::time;
 use crate::tree::Node;
+use crate::type_byte_string::ExpectedConversion;
 use crate::type_common::{AdditionalInfoValue, ParsedBytesWithValue};
+use crate::value::Value;
 
 pub struct Tag {
     parsed_bytes: ParsedBytesWithValue,
@@ -19,21 +36,208 @@ impl Tag {
             payload: Box::new(payload),
         }
     }
+
+    pub fn tag(&self) -> Option<u64> {
+        match self.parsed_bytes.additional_info_value {
+            AdditionalInfoValue::Value(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn payload(&self) -> &CborObject {
+        &self.payload
+    }
+
+    pub fn encoding_indicator(&self) -> Option<&'static str> {
+        self.parsed_bytes.encoding_indicator()
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+        self.payload.encode_into(output);
+    }
+}
+
+fn annotation(name: &str, description: Result<String, String>) -> String {
+    match description {
+        Ok(description) => description,
+        Err(err) => format!("invalid {}: {}", name, err),
+    }
+}
+
+/// Tells whether the byte strings in the payload of a tag are binary data rather than
+/// embedded items.
+fn has_opaque_payload(tag: u64) -> bool {
+    tag_identifier::tag_name(tag).is_some()
+        || tag == tag_magic::TAG_SELF_DESCRIBED_CBOR_SEQUENCE
+        || tag == dag_cbor::TAG_CID
+}
+
+/// Names a tag of a structure with a schema of its own, such as a SUIT envelope or a CoRIM.
+fn structure_schema(tag: u64) -> Option<(&'static str, Schema)> {
+    suit::tag_schema(tag)
+        .or_else(|| corim::tag_schema(tag))
+        .or_else(|| coswid::tag_schema(tag))
+        .or_else(|| eat::tag_schema(tag))
+}
+
+/// Names a tag with a known payload format and checks the payload against it.
+fn describe_payload(
+    tag: u64,
+    payload: &CborObject,
+) -> Option<(&'static str, Result<String, String>)> {
+    if let Some(name) = tag_encoded_text::tag_name(tag) {
+        return Some((name, tag_encoded_text::describe(tag, payload)));
+    }
+    if let Some(name) = tag_identifier::tag_name(tag) {
+        return Some((name, tag_identifier::describe(tag, payload)));
+    }
+    if let Some(name) = tag_magic::tag_name(tag) {
+        return Some((name, tag_magic::describe(tag, payload)));
+    }
+    if let Some(name) = time::tag_name(tag) {
+        return Some((name, time::describe(tag, payload)));
+    }
+    if let Some(name) = dag_cbor::tag_name(tag) {
+        return Some((name, dag_cbor::describe(tag, payload)));
+    }
+    None
 }
 
 impl ToTree for Tag {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, context: &mut Context) -> Node {
+        let Some(tag) = self.tag() else {
+            return self.build_tree(context);
+        };
+        context.enclosing_tags.push(tag);
+        let node = self.build_tree(context);
+        context.enclosing_tags.pop();
+        node
+    }
+}
+
+impl Tag {
+    fn build_tree(self, context: &mut Context) -> Node {
         let Tag {
             parsed_bytes,
             payload,
         } = self;
-        let comment = match parsed_bytes.additional_info_value {
-            AdditionalInfoValue::Value(tag) => format!("tag({:#x} = {})", tag, tag),
-            AdditionalInfoValue::Reserved | AdditionalInfoValue::Indefinite => "tag(*)".to_string(),
+        let schema = std::mem::take(&mut context.schema);
+        let AdditionalInfoValue::Value(tag) = parsed_bytes.additional_info_value else {
+            return parsed_bytes
+                .into_node()
+                .with_comment("tag(*)")
+                .with_child(payload.into_tree_with_context(context));
+        };
+
+        if let Some(conversion) = ExpectedConversion::from_tag(tag) {
+            let outer_conversion = context.expected_conversion.replace(conversion);
+            let child = payload.into_tree_with_context(context);
+            context.expected_conversion = outer_conversion;
+            return parsed_bytes
+                .into_node()
+                .with_comment(format!("tag({:#x} = {}) = {}", tag, tag, conversion.name()))
+                .with_child(child);
+        }
+
+        if let Some((name, cose_schema)) = cose::tag_schema(tag) {
+            // The parent may know more about the message, e.g. that it carries a CWT.
+            let schema = match schema {
+                Schema::Any => cose_schema,
+                schema => schema,
+            };
+            let child = schema::into_tree_with_schema(*payload, schema, context);
+            return parsed_bytes
+                .into_node()
+                .with_comment(format!("tag({:#x} = {}) = {}", tag, tag, name))
+                .with_child(child);
+        }
+
+        if tag == tag_magic::TAG_ENCODED_CBOR {
+            // The byte string is an embedded item even where byte strings are usually
+            // binary data.
+            let schema = match schema {
+                Schema::Embedded(_) => schema,
+                _ => Schema::Embedded(&Schema::Any),
+            };
+            let child = schema::into_tree_with_schema(*payload, schema, context);
+            return parsed_bytes
+                .into_node()
+                .with_comment(format!("tag({:#x} = {}) = encoded cbor", tag, tag))
+                .with_child(child);
+        }
+
+        if tag == cwt::TAG_CWT {
+            let child = schema::into_tree_with_schema(*payload, cwt::CWT, context);
+            return parsed_bytes
+                .into_node()
+                .with_comment(format!("tag({:#x} = {}) = cwt", tag, tag))
+                .with_child(child);
+        }
+
+        if let Some((name, structure)) = structure_schema(tag) {
+            let child = schema::into_tree_with_schema(*payload, structure, context);
+            return parsed_bytes
+                .into_node()
+                .with_comment(format!("tag({:#x} = {}) = {}", tag, tag, name))
+                .with_child(child);
+        }
+
+        if tag == packed::TAG_PACKED {
+            let (description, child) = packed::into_tree(*payload, context);
+            return parsed_bytes
+                .into_node()
+                .with_comment(format!("tag({:#x} = {}) = packed cbor", tag, tag))
+                .with_annotation(annotation("packed cbor", description))
+                .with_child(child);
+        }
+
+        if let Some(table) = &context.packing_table {
+            if let Some(name) = packed::tag_name(tag).filter(|_| packed::is_reference_tag(tag)) {
+                let description = Value::from_object(&payload).and_then(|content| {
+                    packed::describe_reference(&Value::Tag(tag, Box::new(content)), table)
+                });
+                return parsed_bytes
+                    .into_node()
+                    .with_comment(format!("tag({:#x} = {}) = {}", tag, tag, name))
+                    .with_annotation(annotation(name, description))
+                    .with_child(payload.into_tree_with_context(context));
+            }
+        }
+
+        if let Some(name) = tag_reference::tag_name(tag) {
+            let (description, child) = tag_reference::into_tree(tag, *payload, context);
+            let mut node = parsed_bytes
+                .into_node()
+                .with_comment(format!("tag({:#x} = {}) = {}", tag, tag, name));
+            if let Some(description) = description {
+                node = node.with_annotation(annotation(name, description));
+            }
+            return node.with_child(child);
+        }
+
+        if let Some((name, description)) = describe_payload(tag, &payload) {
+            let outer_opaque = context.opaque_byte_strings;
+            context.opaque_byte_strings |= has_opaque_payload(tag);
+            let schema = tag_magic::content_format(tag)
+                .and_then(cose::content_format_schema)
+                .unwrap_or_default();
+            let child = schema::into_tree_with_schema(*payload, schema, context);
+            context.opaque_byte_strings = outer_opaque;
+            return parsed_bytes
+                .into_node()
+                .with_comment(format!("tag({:#x} = {}) = {}", tag, tag, name))
+                .with_annotation(annotation(name, description))
+                .with_child(child);
+        }
+
+        let comment = match context.dictionary.as_ref().and_then(|d| d.tag_name(tag)) {
+            Some(name) => format!("tag({:#x} = {}) = {}", tag, tag, name),
+            None => format!("tag({:#x} = {})", tag, tag),
         };
         parsed_bytes
             .into_node()
             .with_comment(comment)
-            .with_child(payload.into_tree())
+            .with_child(schema::into_tree_with_schema(*payload, schema, context))
     }
 }
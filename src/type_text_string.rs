@@ -1,6 +1,6 @@
-use bstr::ByteSlice as _;
-
 use crate::cbor_object::{CborObject, ToTree};
+use crate::context::Context;
+use crate::diagnostic::text_diagnostic;
 use crate::tree::Node;
 use crate::type_common::{AdditionalInfoValue, ParsedBytesWithValue};
 
@@ -21,12 +21,26 @@ impl TextString {
             value,
         }
     }
+
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    pub fn encoding_indicator(&self) -> Option<&'static str> {
+        self.parsed_bytes.encoding_indicator()
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+        output.extend(&self.value);
+    }
 }
 
 impl ToTree for TextString {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, context: &mut Context) -> Node {
         let comment = format!("tstr({:#x} = {})", self.value.len(), self.value.len());
-        let payload_comment = format!("{:?}", self.value.as_bstr().to_str_lossy());
+        let payload_comment = text_diagnostic(&self.value);
+        context.register_string(self.value.len(), || payload_comment.clone());
         let payload_node = Node::new(self.value.to_owned()).with_comment(payload_comment);
         self.parsed_bytes
             .into_node()
@@ -47,16 +61,32 @@ impl IndefiniteTextString {
             value,
         }
     }
+
+    pub fn chunks(&self) -> &[CborObject] {
+        &self.value
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+        for item in &self.value {
+            item.encode_into(output);
+        }
+    }
 }
 
 impl ToTree for IndefiniteTextString {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, context: &mut Context) -> Node {
         let IndefiniteTextString {
             parsed_bytes,
             value,
         } = self;
         let comment = "tstr(*)";
-        let children = value.into_iter().map(|child| child.into_tree()).collect();
+        let children = context.without_string_namespaces(|context| {
+            value
+                .into_iter()
+                .map(|child| child.into_tree_with_context(context))
+                .collect()
+        });
         parsed_bytes
             .into_node()
             .with_comment(comment)
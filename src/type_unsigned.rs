@@ -1,4 +1,5 @@
 use crate::cbor_object::ToTree;
+use crate::context::Context;
 use crate::tree::Node;
 use crate::type_common::{AdditionalInfoValue, ParsedBytesWithValue};
 
@@ -16,10 +17,25 @@ impl UnsignedInteger {
             parsed_bytes: ParsedBytesWithValue::new(bytes, more_bytes, additional_info_value),
         }
     }
+
+    pub fn value(&self) -> Option<u64> {
+        match self.parsed_bytes.additional_info_value {
+            AdditionalInfoValue::Value(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn encoding_indicator(&self) -> Option<&'static str> {
+        self.parsed_bytes.encoding_indicator()
+    }
+
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        self.parsed_bytes.encode_into(output);
+    }
 }
 
 impl ToTree for UnsignedInteger {
-    fn into_tree(self) -> Node {
+    fn into_tree_with_context(self, _context: &mut Context) -> Node {
         let comment = match self.parsed_bytes.additional_info_value {
             AdditionalInfoValue::Value(x) => format!("unsigned({:#x}) = {}", x, x),
             _ => "unsigned(?)".to_string(),
@@ -0,0 +1,227 @@
+use half::f16;
+
+use crate::cbor_object::CborObject;
+use crate::cbor_parser::{
+    MAJOR_ARRAY, MAJOR_BYTE_STRING, MAJOR_MAP, MAJOR_NEGATIVE_INTEGER, MAJOR_SIMPLE_OR_FLOAT,
+    MAJOR_TAG, MAJOR_TEXT_STRING, MAJOR_UNSIGNED_INTEGER,
+};
+
+/// A decoded data item without any encoding details, used where items are built or
+/// rewritten rather than inspected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Unsigned(u64),
+    /// `-1 - n`
+    Negative(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+    Simple(u8),
+    Float(f64),
+}
+
+pub fn encode_head(major_type: u8, argument: u64, output: &mut Vec<u8>) {
+    let major_type = major_type << 5;
+    match argument {
+        0..24 => output.push(major_type | argument as u8),
+        24..0x100 => output.extend([major_type | 24, argument as u8]),
+        0x100..0x10000 => {
+            output.push(major_type | 25);
+            output.extend((argument as u16).to_be_bytes());
+        }
+        0x10000..0x100000000 => {
+            output.push(major_type | 26);
+            output.extend((argument as u32).to_be_bytes());
+        }
+        _ => {
+            output.push(major_type | 27);
+            output.extend(argument.to_be_bytes());
+        }
+    }
+}
+
+pub fn encode_float(value: f64, output: &mut Vec<u8>) {
+    let half = f16::from_f64(value);
+    let single = value as f32;
+    if f64::from(half) == value || (value.is_nan() && half.is_nan()) {
+        output.push(0xf9);
+        output.extend(half.to_be_bytes());
+    } else if f64::from(single) == value {
+        output.push(0xfa);
+        output.extend(single.to_be_bytes());
+    } else {
+        output.push(0xfb);
+        output.extend(value.to_be_bytes());
+    }
+}
+
+impl Value {
+    /// Encodes the value with the preferred serialization (RFC 8949 Section 4.1).
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.encode_into(&mut output);
+        output
+    }
+
+    fn encode_into(&self, output: &mut Vec<u8>) {
+        match self {
+            Value::Unsigned(x) => encode_head(MAJOR_UNSIGNED_INTEGER, *x, output),
+            Value::Negative(x) => encode_head(MAJOR_NEGATIVE_INTEGER, *x, output),
+            Value::Bytes(x) => {
+                encode_head(MAJOR_BYTE_STRING, x.len() as u64, output);
+                output.extend(x);
+            }
+            Value::Text(x) => {
+                encode_head(MAJOR_TEXT_STRING, x.len() as u64, output);
+                output.extend(x.as_bytes());
+            }
+            Value::Array(x) => {
+                encode_head(MAJOR_ARRAY, x.len() as u64, output);
+                x.iter().for_each(|item| item.encode_into(output));
+            }
+            Value::Map(x) => {
+                encode_head(MAJOR_MAP, x.len() as u64, output);
+                x.iter().for_each(|(key, value)| {
+                    key.encode_into(output);
+                    value.encode_into(output);
+                });
+            }
+            Value::Tag(tag, x) => {
+                encode_head(MAJOR_TAG, *tag, output);
+                x.encode_into(output);
+            }
+            Value::Simple(x) => match x {
+                0..24 => output.push((MAJOR_SIMPLE_OR_FLOAT << 5) | x),
+                _ => output.extend([(MAJOR_SIMPLE_OR_FLOAT << 5) | 24, *x]),
+            },
+            Value::Float(x) => encode_float(*x, output),
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            Value::Unsigned(x) => Some(i128::from(*x)),
+            Value::Negative(x) => Some(-1 - i128::from(*x)),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Value::Text(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the entry with the text key `label` of a map.
+    pub fn get_text(&self, label: &str) -> Option<&Value> {
+        let Value::Map(pairs) = self else {
+            return None;
+        };
+        pairs
+            .iter()
+            .find(|(key, _)| key.as_text() == Some(label))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the value of the entry with the integer key `label` of a map.
+    pub fn get(&self, label: i64) -> Option<&Value> {
+        let Value::Map(pairs) = self else {
+            return None;
+        };
+        pairs
+            .iter()
+            .find(|(key, _)| key.as_integer() == Some(i128::from(label)))
+            .map(|(_, value)| value)
+    }
+
+    /// Converts a parsed item, joining indefinite-length items into definite-length ones.
+    pub fn from_object(object: &CborObject) -> Result<Value, String> {
+        Ok(match object {
+            CborObject::UnsignedInteger(x) => {
+                Value::Unsigned(x.value().ok_or("reserved additional information")?)
+            }
+            CborObject::NegativeInteger(x) => {
+                Value::Negative(x.value().ok_or("reserved additional information")?)
+            }
+            CborObject::ByteString(_)
+            | CborObject::ByteStringWithEmbedded(_)
+            | CborObject::IndefiniteByteString(_) => {
+                Value::Bytes(object.as_bytes().ok_or("invalid byte string chunk")?)
+            }
+            CborObject::TextString(_) | CborObject::IndefiniteTextString(_) => {
+                Value::Text(object.as_text().ok_or("invalid text string chunk")?)
+            }
+            CborObject::Array(_) => Value::Array(
+                // unwrap safety: object is an array
+                object
+                    .as_array()
+                    .unwrap()
+                    .into_iter()
+                    .map(Value::from_object)
+                    .collect::<Result<_, _>>()?,
+            ),
+            CborObject::Map(_) => Value::Map(
+                // unwrap safety: object is a map
+                object
+                    .as_map()
+                    .unwrap()
+                    .into_iter()
+                    .map(|(key, value)| Ok((Value::from_object(key)?, Value::from_object(value)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            CborObject::Tag(x) => Value::Tag(
+                x.tag().ok_or("reserved additional information")?,
+                Box::new(Value::from_object(x.payload())?),
+            ),
+            CborObject::SimpleValue(x) => Value::Simple(x.value()),
+            CborObject::HalfPrecisionFloat(x) => Value::Float(x.value().into()),
+            CborObject::SinglePrecisionFloat(x) => Value::Float(x.value().into()),
+            CborObject::DoublePrecisionFloat(x) => Value::Float(x.value()),
+            CborObject::ReservedSimpleOrFloat(_) => return Err("reserved simple/float".to_string()),
+            CborObject::Break(_) => return Err("unexpected break".to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+
+    #[test]
+    fn encode() {
+        assert_eq!(Value::Unsigned(23).encode(), vec![0x17]);
+        assert_eq!(Value::Unsigned(24).encode(), vec![0x18, 0x18]);
+        assert_eq!(Value::Negative(499).encode(), vec![0x39, 0x01, 0xf3]);
+        assert_eq!(
+            Value::Array(vec![Value::Text("a".to_string()), Value::Bytes(vec![1])]).encode(),
+            vec![0x82, 0x61, 0x61, 0x41, 0x01]
+        );
+        assert_eq!(
+            Value::Tag(
+                1,
+                Box::new(Value::Map(vec![(Value::Simple(20), Value::Simple(255))]))
+            )
+            .encode(),
+            vec![0xc1, 0xa1, 0xf4, 0xf8, 0xff]
+        );
+        assert_eq!(Value::Float(1.5).encode(), vec![0xf9, 0x3e, 0x00]);
+        assert_eq!(
+            Value::Float(100000.0).encode(),
+            vec![0xfa, 0x47, 0xc3, 0x50, 0x00]
+        );
+        assert_eq!(
+            Value::Float(1.1).encode(),
+            vec![0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a]
+        );
+    }
+}
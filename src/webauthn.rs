@@ -0,0 +1,297 @@
+//! WebAuthn attestation objects and the binary layout of authenticator data (Web
+//! Authentication Level 3, Sections 6.1 and 6.5).
+
+use crate::cbor_object::CborObject;
+use crate::cbor_parser::parse_cbor;
+use crate::context::Context;
+use crate::cose::ALGORITHMS;
+use crate::cose_key;
+use crate::schema::{self, Entry, Schema};
+use crate::tag_identifier;
+use crate::tree::Node;
+use crate::value::Value;
+
+const FLAG_AT: u8 = 0x40;
+const FLAG_ED: u8 = 0x80;
+
+/// Bits of the flags of authenticator data. Bits 1 and 5 are reserved.
+const FLAGS: &[(u8, &str)] = &[
+    (0x01, "UP"),
+    (0x04, "UV"),
+    (0x08, "BE"),
+    (0x10, "BS"),
+    (FLAG_AT, "AT"),
+    (FLAG_ED, "ED"),
+];
+
+const CRED_PROTECT: &[(i64, &str)] = &[
+    (1, "userVerificationOptional"),
+    (2, "userVerificationOptionalWithCredentialIDList"),
+    (3, "userVerificationRequired"),
+];
+
+/// Authenticator extension outputs of CTAP 2.1 Section 12.
+const EXTENSIONS: Schema = Schema::Map(&[
+    Entry::text(
+        "credProtect",
+        "credential protection policy",
+        Schema::Values(CRED_PROTECT),
+    ),
+    Entry::text("hmac-secret", "hmac-secret support", Schema::Any),
+    Entry::text("credBlob", "credBlob stored", Schema::Any),
+    Entry::text("largeBlobKey", "large blob key", Schema::Opaque),
+    Entry::text("minPinLength", "minimum PIN length", Schema::Any),
+]);
+
+/// Removes the next `length` bytes of a field from `rest`.
+fn take<'a>(rest: &mut &'a [u8], length: usize, name: &str) -> Result<&'a [u8], String> {
+    if rest.len() < length {
+        return Err(format!("truncated {}", name));
+    }
+    let (field, tail) = rest.split_at(length);
+    *rest = tail;
+    Ok(field)
+}
+
+/// Builds the CBOR item at the start of `rest`, removing it.
+fn take_item(
+    rest: &mut &[u8],
+    name: &'static str,
+    schema: Schema,
+    context: &mut Context,
+) -> Result<Node, String> {
+    let Ok((tail, object)) = parse_cbor(rest) else {
+        return Err(format!("invalid {}", name));
+    };
+    *rest = tail;
+    Ok(context.without_string_namespaces(|context| {
+        schema::child_into_tree(object, Some(name), schema, context)
+    }))
+}
+
+fn auth_data_fields(bytes: &[u8], context: &mut Context) -> Result<Vec<Node>, String> {
+    let mut rest = bytes;
+    let mut fields =
+        vec![Node::new(take(&mut rest, 32, "rpIdHash")?.to_vec()).with_comment("rpIdHash")];
+
+    let flags = take(&mut rest, 1, "flags")?[0];
+    let set_flags: Vec<&str> = FLAGS
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    let mut flags_node = Node::new(vec![flags]).with_comment(format!("flags = {:#04x}", flags));
+    if !set_flags.is_empty() {
+        flags_node = flags_node.with_annotation(set_flags.join(", "));
+    }
+    fields.push(flags_node);
+
+    let sign_count = take(&mut rest, 4, "signCount")?;
+    // unwrap safety: `take` returns 4 bytes
+    let count = u32::from_be_bytes(sign_count.try_into().unwrap());
+    fields.push(Node::new(sign_count.to_vec()).with_comment(format!("signCount = {}", count)));
+
+    if flags & FLAG_AT != 0 {
+        let aaguid = take(&mut rest, 16, "aaguid")?;
+        // unwrap safety: `take` returns 16 bytes
+        let uuid = tag_identifier::format_uuid(aaguid.try_into().unwrap());
+        fields.push(Node::new(aaguid.to_vec()).with_comment(format!("aaguid = {}", uuid)));
+        let length = take(&mut rest, 2, "credentialIdLength")?;
+        let length_value = u16::from_be_bytes([length[0], length[1]]);
+        fields.push(
+            Node::new(length.to_vec())
+                .with_comment(format!("credentialIdLength = {}", length_value)),
+        );
+        let credential_id = take(&mut rest, usize::from(length_value), "credentialId")?;
+        fields.push(Node::new(credential_id.to_vec()).with_comment("credentialId"));
+        fields.push(take_item(
+            &mut rest,
+            "credentialPublicKey",
+            cose_key::COSE_KEY,
+            context,
+        )?);
+    }
+    if flags & FLAG_ED != 0 {
+        fields.push(take_item(&mut rest, "extensions", EXTENSIONS, context)?);
+    }
+    if !rest.is_empty() {
+        return Err(format!("{} trailing bytes", rest.len()));
+    }
+    Ok(fields)
+}
+
+/// Splits authenticator data into its fields.
+fn auth_data(bytes: &[u8], context: &mut Context) -> Result<Vec<Node>, String> {
+    auth_data_fields(bytes, context).map_err(|err| format!("invalid authenticator data: {}", err))
+}
+
+pub const AUTH_DATA: Schema = Schema::Layout(auth_data);
+
+const ALG: Entry = Entry::text("alg", "algorithm", Schema::Values(ALGORITHMS));
+const SIG: Entry = Entry::text("sig", "attestation signature", Schema::Opaque);
+const X5C: Entry = Entry::text(
+    "x5c",
+    "attestation certificate chain",
+    Schema::ArrayOf(&Schema::Opaque),
+);
+
+/// Attestation statements of the formats of Sections 8.2 to 8.8.
+const PACKED: Schema = Schema::Map(&[
+    ALG,
+    SIG,
+    X5C,
+    Entry::text("ecdaaKeyId", "ECDAA key id", Schema::Opaque),
+]);
+const TPM: Schema = Schema::Map(&[
+    Entry::text("ver", "TPM specification version", Schema::Any),
+    ALG,
+    X5C,
+    SIG,
+    Entry::text("certInfo", "TPMS_ATTEST", Schema::Opaque),
+    Entry::text("pubArea", "TPMT_PUBLIC", Schema::Opaque),
+]);
+const ANDROID_KEY: Schema = Schema::Map(&[ALG, SIG, X5C]);
+const ANDROID_SAFETYNET: Schema = Schema::Map(&[
+    Entry::text("ver", "Google Play Services version", Schema::Any),
+    Entry::text("response", "SafetyNet response", Schema::Opaque),
+]);
+const FIDO_U2F: Schema = Schema::Map(&[SIG, X5C]);
+const APPLE: Schema = Schema::Map(&[X5C]);
+
+/// The members of an attestation object, which an authenticatorMakeCredential response
+/// (CTAP 2.1 Section 6.1.2) has under integer keys, along with a few more.
+const fn attestation_object(statement: Schema) -> [Entry; 8] {
+    [
+        Entry::text("fmt", "attestation statement format", Schema::Any),
+        Entry::text("attStmt", "attestation statement", statement),
+        Entry::text("authData", "authenticator data", AUTH_DATA),
+        Entry::int(1, "attestation statement format", Schema::Any),
+        Entry::int(2, "authenticator data", AUTH_DATA),
+        Entry::int(3, "attestation statement", statement),
+        Entry::int(4, "enterprise attestation", Schema::Any),
+        Entry::int(5, "large blob key", Schema::Opaque),
+    ]
+}
+
+/// Attestation objects by the format of their statement.
+const FORMATS: &[(&str, Schema)] = &[
+    ("packed", Schema::Map(&attestation_object(PACKED))),
+    ("tpm", Schema::Map(&attestation_object(TPM))),
+    ("android-key", Schema::Map(&attestation_object(ANDROID_KEY))),
+    (
+        "android-safetynet",
+        Schema::Map(&attestation_object(ANDROID_SAFETYNET)),
+    ),
+    ("fido-u2f", Schema::Map(&attestation_object(FIDO_U2F))),
+    ("apple", Schema::Map(&attestation_object(APPLE))),
+    ("none", Schema::Map(&attestation_object(Schema::Map(&[])))),
+];
+const UNKNOWN_FORMAT: Schema = Schema::Map(&attestation_object(Schema::Any));
+
+/// Names the members of an attestation object and describes its attestation format.
+fn select_attestation_object(object: &CborObject, _: &Context) -> (Schema, Option<String>) {
+    let value = Value::from_object(object);
+    let fmt = match &value {
+        Ok(value) => value
+            .get_text("fmt")
+            .or_else(|| value.get(1))
+            .and_then(Value::as_text),
+        Err(_) => None,
+    };
+    let Some(fmt) = fmt else {
+        return (
+            UNKNOWN_FORMAT,
+            Some("invalid attestation object: missing fmt".to_string()),
+        );
+    };
+    match FORMATS.iter().find(|(name, _)| *name == fmt) {
+        Some((_, schema)) => (*schema, Some(format!("{} attestation", fmt))),
+        None => (
+            UNKNOWN_FORMAT,
+            Some(format!("unknown attestation format {:?}", fmt)),
+        ),
+    }
+}
+
+pub const ATTESTATION_OBJECT: Schema = Schema::Select(select_attestation_object);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth_data_bytes(flags: u8, rest: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0x11; 32];
+        bytes.push(flags);
+        bytes.extend([0, 0, 1, 0]);
+        bytes.extend(rest);
+        bytes
+    }
+
+    #[test]
+    fn auth_data_without_credential() {
+        let bytes = auth_data_bytes(0x05, &[]);
+        let expected = vec![
+            Node::new(vec![0x11; 32]).with_comment("rpIdHash"),
+            Node::new(vec![0x05])
+                .with_comment("flags = 0x05")
+                .with_annotation("UP, UV"),
+            Node::new(vec![0, 0, 1, 0]).with_comment("signCount = 256"),
+        ];
+        assert_eq!(auth_data(&bytes, &mut Context::default()), Ok(expected));
+    }
+
+    #[test]
+    fn auth_data_with_extensions() {
+        // {"hmac-secret": true}
+        let bytes = auth_data_bytes(0x80, b"\xa1\x6bhmac-secret\xf5");
+        let fields = auth_data(&bytes, &mut Context::default()).unwrap();
+        assert_eq!(fields.len(), 4);
+        let expected = Node::new(vec![0xa1])
+            .with_comment("map(0x1 = 1)")
+            .with_annotation("extensions")
+            .with_children(vec![
+                Node::new(vec![0x6b])
+                    .with_comment("tstr(0xb = 11)")
+                    .with_annotation("hmac-secret support")
+                    .with_child(Node::new(b"hmac-secret".to_vec()).with_comment("\"hmac-secret\"")),
+                Node::new(vec![0xf5]).with_comment("simple(0x15 = 21) = true"),
+            ]);
+        assert_eq!(fields[3], expected);
+    }
+
+    #[test]
+    fn invalid_auth_data() {
+        let mut context = Context::default();
+        assert_eq!(
+            auth_data(&[0; 36], &mut context),
+            Err("invalid authenticator data: truncated signCount".to_string())
+        );
+        assert_eq!(
+            auth_data(&auth_data_bytes(0x41, &[0; 17]), &mut context),
+            Err("invalid authenticator data: truncated credentialIdLength".to_string())
+        );
+        assert_eq!(
+            auth_data(&auth_data_bytes(0x01, &[0]), &mut context),
+            Err("invalid authenticator data: 1 trailing bytes".to_string())
+        );
+    }
+
+    #[test]
+    fn attestation_formats() {
+        let object = |fmt: &str| {
+            let mut bytes = b"\xa3\x63fmt".to_vec();
+            bytes.push(0x60 + fmt.len() as u8);
+            bytes.extend(fmt.as_bytes());
+            bytes.extend(b"\x67attStmt\xa0\x68authData\x40");
+            parse_cbor(&bytes).unwrap().1
+        };
+        let context = Context::default();
+        let describe = |fmt| select_attestation_object(&object(fmt), &context).1;
+        assert_eq!(describe("none"), Some("none attestation".to_string()));
+        assert_eq!(describe("tpm"), Some("tpm attestation".to_string()));
+        assert_eq!(
+            describe("x"),
+            Some("unknown attestation format \"x\"".to_string())
+        );
+    }
+}